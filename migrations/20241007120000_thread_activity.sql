@@ -0,0 +1,58 @@
+-- Track thread activity for sorting
+
+ALTER TABLE threads
+ADD COLUMN IF NOT EXISTS last_message_id BIGINT,
+ADD COLUMN IF NOT EXISTS last_activity_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+ADD COLUMN IF NOT EXISTS message_count INTEGER NOT NULL DEFAULT 0;
+
+UPDATE threads t SET
+	last_message_id = (SELECT MAX(m.id) FROM messages m WHERE m.thread_id = t.id),
+	message_count = (SELECT COUNT(*) FROM messages m WHERE m.thread_id = t.id);
+
+CREATE INDEX IF NOT EXISTS threads_category_id_idx ON threads(category_id);
+
+-- Thread is inserted after its original message, so it has to count already saved messages
+CREATE OR REPLACE FUNCTION thread_activity_init() RETURNS TRIGGER AS $$
+    BEGIN
+        NEW.last_message_id = (SELECT MAX(id) FROM messages WHERE thread_id = NEW.id);
+        NEW.message_count = (SELECT COUNT(*) FROM messages WHERE thread_id = NEW.id);
+        NEW.last_activity_at = CURRENT_TIMESTAMP;
+        RETURN NEW;
+    END;
+$$ LANGUAGE 'plpgsql';
+
+CREATE OR REPLACE FUNCTION thread_activity_refresh(thread BIGINT) RETURNS VOID AS $$
+    BEGIN
+        UPDATE threads SET
+            last_message_id = (SELECT MAX(id) FROM messages WHERE thread_id = thread),
+            message_count = (SELECT COUNT(*) FROM messages WHERE thread_id = thread)
+        WHERE id = thread;
+    END;
+$$ LANGUAGE 'plpgsql';
+
+CREATE OR REPLACE FUNCTION thread_activity_column() RETURNS TRIGGER AS $$
+    BEGIN
+        IF TG_OP = 'INSERT' THEN
+            UPDATE threads SET
+                last_message_id = GREATEST(last_message_id, NEW.id),
+                message_count = message_count + 1,
+                last_activity_at = CURRENT_TIMESTAMP
+            WHERE id = NEW.thread_id;
+        ELSIF TG_OP = 'UPDATE' THEN
+            IF OLD.thread_id <> NEW.thread_id THEN
+                PERFORM thread_activity_refresh(OLD.thread_id);
+                PERFORM thread_activity_refresh(NEW.thread_id);
+            END IF;
+            -- Only edits of the content count as activity, flag changes by moderators don't
+            IF OLD.content IS DISTINCT FROM NEW.content THEN
+                UPDATE threads SET last_activity_at = CURRENT_TIMESTAMP WHERE id = NEW.thread_id;
+            END IF;
+        ELSE
+            PERFORM thread_activity_refresh(OLD.thread_id);
+        END IF;
+        RETURN NULL;
+    END;
+$$ LANGUAGE 'plpgsql';
+
+CREATE OR REPLACE TRIGGER thread_activity_threads BEFORE INSERT ON threads FOR EACH ROW EXECUTE PROCEDURE thread_activity_init();
+CREATE OR REPLACE TRIGGER thread_activity_messages AFTER INSERT OR UPDATE OR DELETE ON messages FOR EACH ROW EXECUTE PROCEDURE thread_activity_column();
@@ -0,0 +1,4 @@
+-- Only content changes mark message as edited (moving messages between threads doesn't)
+
+CREATE OR REPLACE TRIGGER updated_at_users BEFORE UPDATE ON messages FOR EACH ROW
+WHEN (OLD.content IS DISTINCT FROM NEW.content) EXECUTE PROCEDURE updated_at_column();
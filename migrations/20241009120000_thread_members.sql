@@ -0,0 +1,10 @@
+-- Add thread types and thread members
+
+ALTER TABLE threads
+ADD COLUMN IF NOT EXISTS type INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS thread_members (
+	thread_id BIGINT NOT NULL REFERENCES threads(id) ON DELETE CASCADE,
+	user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	PRIMARY KEY (thread_id, user_id)
+);
@@ -0,0 +1,16 @@
+-- Add requirements to join threads
+
+ALTER TABLE threads
+ADD COLUMN IF NOT EXISTS min_account_age BIGINT,
+ADD COLUMN IF NOT EXISTS min_message_count INTEGER,
+ADD COLUMN IF NOT EXISTS required_permissions BIGINT,
+ADD COLUMN IF NOT EXISTS join_question VARCHAR(512);
+
+CREATE TABLE IF NOT EXISTS thread_join_requests (
+	thread_id BIGINT NOT NULL REFERENCES threads(id) ON DELETE CASCADE,
+	user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	answer VARCHAR(2048) NOT NULL,
+	status INTEGER NOT NULL DEFAULT 0,
+	reviewer_id BIGINT REFERENCES users(id) ON DELETE SET NULL,
+	PRIMARY KEY (thread_id, user_id)
+);
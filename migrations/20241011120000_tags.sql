@@ -0,0 +1,18 @@
+-- Add category tags
+
+CREATE TABLE IF NOT EXISTS tags (
+	id BIGINT PRIMARY KEY NOT NULL UNIQUE,
+	category_id BIGINT NOT NULL REFERENCES categories(id) ON DELETE CASCADE,
+	name VARCHAR(32) NOT NULL,
+	color INTEGER NOT NULL DEFAULT 0,
+	moderated BOOLEAN NOT NULL DEFAULT FALSE,
+	UNIQUE (category_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS thread_tags (
+	thread_id BIGINT NOT NULL REFERENCES threads(id) ON DELETE CASCADE,
+	tag_id BIGINT NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+	PRIMARY KEY (thread_id, tag_id)
+);
+
+CREATE INDEX IF NOT EXISTS thread_tags_tag_id_idx ON thread_tags(tag_id);
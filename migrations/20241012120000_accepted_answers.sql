@@ -0,0 +1,18 @@
+-- Add question categories with accepted answers
+
+ALTER TABLE categories
+ADD COLUMN IF NOT EXISTS question_mode BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE threads
+ADD COLUMN IF NOT EXISTS accepted_answer_id BIGINT REFERENCES messages(id) ON DELETE SET NULL;
+
+-- Accepted answer moved to another thread no longer answers the thread
+CREATE OR REPLACE FUNCTION accepted_answer_column() RETURNS TRIGGER AS $$
+    BEGIN
+        UPDATE threads SET accepted_answer_id = NULL WHERE accepted_answer_id = NEW.id AND id <> NEW.thread_id;
+        RETURN NULL;
+    END;
+$$ LANGUAGE 'plpgsql';
+
+CREATE OR REPLACE TRIGGER accepted_answer_messages AFTER UPDATE ON messages FOR EACH ROW
+WHEN (OLD.thread_id IS DISTINCT FROM NEW.thread_id) EXECUTE PROCEDURE accepted_answer_column();
@@ -0,0 +1,33 @@
+-- Add polls attached to threads
+
+CREATE TABLE IF NOT EXISTS polls (
+	thread_id BIGINT PRIMARY KEY NOT NULL REFERENCES threads(id) ON DELETE CASCADE,
+	question VARCHAR(300) NOT NULL,
+	multiple_choice BOOLEAN NOT NULL DEFAULT FALSE,
+	anonymous BOOLEAN NOT NULL DEFAULT FALSE,
+	closes_at TIMESTAMPTZ,
+	closed BOOLEAN NOT NULL DEFAULT FALSE,
+	UNIQUE (thread_id, multiple_choice)
+);
+
+CREATE TABLE IF NOT EXISTS poll_options (
+	id BIGINT PRIMARY KEY NOT NULL UNIQUE,
+	thread_id BIGINT NOT NULL REFERENCES polls(thread_id) ON DELETE CASCADE,
+	text VARCHAR(100) NOT NULL,
+	position INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS poll_votes (
+	thread_id BIGINT NOT NULL REFERENCES polls(thread_id) ON DELETE CASCADE,
+	option_id BIGINT NOT NULL REFERENCES poll_options(id) ON DELETE CASCADE,
+	user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	multiple_choice BOOLEAN NOT NULL,
+	PRIMARY KEY (option_id, user_id),
+	FOREIGN KEY (thread_id, multiple_choice) REFERENCES polls(thread_id, multiple_choice) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS poll_options_thread_id_idx ON poll_options(thread_id);
+CREATE INDEX IF NOT EXISTS poll_votes_thread_id_user_id_idx ON poll_votes(thread_id, user_id);
+-- Users have a single vote in single choice polls, whatever requests race
+CREATE UNIQUE INDEX IF NOT EXISTS poll_votes_single_choice_idx ON poll_votes(thread_id, user_id) WHERE NOT multiple_choice;
+CREATE INDEX IF NOT EXISTS polls_closes_at_idx ON polls(closes_at) WHERE NOT closed;
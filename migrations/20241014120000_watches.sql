@@ -0,0 +1,15 @@
+-- Add watch levels of threads and categories
+
+CREATE TABLE IF NOT EXISTS thread_watches (
+	thread_id BIGINT NOT NULL REFERENCES threads(id) ON DELETE CASCADE,
+	user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	level INTEGER NOT NULL DEFAULT 0,
+	PRIMARY KEY (thread_id, user_id)
+);
+
+CREATE TABLE IF NOT EXISTS category_watches (
+	category_id BIGINT NOT NULL REFERENCES categories(id) ON DELETE CASCADE,
+	user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	level INTEGER NOT NULL DEFAULT 0,
+	PRIMARY KEY (category_id, user_id)
+);
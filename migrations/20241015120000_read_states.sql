@@ -0,0 +1,8 @@
+-- Add per-user read state of threads
+
+CREATE TABLE IF NOT EXISTS read_states (
+	thread_id BIGINT NOT NULL REFERENCES threads(id) ON DELETE CASCADE,
+	user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	last_read_message_id BIGINT NOT NULL,
+	PRIMARY KEY (user_id, thread_id)
+);
@@ -0,0 +1,45 @@
+-- Add message reactions
+
+CREATE TABLE IF NOT EXISTS message_reactions (
+	message_id BIGINT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+	user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	-- References the custom emoji once they can be uploaded
+	emoji_id BIGINT,
+	emoji_name VARCHAR(64) NOT NULL,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Unicode emoji have no ID
+CREATE UNIQUE INDEX IF NOT EXISTS message_reactions_unique_idx ON message_reactions(message_id, emoji_name, COALESCE(emoji_id, 0), user_id);
+
+-- Messages can have up to 20 distinct reactions, the message is locked so concurrent reactions are counted one by one
+CREATE OR REPLACE FUNCTION check_message_reactions_limit() RETURNS TRIGGER AS $$
+    BEGIN
+        PERFORM 1 FROM messages WHERE id = NEW.message_id FOR NO KEY UPDATE;
+        IF NOT EXISTS (
+            SELECT 1 FROM message_reactions WHERE message_id = NEW.message_id
+            AND COALESCE(emoji_id::TEXT, emoji_name) = COALESCE(NEW.emoji_id::TEXT, NEW.emoji_name)
+        ) AND (
+            SELECT COUNT(DISTINCT COALESCE(emoji_id::TEXT, emoji_name)) FROM message_reactions WHERE message_id = NEW.message_id
+        ) >= 20 THEN
+            RAISE EXCEPTION 'Message can''t have more than 20 distinct reactions'
+                USING ERRCODE = 'check_violation', CONSTRAINT = 'message_reactions_limit';
+        END IF;
+        RETURN NEW;
+    END;
+$$ LANGUAGE 'plpgsql';
+
+CREATE OR REPLACE TRIGGER message_reactions_limit BEFORE INSERT ON message_reactions
+FOR EACH ROW EXECUTE PROCEDURE check_message_reactions_limit();
+
+-- Reactions of the message aggregated by emoji in order they were first added
+CREATE OR REPLACE FUNCTION message_reactions_json(message BIGINT) RETURNS JSON AS $$
+    SELECT COALESCE(JSON_AGG(JSON_BUILD_OBJECT(
+        'emoji', JSON_BUILD_OBJECT('id', emoji_id::TEXT, 'name', emoji_name),
+        'count', count
+    ) ORDER BY first_created_at), '[]')
+    FROM (
+        SELECT emoji_id, emoji_name, COUNT(*) AS count, MIN(created_at) AS first_created_at
+        FROM message_reactions WHERE message_id = message GROUP BY emoji_id, emoji_name
+    ) r
+$$ LANGUAGE sql STABLE;
@@ -0,0 +1,29 @@
+-- Add message attachments
+
+CREATE TABLE IF NOT EXISTS attachments (
+	id BIGINT NOT NULL PRIMARY KEY,
+	message_id BIGINT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+	filename VARCHAR(255) NOT NULL,
+	content_type VARCHAR(255) NOT NULL,
+	size BIGINT NOT NULL,
+	width INTEGER,
+	height INTEGER,
+	hash CHAR(64) NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS attachments_message_id_idx ON attachments(message_id);
+
+-- Attachments of the message in order they were uploaded
+CREATE OR REPLACE FUNCTION message_attachments_json(message BIGINT) RETURNS JSON AS $$
+    SELECT COALESCE(JSON_AGG(JSON_BUILD_OBJECT(
+        'id', id::TEXT,
+        'message_id', message_id::TEXT,
+        'filename', filename,
+        'content_type', content_type,
+        'size', size,
+        'width', width,
+        'height', height,
+        'hash', hash
+    ) ORDER BY id), '[]')
+    FROM attachments WHERE message_id = message
+$$ LANGUAGE sql STABLE;
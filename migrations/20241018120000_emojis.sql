@@ -0,0 +1,21 @@
+-- Add custom emoji
+
+CREATE TABLE IF NOT EXISTS emojis (
+	id BIGINT NOT NULL PRIMARY KEY,
+	name VARCHAR(32) NOT NULL UNIQUE,
+	content_type VARCHAR(32) NOT NULL,
+	creator_id BIGINT REFERENCES users(id) ON DELETE SET NULL,
+	required_permissions BIGINT
+);
+
+ALTER TABLE message_reactions
+ADD CONSTRAINT fk_message_reactions_emoji
+FOREIGN KEY (emoji_id) REFERENCES emojis(id) ON DELETE CASCADE;
+
+-- Custom emoji used in the content with <:name:id> syntax, IDs are compared as numbers to avoid BIGINT overflow
+CREATE OR REPLACE FUNCTION message_emojis_json(content TEXT) RETURNS JSON AS $$
+    SELECT COALESCE(JSON_AGG(JSON_BUILD_OBJECT('id', e.id::TEXT, 'name', e.name) ORDER BY e.id), '[]')
+    FROM emojis e WHERE e.id::NUMERIC IN (
+        SELECT match[1]::NUMERIC FROM REGEXP_MATCHES(content, '<:[A-Za-z0-9_]{2,32}:(\d{1,19})>', 'g') AS match
+    )
+$$ LANGUAGE sql STABLE;
@@ -0,0 +1,23 @@
+-- Keep previous contents of edited messages
+
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS edit_count INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS message_revisions (
+	message_id BIGINT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+	revision INTEGER NOT NULL,
+	content VARCHAR(4096) NOT NULL,
+	edited_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	PRIMARY KEY (message_id, revision)
+);
+
+-- Store the replaced content as the next revision, so no edit is lost whatever updates the message
+CREATE OR REPLACE FUNCTION message_revision() RETURNS TRIGGER AS $$
+    BEGIN
+        NEW.edit_count = OLD.edit_count + 1;
+        INSERT INTO message_revisions(message_id, revision, content) VALUES (OLD.id, NEW.edit_count, OLD.content);
+        RETURN NEW;
+    END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER message_revisions BEFORE UPDATE ON messages FOR EACH ROW
+WHEN (OLD.content IS DISTINCT FROM NEW.content) EXECUTE PROCEDURE message_revision();
@@ -0,0 +1,58 @@
+-- Keep deleted messages and threads until they are purged after the retention period
+
+ALTER TABLE messages
+ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ,
+ADD COLUMN IF NOT EXISTS deleted_by BIGINT REFERENCES users(id) ON DELETE SET NULL;
+
+ALTER TABLE threads
+ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ,
+ADD COLUMN IF NOT EXISTS deleted_by BIGINT REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS messages_deleted_at_idx ON messages(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS threads_deleted_at_idx ON threads(deleted_at) WHERE deleted_at IS NOT NULL;
+
+-- Deleted messages don't count towards thread activity
+CREATE OR REPLACE FUNCTION thread_activity_init() RETURNS TRIGGER AS $$
+    BEGIN
+        NEW.last_message_id = (SELECT MAX(id) FROM messages WHERE thread_id = NEW.id AND deleted_at IS NULL);
+        NEW.message_count = (SELECT COUNT(*) FROM messages WHERE thread_id = NEW.id AND deleted_at IS NULL);
+        NEW.last_activity_at = CURRENT_TIMESTAMP;
+        RETURN NEW;
+    END;
+$$ LANGUAGE 'plpgsql';
+
+CREATE OR REPLACE FUNCTION thread_activity_refresh(thread BIGINT) RETURNS VOID AS $$
+    BEGIN
+        UPDATE threads SET
+            last_message_id = (SELECT MAX(id) FROM messages WHERE thread_id = thread AND deleted_at IS NULL),
+            message_count = (SELECT COUNT(*) FROM messages WHERE thread_id = thread AND deleted_at IS NULL)
+        WHERE id = thread;
+    END;
+$$ LANGUAGE 'plpgsql';
+
+CREATE OR REPLACE FUNCTION thread_activity_column() RETURNS TRIGGER AS $$
+    BEGIN
+        IF TG_OP = 'INSERT' THEN
+            UPDATE threads SET
+                last_message_id = GREATEST(last_message_id, NEW.id),
+                message_count = message_count + 1,
+                last_activity_at = CURRENT_TIMESTAMP
+            WHERE id = NEW.thread_id;
+        ELSIF TG_OP = 'UPDATE' THEN
+            IF OLD.thread_id <> NEW.thread_id THEN
+                PERFORM thread_activity_refresh(OLD.thread_id);
+                PERFORM thread_activity_refresh(NEW.thread_id);
+            ELSIF OLD.deleted_at IS DISTINCT FROM NEW.deleted_at THEN
+                PERFORM thread_activity_refresh(NEW.thread_id);
+                RETURN NULL;
+            END IF;
+            -- Only edits of the content count as activity, flag changes by moderators don't
+            IF OLD.content IS DISTINCT FROM NEW.content THEN
+                UPDATE threads SET last_activity_at = CURRENT_TIMESTAMP WHERE id = NEW.thread_id;
+            END IF;
+        ELSE
+            PERFORM thread_activity_refresh(OLD.thread_id);
+        END IF;
+        RETURN NULL;
+    END;
+$$ LANGUAGE 'plpgsql';
@@ -0,0 +1,3 @@
+-- Find messages of a user, e.g. to purge them
+
+CREATE INDEX IF NOT EXISTS messages_author_id_idx ON messages(author_id);
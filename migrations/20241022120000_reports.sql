@@ -0,0 +1,17 @@
+-- Add message reports
+
+CREATE TABLE IF NOT EXISTS reports (
+	id BIGINT NOT NULL PRIMARY KEY,
+	message_id BIGINT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+	reporter_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	reason INTEGER NOT NULL,
+	details VARCHAR(1024),
+	status INTEGER NOT NULL DEFAULT 0,
+	assignee_id BIGINT REFERENCES users(id) ON DELETE SET NULL,
+	resolution_note VARCHAR(1024),
+	resolved_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+	resolved_at TIMESTAMPTZ,
+	UNIQUE (message_id, reporter_id)
+);
+
+CREATE INDEX IF NOT EXISTS reports_status_idx ON reports(status);
@@ -0,0 +1,22 @@
+-- Add resolved mentions of messages
+
+CREATE TABLE IF NOT EXISTS message_mentions (
+	message_id BIGINT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+	user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+	PRIMARY KEY (message_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS message_mentions_user_id_idx ON message_mentions(user_id);
+
+-- Mentions by ID were only written in the content before
+INSERT INTO message_mentions(message_id, user_id)
+SELECT DISTINCT m.id, u.id FROM messages m
+CROSS JOIN LATERAL REGEXP_MATCHES(m.content, '<@(\d{1,19})>', 'g') AS match
+JOIN users u ON u.id = match[1]::NUMERIC
+ON CONFLICT DO NOTHING;
+
+CREATE OR REPLACE FUNCTION message_mentions_json(message BIGINT) RETURNS JSON AS $$
+    SELECT COALESCE(JSON_AGG(ROW_TO_JSON(u.*) ORDER BY u.id), '[]')
+    FROM message_mentions mm JOIN users u ON mm.user_id = u.id WHERE mm.message_id = message
+$$ LANGUAGE sql STABLE;
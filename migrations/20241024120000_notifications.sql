@@ -0,0 +1,22 @@
+-- Add notifications inbox
+
+CREATE TABLE IF NOT EXISTS notifications (
+	id BIGINT NOT NULL PRIMARY KEY,
+	user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	type INTEGER NOT NULL,
+	actor_id BIGINT REFERENCES users(id) ON DELETE SET NULL,
+	thread_id BIGINT REFERENCES threads(id) ON DELETE CASCADE,
+	message_id BIGINT REFERENCES messages(id) ON DELETE CASCADE,
+	emoji_id BIGINT,
+	emoji_name VARCHAR(64),
+	read BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX IF NOT EXISTS notifications_user_id_idx ON notifications(user_id, id);
+
+CREATE TABLE IF NOT EXISTS notification_settings (
+	user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	type INTEGER NOT NULL,
+	enabled BOOLEAN NOT NULL,
+	PRIMARY KEY (user_id, type)
+);
@@ -0,0 +1,5 @@
+-- Add full-text search indexes
+
+CREATE INDEX IF NOT EXISTS messages_content_search_idx ON messages USING GIN (to_tsvector('english', content));
+
+CREATE INDEX IF NOT EXISTS threads_title_search_idx ON threads USING GIN (to_tsvector('english', title));
@@ -0,0 +1,17 @@
+-- Add private bookmarks on threads and messages
+
+CREATE TABLE IF NOT EXISTS bookmarks (
+	id BIGINT NOT NULL PRIMARY KEY,
+	user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	thread_id BIGINT REFERENCES threads(id) ON DELETE CASCADE,
+	message_id BIGINT REFERENCES messages(id) ON DELETE CASCADE,
+	folder VARCHAR(64),
+	note VARCHAR(512),
+	CHECK ((thread_id IS NULL) <> (message_id IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS bookmarks_user_id_idx ON bookmarks(user_id, id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS bookmarks_thread_id_idx ON bookmarks(user_id, thread_id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS bookmarks_message_id_idx ON bookmarks(user_id, message_id);
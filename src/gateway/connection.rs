@@ -12,7 +12,7 @@ use {
         App,
         DispatchTarget,
         models::{
-            user::User,
+            user::{User, Permissions},
             gateway::{
                 GatewayEvent, GatewayHelloPacket, Ready,
                 IncomingGatewayPacket, OutgoingGatewayPacket,
@@ -79,7 +79,7 @@ impl GatewayConnection {
                 //self.app.online.insert(user.id, *self);
                 self.dispatch(Ready {
                     user,
-                    users: online.iter().map(|x| (x.0.clone(), x.1.user.clone())).collect(),
+                    users: online.iter().map(|x| (*x.0, x.1.user.clone())).collect(),
                 })
                     .await
             }
@@ -109,6 +109,9 @@ impl GatewayConnection {
                             match target {
                                 DispatchTarget::Global => self.dispatch(event).await?,
                                 DispatchTarget::User(target_id) if user.id == target_id => self.dispatch(event).await?,
+                                DispatchTarget::Members(members) if members.contains(&user.id)
+                                    || user.has_permission(Permissions::MANAGE_THREADS) => self.dispatch(event).await?,
+                                DispatchTarget::Moderators if user.has_permission(Permissions::MANAGE_MESSAGES) => self.dispatch(event).await?,
                                 _ => (),
                             }
                         },
@@ -121,7 +124,7 @@ impl GatewayConnection {
                     let packet = match message.map_err(GatewayError::WebSocketError)? {
                         actix_ws::Message::Text(text) => serde_json::from_str::<IncomingGatewayPacket>(&text).map_err(GatewayError::Decode),
                         actix_ws::Message::Close(..) => {
-                            if let Some(_) = self.user.clone() {
+                            if self.user.is_some() {
                                 //self.app.online.remove(&user.id.clone());
                             }
 
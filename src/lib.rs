@@ -7,10 +7,16 @@ use {
     crate::{
         models::{
             gateway::GatewayEvent,
-            database::Database
+            database::Database,
+            poll::Poll,
+            message::Message,
+            thread::Thread,
+            notification::Notification
         },
+        routes::Result as HttpResult,
         utils::snowflake::{SnowflakeBuilder, Snowflake},
-        gateway::connection::GatewayConnection
+        gateway::connection::GatewayConnection,
+        storage::Storage
     }
 };
 
@@ -18,11 +24,20 @@ pub mod utils;
 pub mod routes;
 pub mod gateway;
 pub mod models;
+pub mod storage;
 
 #[derive(Clone)]
 pub enum DispatchTarget {
     Global,
-    User(Snowflake)
+    User(Snowflake),
+    /// Members of a private thread and users with [`Permissions::MANAGE_THREADS`]
+    ///
+    /// [`Permissions::MANAGE_THREADS`]: crate::models::user::Permissions::MANAGE_THREADS
+    Members(Vec<Snowflake>),
+    /// Users with [`Permissions::MANAGE_MESSAGES`]
+    ///
+    /// [`Permissions::MANAGE_MESSAGES`]: crate::models::user::Permissions::MANAGE_MESSAGES
+    Moderators
 }
 
 pub struct App {
@@ -31,10 +46,12 @@ pub struct App {
     pub online: HashMap<Snowflake, GatewayConnection>,
     pub database: Database,
     pub pool: PgPool,
+    pub storage: Box<dyn Storage>,
 }
 
 impl App {
     /// Dispatch to all users.
+    #[allow(clippy::result_large_err)]
     pub fn dispatch<T: Into<GatewayEvent>>(
         &self,
         to: DispatchTarget,
@@ -42,4 +59,41 @@ impl App {
     ) -> Result<(), broadcast::error::SendError<(DispatchTarget, GatewayEvent)>> {
         self.channel.send((to, event.into())).map(|_| ())
     }
+
+    /// Save the notification and dispatch it to the notified user, unless they disabled notifications of its type.
+    pub async fn notify(&self, notification: Notification) -> HttpResult<()> {
+        if let Some(notification) = notification.save(&self.pool).await? {
+            _ = self.dispatch(DispatchTarget::User(notification.user_id), GatewayEvent::NotificationCreate(notification));
+        }
+
+        Ok(())
+    }
+
+    /// Close polls which reached their closing time and dispatch their final results.
+    pub async fn close_expired_polls(&self) -> HttpResult<()> {
+        for thread_id in Poll::close_expired(&self.pool).await? {
+            let Ok(thread) = self.database.fetch_thread(thread_id).await else {
+                continue
+            };
+
+            if let Some(poll) = thread.poll.clone() {
+                _ = self.dispatch(self.database.fetch_dispatch_target(&thread).await?, GatewayEvent::PollUpdate(poll));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Permanently delete messages and threads deleted before the retention period and remove their files.
+    pub async fn purge_deleted(&self, retention: chrono::Duration) -> HttpResult<()> {
+        let before = chrono::Utc::now() - retention;
+        let mut attachments = Thread::purge_deleted(&self.pool, before).await?;
+        attachments.extend(Message::purge_deleted(&self.pool, before).await?);
+
+        for attachment in attachments {
+            _ = self.storage.delete(&attachment.key()).await;
+        }
+
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -3,16 +3,24 @@ use {
     tokio::sync::broadcast::Sender,
     std::collections::HashMap,
     env_logger::Env,
-    log::info,
+    log::{info, error},
+    tokio::time::{interval, Duration},
     sqlx::PgPool,
     forum::{
         routes,
         App as AppData,
         utils::snowflake::{EPOCH, SnowflakeBuilder},
-        models::database::Database
+        models::database::Database,
+        storage
     },
 };
 
+/// How often polls are checked for reaching their closing time
+const POLLS_CLOSE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How often deleted messages and threads are checked for outliving the retention period
+const PURGE_DELETED_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenvy::dotenv().ok();
@@ -33,7 +41,34 @@ async fn main() -> std::io::Result<()> {
         channel,
         online: HashMap::new(),
         database: Database::new(pool.clone()),
-        pool: pool.clone()
+        pool: pool.clone(),
+        storage: storage::from_env()
+    });
+
+    let polls_data = web::Data::clone(&data);
+    actix_web::rt::spawn(async move {
+        let mut interval = interval(POLLS_CLOSE_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(err) = polls_data.close_expired_polls().await {
+                error!("Failed to close expired polls: {}", err);
+            }
+        }
+    });
+
+    let retention = dotenvy::var("DELETED_RETENTION_DAYS").ok()
+        .map_or(Some(30), |days| days.parse::<u32>().ok())
+        .and_then(|days| chrono::Duration::try_days(days.into()))
+        .expect("`DELETED_RETENTION_DAYS` in .env is not a valid number of days");
+    let purge_data = web::Data::clone(&data);
+    actix_web::rt::spawn(async move {
+        let mut interval = interval(PURGE_DELETED_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(err) = purge_data.purge_deleted(retention).await {
+                error!("Failed to purge deleted messages and threads: {}", err);
+            }
+        }
     });
 
     info!(
@@ -0,0 +1,136 @@
+use {
+    serde::{Serialize, Deserialize},
+    sqlx::{
+        Decode, Postgres, PgExecutor,
+        postgres::PgValueRef
+    },
+    crate::{
+        models::user::{User, Permissions},
+        utils::snowflake::Snowflake,
+        routes::{HttpError, Result as HttpResult},
+        storage::content::Content
+    }
+};
+
+/// The maximum number of files a message can have
+pub const MAX_MESSAGE_ATTACHMENTS: usize = 10;
+
+/// Content types users without [`Permissions::MANAGE_MESSAGES`] can upload
+pub const ALLOWED_CONTENT_TYPES: [&str; 7] = [
+    "image/png", "image/jpeg", "image/gif", "image/webp",
+    "text/plain", "application/pdf", "application/zip"
+];
+
+/// Upload limits of a user, depending on their permissions
+#[derive(Debug, Clone, Copy)]
+pub struct AttachmentLimits {
+    /// The maximum size of a file in bytes
+    pub max_size: u64,
+    /// Content types the user can upload, `None` if any
+    pub content_types: Option<&'static [&'static str]>
+}
+
+impl AttachmentLimits {
+    /// Returns limits of the user
+    pub fn of(user: &User) -> Self {
+        if user.has_permission(Permissions::MANAGE_MESSAGES) {
+            Self { max_size: 50 * 1024 * 1024, content_types: None }
+        } else {
+            Self { max_size: 8 * 1024 * 1024, content_types: Some(&ALLOWED_CONTENT_TYPES) }
+        }
+    }
+
+    /// Checks whether the user can upload files of the content type
+    pub fn allows(&self, content_type: &str) -> bool {
+        self.content_types.is_none_or(|types| types.contains(&content_type))
+    }
+}
+
+/// File attached to a message
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Attachment {
+    /// The ID of the attachment
+    pub id: Snowflake,
+    /// The ID of the message the file is attached to
+    pub message_id: Snowflake,
+    /// The name of the file
+    pub filename: String,
+    /// The media type of the file
+    pub content_type: String,
+    /// The size of the file in bytes
+    pub size: i64,
+    /// The width of the image, `None` if the file is not an image
+    pub width: Option<i32>,
+    /// The height of the image, `None` if the file is not an image
+    pub height: Option<i32>,
+    /// SHA-256 hash of the file
+    pub hash: String
+}
+
+impl Attachment {
+    /// Create a new [`Attachment`] object, image dimensions are read from the beginning of the content
+    pub fn new(id: Snowflake, message_id: Snowflake, filename: &str, content_type: &str, content: &Content) -> Self {
+        let dimensions = content_type.starts_with("image/")
+            .then(|| imagesize::blob_size(&content.head).ok())
+            .flatten();
+
+        Self {
+            id,
+            message_id,
+            filename: filename.to_string(),
+            content_type: content_type.to_string(),
+            size: content.size as i64,
+            width: dimensions.map(|size| size.width as i32),
+            height: dimensions.map(|size| size.height as i32),
+            hash: content.hash.clone()
+        }
+    }
+
+    /// Checks whether the file is an image with known dimensions
+    pub fn is_image(&self) -> bool {
+        self.width.is_some()
+    }
+
+    /// The key the file is stored under in [`Storage`]
+    ///
+    /// [`Storage`]: crate::storage::Storage
+    pub fn key(&self) -> String {
+        format!("attachments/{}/{}", self.message_id.0, self.id.0)
+    }
+
+    /// Save a new attachment in the database.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Attachment`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::UnknownMessage`] - If the message is not found.
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn save<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Self> {
+        sqlx::query!(r#"INSERT INTO attachments(id, message_id, filename, content_type, size, width, height, hash) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+            self.id.0, self.message_id.0, self.filename, self.content_type, self.size, self.width, self.height, self.hash
+        )
+            .execute(executor).await
+            .map(|_| self)
+            .map_err(|err| match err.as_database_error().and_then(|err| err.constraint()) {
+                Some("attachments_message_id_fkey") => HttpError::UnknownMessage,
+                _ => HttpError::Database(err)
+            })
+    }
+}
+
+/// Files attached to a message in order they were uploaded
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(transparent)]
+pub struct Attachments(pub Vec<Attachment>);
+
+impl Decode<'_, Postgres> for Attachments {
+    fn decode(
+        value: PgValueRef<'_>,
+    ) -> Result<Self, Box<dyn std::error::Error + 'static + Send + Sync>> {
+        let s: sqlx::types::Json<Attachments> =  sqlx::Decode::<'_, Postgres>::decode(value)?;
+        Ok(s.0)
+    }
+}
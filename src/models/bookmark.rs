@@ -0,0 +1,137 @@
+use {
+    serde::{Serialize, Deserialize},
+    sqlx::PgExecutor,
+    crate::{
+        models::{
+            message::Message,
+            thread::Thread
+        },
+        utils::snowflake::Snowflake,
+        routes::{HttpError, Result as HttpResult}
+    }
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Bookmark {
+    /// The ID of the bookmark
+    pub id: Snowflake,
+    /// The ID of the user who saved the bookmark
+    pub user_id: Snowflake,
+    /// The ID of the bookmarked thread or the thread of the bookmarked message
+    pub thread_id: Snowflake,
+    /// The ID of the bookmarked message, `None` if the whole thread is bookmarked
+    pub message_id: Option<Snowflake>,
+    /// The folder the bookmark is sorted into
+    pub folder: Option<String>,
+    /// The user's note on the bookmark
+    pub note: Option<String>,
+    /// The thread, `None` if the user can't see it anymore
+    pub thread: Option<Thread>,
+    /// The bookmarked message
+    pub message: Option<Message>
+}
+
+/// A folder of bookmarks
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BookmarkFolder {
+    /// The name of the folder
+    pub name: String,
+    /// The number of bookmarks in the folder
+    pub bookmark_count: i64
+}
+
+impl Bookmark {
+    /// Create a new [`Bookmark`] object on the thread
+    pub fn new(id: Snowflake, user_id: Snowflake, thread: Thread) -> Self {
+        Self {
+            id,
+            user_id,
+            thread_id: thread.id,
+            message_id: None,
+            folder: None,
+            note: None,
+            thread: Some(thread),
+            message: None
+        }
+    }
+
+    /// Bookmark the message of the thread instead of the whole thread
+    pub fn with_message(mut self, message: Message) -> Self {
+        self.message_id = Some(message.id);
+        self.message = Some(message);
+        self
+    }
+
+    /// Set the folder of the bookmark
+    pub fn with_folder(mut self, folder: Option<String>) -> Self {
+        self.folder = folder;
+        self
+    }
+
+    /// Set the note of the bookmark
+    pub fn with_note(mut self, note: Option<String>) -> Self {
+        self.note = note;
+        self
+    }
+
+    /// Save a new bookmark in the database.
+    ///
+    /// Message bookmarks don't store the thread, so they follow the message when it's moved to another thread.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Bookmark`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::InvalidOperation`] - If the user has already bookmarked the thread or message.
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn save<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Self> {
+        let thread_id = self.message_id.is_none().then_some(self.thread_id.0);
+        let result = sqlx::query!(r#"
+                INSERT INTO bookmarks(id, user_id, thread_id, message_id, folder, note) VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT DO NOTHING"#,
+            self.id.0, self.user_id.0, thread_id, self.message_id.map(i64::from), self.folder, self.note
+        )
+            .execute(executor).await
+            .map_err(HttpError::Database)?;
+
+        if result.rows_affected() == 0 {
+            return Err(HttpError::InvalidOperation("You have already bookmarked this item".to_string()))
+        }
+
+        Ok(self)
+    }
+
+    /// Save changes of the folder and note in the database.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Bookmark`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn edit<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Self> {
+        sqlx::query!(r#"UPDATE bookmarks SET folder = $1, note = $2 WHERE id = $3"#,
+            self.folder, self.note, self.id.0
+        )
+            .execute(executor).await
+            .map(|_| self)
+            .map_err(HttpError::Database)
+    }
+
+    /// Delete the bookmark.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn delete<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<()> {
+        sqlx::query!(r#"DELETE FROM bookmarks WHERE id = $1"#,
+            self.id.0
+        )
+            .execute(executor).await
+            .map(|_| ())
+            .map_err(HttpError::Database)
+    }
+}
@@ -5,7 +5,7 @@ use {
     },
     serde::{Serialize, Deserialize},
     crate::{
-        models::user::User,
+        models::{user::User, attachment::Attachment},
         utils::snowflake::Snowflake,
         routes::{HttpError, Result as HttpResult}
     }
@@ -22,7 +22,9 @@ pub struct Category {
     /// Descriptions of the category
     pub description: String,
     /// Whether the category is locked
-    pub locked: bool
+    pub locked: bool,
+    /// Whether threads of the category are questions with accepted answers
+    pub question_mode: bool
 }
 
 impl Decode<'_, Postgres> for Category {
@@ -42,9 +44,16 @@ impl Category {
             locked,
             title: title.to_string(),
             description: description.to_string(),
+            question_mode: false
         }
     }
 
+    /// Set whether threads of the category are questions
+    pub fn with_question_mode(mut self, question_mode: bool) -> Self {
+        self.question_mode = question_mode;
+        self
+    }
+
     /// Save a new category in the database.
     ///
     /// ### Returns
@@ -55,25 +64,50 @@ impl Category {
     ///
     /// * [`HttpError::Database`] - If the database query fails.
     pub async fn save<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Self> {
-        sqlx::query!(r#"INSERT INTO categories(id, title, description, owner_id, locked) VALUES ($1, $2, $3, $4, $5)"#,
-            self.id.0, self.title, self.description, self.owner.id.0, self.locked
+        sqlx::query!(r#"INSERT INTO categories(id, title, description, owner_id, locked, question_mode) VALUES ($1, $2, $3, $4, $5, $6)"#,
+            self.id.0, self.title, self.description, self.owner.id.0, self.locked, self.question_mode
         )
             .execute(executor).await
             .map(|_| self)
             .map_err(HttpError::Database)
     }
 
-    /// Delete the category.
+    /// Save changes of the category in the database.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Category`] on success, otherwise [`HttpError`].
     ///
     /// ### Errors
     ///
     /// * [`HttpError::Database`] - If the database query fails.
-    pub async fn delete<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<()> {
-        sqlx::query_as!(ThreadRecord, r#"DELETE FROM categories WHERE id = $1"#,
-            self.id.0
+    pub async fn edit<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Self> {
+        sqlx::query!(r#"UPDATE categories SET title = $1, description = $2, locked = $3, question_mode = $4 WHERE id = $5"#,
+            self.title, self.description, self.locked, self.question_mode, self.id.0
         )
             .execute(executor).await
-            .map(|_| ())
+            .map(|_| self)
+            .map_err(HttpError::Database)
+    }
+
+    /// Delete the category with all its threads.
+    ///
+    /// ### Returns
+    ///
+    /// * Attachments of the deleted messages, their files have to be removed from the storage.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn delete<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Vec<Attachment>> {
+        // The outer query sees attachments as they were before the category was deleted
+        sqlx::query_as!(Attachment, r#"
+                WITH deleted AS (DELETE FROM categories WHERE id = $1 RETURNING id)
+                SELECT a.* FROM attachments a JOIN messages m ON a.message_id = m.id JOIN threads t ON m.thread_id = t.id
+                WHERE t.category_id IN (SELECT id FROM deleted)"#,
+            self.id.0
+        )
+            .fetch_all(executor).await
             .map_err(HttpError::Database)
     }
 }
\ No newline at end of file
@@ -1,15 +1,31 @@
 use {
+    std::collections::HashMap,
     base64::prelude::{Engine as _, BASE64_URL_SAFE_NO_PAD},
-    sqlx::PgPool,
+    chrono::{DateTime, Utc},
+    sqlx::{PgPool, types::Json},
     crate::{
+        DispatchTarget,
         models::{
             category::Category,
-            thread::{Thread, ThreadFlags},
+            thread::{Thread, ThreadCursor, ThreadFlags, ThreadFilter, ThreadType, ThreadRequirements, TagMode},
+            tag::Tag,
+            poll::{Poll, PollOption},
+            watch::{ThreadWatch, CategoryWatch, WatchLevel},
+            read_state::{ThreadUnread, UnreadSummary},
+            join_request::JoinRequest,
             session::{
                 Session, serialize_secret_timestamp, serialize_user_secret, serialize_user_token
             },
-            user::User,
-            message::Message
+            user::{User, Permissions},
+            message::{Message, MessagePreview, MessageRevision},
+            reaction::Reactions,
+            emoji::{Emoji, Emojis, CustomEmoji},
+            mention::Mentions,
+            attachment::{Attachment, Attachments},
+            report::{Report, ReportStatus},
+            notification::{Notification, NotificationSettings, NotificationType},
+            search::{SearchFilter, SearchResult},
+            bookmark::{Bookmark, BookmarkFolder}
         },
         routes::{HttpError, Result as HttpResult},
         utils::{
@@ -65,6 +81,38 @@ impl Database {
             .await.ok()?
     }
 
+    /// Fetch users mentioned by their IDs or usernames.
+    ///
+    /// ### Returns
+    ///
+    /// [`Vec<User>`] - Found users ordered by ID, unknown IDs and usernames are skipped.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_mentioned_users(&self, user_ids: &[i64], usernames: &[String]) -> HttpResult<Vec<User>> {
+        sqlx::query_as!(User, "SELECT * FROM users WHERE id = ANY($1) OR username = ANY($2) ORDER BY id",
+            user_ids, usernames
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)
+    }
+
+    /// Fetch the number of users mentioned by the user in messages since the given time.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_recent_mention_count(&self, user_id: Snowflake, since: DateTime<Utc>) -> HttpResult<i64> {
+        sqlx::query_scalar!(r#"
+                SELECT COUNT(*) AS "count!" FROM message_mentions mm JOIN messages m ON mm.message_id = m.id
+                WHERE m.author_id = $1 AND mm.created_at > $2"#,
+            user_id.0, since
+        )
+            .fetch_one(&self.pool).await
+            .map_err(HttpError::Database)
+    }
+
     /// Fetch a session from the database by their auth token.
     ///
     /// ### Arguments
@@ -85,7 +133,7 @@ impl Database {
             .decode(parts[0])
             .map_err(|_| HttpError::Unauthorized)
             .and_then(|v| String::from_utf8(v).map_err(|_| HttpError::Unauthorized))
-            .and_then(|s| s.parse::<i64>().map(|x| format!("{:x}", x)).map_err(|_| HttpError::Unauthorized))?;
+            .and_then(|s| s.parse::<i64>().map(|x| format!("{:014x}", x)).map_err(|_| HttpError::Unauthorized))?;
 
         let session = self.fetch_session(session_id.clone()).await
             .ok_or(HttpError::Unauthorized)?;
@@ -115,7 +163,7 @@ impl Database {
     /// * [`CategoryRecord`] if found, otherwise `None`.
     pub async fn fetch_category(&self, category_id: Snowflake) -> Option<Category> {
         sqlx::query_as!(Category, r#"
-                SELECT c.id, c.title, c.description, c.locked, ROW_TO_JSON(u.*) AS "owner!: User"
+                SELECT c.id, c.title, c.description, c.locked, c.question_mode, ROW_TO_JSON(u.*) AS "owner!: User"
                 FROM categories c LEFT JOIN users u ON c.owner_id = u.id WHERE c.id = $1"#,
             category_id.0
         )
@@ -131,15 +179,139 @@ impl Database {
     ///
     /// ### Returns
     ///
-    /// * [`Thread`] if found, otherwise `None`.
+    /// * [`Thread`] if found, otherwise [`HttpError::UnknownThread`].
     pub async fn fetch_thread(&self, thread_id: Snowflake) -> HttpResult<Thread> {
-        let row = sqlx::query!(r#"SELECT * FROM threads WHERE id = $1"#,thread_id.0)
-            .fetch_one(&self.pool).await
+        self.fetch_threads_by_ids(&[thread_id.0]).await?
+            .pop().ok_or(HttpError::UnknownThread)
+    }
+
+    /// Fetch threads from the database by their IDs.
+    ///
+    /// Runs three queries regardless of the number of threads: one for the threads, one for
+    /// their original messages and accepted answers with authors, and one for their polls.
+    ///
+    /// ### Arguments
+    ///
+    /// * `thread_ids` - The IDs of the threads to fetch.
+    ///
+    /// ### Returns
+    ///
+    /// [`Vec<Thread>`] - Found threads in the order of the given IDs.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_threads_by_ids(&self, thread_ids: &[i64]) -> HttpResult<Vec<Thread>> {
+        let rows = sqlx::query!(r#"
+                SELECT t.*, ARRAY(SELECT tag_id FROM thread_tags WHERE thread_id = t.id ORDER BY tag_id) AS "tags!"
+                FROM threads t WHERE t.id = ANY($1)"#,
+            thread_ids
+        )
+            .fetch_all(&self.pool).await
             .map_err(HttpError::Database)?;
-        let message = self.fetch_message(row.id.into(), row.original_message_id.into())
-            .await.ok_or(HttpError::UnknownMessage)?;
 
-        Ok(Thread::new(row.id.into(), row.category_id.into(), message, &row.title, Some(ThreadFlags::from(row.flags))))
+        let message_ids = rows.iter()
+            .flat_map(|row| [Some(row.original_message_id), row.accepted_answer_id])
+            .flatten()
+            .collect::<Vec<_>>();
+        let mut messages = self.fetch_messages_by_ids(&message_ids).await?
+            .into_iter()
+            .map(|message| (message.id, message))
+            .collect::<HashMap<_, _>>();
+        let mut polls = self.fetch_polls(thread_ids).await?;
+
+        let mut threads = rows.into_iter()
+            .map(|row| {
+                let message = messages.remove(&row.original_message_id.into())
+                    .ok_or(HttpError::UnknownMessage)?;
+
+                let mut thread = Thread::new(row.id.into(), row.category_id.into(), message, &row.title, Some(ThreadFlags::from(row.flags)))
+                    .with_type(row.r#type.into())
+                    .with_requirements(ThreadRequirements {
+                        min_account_age: row.min_account_age,
+                        min_message_count: row.min_message_count,
+                        required_permissions: row.required_permissions.map(Permissions::from),
+                        question: row.join_question
+                    });
+                thread.last_message_id = row.last_message_id.map(Into::into);
+                thread.last_activity_at = row.last_activity_at;
+                thread.message_count = row.message_count;
+                thread.deleted_at = row.deleted_at;
+                thread.deleted_by = row.deleted_by.map(Into::into);
+                thread.tags = row.tags.into_iter().map(Into::into).collect();
+                thread.accepted_answer = row.accepted_answer_id.and_then(|id| messages.remove(&id.into()))
+                    .filter(|message| !message.is_deleted());
+                thread.poll = polls.remove(&thread.id);
+
+                Ok((thread.id, thread))
+            })
+            .collect::<HttpResult<HashMap<_, _>>>()?;
+
+        Ok(thread_ids.iter()
+            .filter_map(|id| threads.remove(&Snowflake(*id)))
+            .collect())
+    }
+
+    /// Fetch the poll attached to the thread.
+    ///
+    /// ### Arguments
+    ///
+    /// * `thread_id` - The ID of the thread.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Poll`] if found, otherwise `None`.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_poll(&self, thread_id: Snowflake) -> HttpResult<Option<Poll>> {
+        self.fetch_polls(&[thread_id.0]).await
+            .map(|mut polls| polls.remove(&thread_id))
+    }
+
+    /// Fetch polls attached to the threads with their options and votes.
+    ///
+    /// ### Arguments
+    ///
+    /// * `thread_ids` - The IDs of the threads.
+    ///
+    /// ### Returns
+    ///
+    /// [`HashMap<Snowflake, Poll>`] - Found polls by the IDs of their threads.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_polls(&self, thread_ids: &[i64]) -> HttpResult<HashMap<Snowflake, Poll>> {
+        let rows = sqlx::query!(r#"
+                SELECT p.*, COALESCE((
+                    SELECT JSON_AGG(JSON_BUILD_OBJECT(
+                        'id', o.id::TEXT,
+                        'text', o.text,
+                        'vote_count', (SELECT COUNT(*) FROM poll_votes v WHERE v.option_id = o.id),
+                        'voters', CASE WHEN p.anonymous THEN NULL ELSE (
+                            SELECT COALESCE(JSON_AGG(v.user_id::TEXT ORDER BY v.user_id), '[]') FROM poll_votes v WHERE v.option_id = o.id
+                        ) END
+                    ) ORDER BY o.position) FROM poll_options o WHERE o.thread_id = p.thread_id
+                ), '[]') AS "options!: Json<Vec<PollOption>>"
+                FROM polls p WHERE p.thread_id = ANY($1)"#,
+            thread_ids
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)?;
+
+        Ok(rows.into_iter()
+            .map(|row| (row.thread_id.into(), Poll {
+                thread_id: row.thread_id.into(),
+                question: row.question,
+                options: row.options.0,
+                multiple_choice: row.multiple_choice,
+                anonymous: row.anonymous,
+                closes_at: row.closes_at,
+                closed: row.closed
+            }))
+            .collect())
     }
 
     /// Fetch a message from the database by ID.
@@ -153,13 +325,7 @@ impl Database {
     ///
     /// * [`Message`] if found, otherwise `None`.
     pub async fn fetch_message(&self, thread_id: Snowflake, message_id: Snowflake) -> Option<Message> {
-        sqlx::query_as!(Message, r#"
-                SELECT m.id, m.content, m.thread_id, m.flags, m.referenced_message_id, m.updated_at, ROW_TO_JSON(u.*) AS "author!: User"
-                FROM messages m LEFT JOIN users u ON m.author_id = u.id WHERE m.id = $1 AND thread_id = $2"#,
-            message_id.0, thread_id.0
-        )
-            .fetch_optional(&self.pool)
-            .await.ok()?
+        self.query_messages(Some(&[message_id.0]), Some(thread_id), None, None, None).await.ok()?.pop()
     }
 
     /// Fetch messages from the thread.
@@ -167,9 +333,10 @@ impl Database {
     /// ### Arguments
     ///
     /// * `thread_id` - The ID of the threads the messages fetch from
-    /// * `limit` - The maximum number of messages to fetch. Defaults to 50, capped at 100.
+    /// * `limit` - The maximum number of messages to fetch, the route validates it to be between 1 and 100. Defaults to 50.
     /// * `before` - Fetch messages before this ID.
     /// * `after` - Fetch messages after this ID.
+    /// * `around` - Fetch messages around this ID, including the message itself. Takes precedence over `before` and `after`.
     ///
     /// ### Returns
     ///
@@ -177,73 +344,847 @@ impl Database {
     ///
     /// ### Errors
     ///
-    /// * [`sqlx::Error`] - If the database query fails.
-    pub async fn fetch_messages(&self, thread_id: Snowflake, limit: Option<u16>, before: Option<Snowflake>, after: Option<Snowflake>) -> HttpResult<Vec<Message>> {
-        let limit = limit.unwrap_or(50).min(100);
-        let rows = if before.is_none() && after.is_none() {
-            sqlx::query_as!(Message, r#"
-                   SELECT m.id, m.content, m.thread_id, m.flags, m.referenced_message_id, m.updated_at, ROW_TO_JSON(u.*) AS "author!: User"
-                   FROM messages m LEFT JOIN users u ON m.author_id = u.id
-                   WHERE thread_id = $1 ORDER BY m.id DESC LIMIT $2"#,
-                thread_id.0, i64::from(limit)
-            )
-                .fetch_all(&self.pool).await
-                .map_err(|_| HttpError::UnknownThread)?
-        } else {
-            sqlx::query_as!(Message, r#"
-                   SELECT m.id, m.content, m.thread_id, m.flags, m.referenced_message_id, m.updated_at, ROW_TO_JSON(u.*) AS "author!: User"
-                   FROM messages m LEFT JOIN users u ON m.author_id = u.id
-                   WHERE thread_id = $1 AND m.id < $2 AND m.id > $3 ORDER BY m.id DESC LIMIT $4"#,
-                thread_id.0, before.map_or(i64::MAX, Into::into), after.map_or(i64::MIN, Into::into), i64::from(limit)
-            )
-                .fetch_all(&self.pool).await
-                .map_err(|_| HttpError::UnknownThread)?
-        };
-
-        Ok(rows)
-    }
-
-    /// Fetch channels from the category.
+    /// * [`HttpError::UnknownMessage`] - If the `around` message is not in the thread.
+    /// * [`HttpError::UnknownThread`] - If the database query fails.
+    /// * [`HttpError::Database`] - If the database query for the `around` messages fails.
+    pub async fn fetch_messages(&self, thread_id: Snowflake, limit: Option<u16>, before: Option<Snowflake>, after: Option<Snowflake>, around: Option<Snowflake>) -> HttpResult<Vec<Message>> {
+        let limit = limit.unwrap_or(50);
+
+        if let Some(around) = around {
+            return self.fetch_messages_around(thread_id, around, limit).await
+        }
+
+        self.query_messages(None, Some(thread_id), before, after, Some(limit)).await
+            .map_err(|_| HttpError::UnknownThread)
+    }
+
+    /// Fetch a window of messages centred on the message, newest first.
+    ///
+    /// Half of the limit, rounded up, is taken by the message and older ones, the rest by newer ones.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::UnknownMessage`] - If the message is not in the thread.
+    /// * [`HttpError::Database`] - If the database query fails.
+    async fn fetch_messages_around(&self, thread_id: Snowflake, message_id: Snowflake, limit: u16) -> HttpResult<Vec<Message>> {
+        let older = limit.div_ceil(2);
+
+        let rows = sqlx::query_as!(Id, r#"
+                SELECT id AS "id!" FROM (
+                    (SELECT id FROM messages WHERE thread_id = $1 AND id <= $2 ORDER BY id DESC LIMIT $3)
+                    UNION ALL
+                    (SELECT id FROM messages WHERE thread_id = $1 AND id > $2 ORDER BY id LIMIT $4)
+                ) m ORDER BY id DESC"#,
+            thread_id.0, message_id.0, i64::from(older), i64::from(limit - older)
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)?;
+
+        if !rows.iter().any(|row| row.id == message_id) {
+            return Err(HttpError::UnknownMessage)
+        }
+
+        self.fetch_messages_by_ids(&rows.into_iter().map(|row| row.id.0).collect::<Vec<_>>()).await
+    }
+
+    /// Fetch messages by their IDs.
     ///
     /// ### Arguments
     ///
-    /// * `category_id` - The ID of the category the threads fetch from
-    /// * `limit` - The maximum number of threads to fetch. Defaults to 50, capped at 100.
+    /// * `message_ids` - The IDs of the messages to fetch.
+    ///
+    /// ### Returns
+    ///
+    /// [`Vec<Message>`] - Found messages in the order of the given IDs.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_messages_by_ids(&self, message_ids: &[i64]) -> HttpResult<Vec<Message>> {
+        let mut messages = self.query_messages(Some(message_ids), None, None, None, None).await?
+            .into_iter()
+            .map(|message| (message.id, message))
+            .collect::<HashMap<_, _>>();
+
+        Ok(message_ids.iter()
+            .filter_map(|id| messages.remove(&Snowflake(*id)))
+            .collect())
+    }
+
+    /// Fetch messages with their authors, emojis, mentions, reactions, attachments and previews of replied messages,
+    /// newest first. All message queries go through it, so the selected columns are defined once.
+    ///
+    /// ### Arguments
+    ///
+    /// * `message_ids` - Fetch only messages with these IDs.
+    /// * `thread_id` - Fetch only messages of this thread.
     /// * `before` - Fetch messages before this ID.
     /// * `after` - Fetch messages after this ID.
+    /// * `limit` - The maximum number of messages to fetch, all of them if `None`.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    async fn query_messages(&self, message_ids: Option<&[i64]>, thread_id: Option<Snowflake>, before: Option<Snowflake>, after: Option<Snowflake>, limit: Option<u16>) -> HttpResult<Vec<Message>> {
+        sqlx::query_as!(Message, r#"
+                SELECT m.id, m.content, m.thread_id, m.flags, m.referenced_message_id AS "referenced_message_id: Snowflake", m.edit_count, m.updated_at, m.deleted_at, m.deleted_by AS "deleted_by: Snowflake", ROW_TO_JSON(u.*) AS "author!: User",
+                message_emojis_json(m.content) AS "emojis!: Emojis", message_mentions_json(m.id) AS "mentions!: Mentions", message_reactions_json(m.id) AS "reactions!: Reactions", message_attachments_json(m.id) AS "attachments!: Attachments",
+                CASE WHEN r.id IS NULL OR r.deleted_at IS NOT NULL THEN NULL ELSE JSON_BUILD_OBJECT(
+                    'id', r.id::TEXT, 'author', ROW_TO_JSON(ru.*), 'content', LEFT(r.content, 100)
+                ) END AS "referenced_message: MessagePreview"
+                FROM messages m LEFT JOIN users u ON m.author_id = u.id
+                LEFT JOIN messages r ON m.referenced_message_id = r.id LEFT JOIN users ru ON r.author_id = ru.id
+                WHERE ($1::BIGINT[] IS NULL OR m.id = ANY($1)) AND ($2::BIGINT IS NULL OR m.thread_id = $2) AND m.id < $3 AND m.id > $4
+                ORDER BY m.id DESC LIMIT $5"#,
+            message_ids, thread_id.map(i64::from), before.map_or(i64::MAX, Into::into), after.map_or(i64::MIN, Into::into),
+            limit.map(i64::from)
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)
+    }
+
+    /// Search messages and thread titles the user can see.
+    ///
+    /// A thread matches through its original message. Private threads are searched only if the user can see them.
+    ///
+    /// ### Arguments
+    ///
+    /// * `user` - The user searching.
+    /// * `filter` - The query and filters of the search.
+    /// * `limit` - The maximum number of results to fetch, the route validates it to be between 1 and 100. Defaults to 25.
+    /// * `before` - Fetch results with message ID before this ID.
+    ///
+    /// ### Returns
+    ///
+    /// [`Vec<SearchResult>`] - Matching messages, newest first.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn search_messages(&self, user: &User, filter: &SearchFilter, limit: Option<u16>, before: Option<Snowflake>) -> HttpResult<Vec<SearchResult>> {
+        let limit = limit.unwrap_or(25);
+        let before = [before, filter.until.map(Snowflake::from_timestamp)].into_iter()
+            .flatten()
+            .min();
+
+        let rows = sqlx::query!(r#"
+                WITH q AS (SELECT websearch_to_tsquery('english', $1) AS query)
+                SELECT m.id, t.category_id, t.title,
+                ts_headline('english', translate(m.content, $13, ''), q.query, $2) AS "highlight!",
+                CASE WHEN m.id = t.original_message_id AND to_tsvector('english', t.title) @@ q.query
+                    THEN ts_headline('english', translate(t.title, $13, ''), q.query, $2)
+                END AS title_highlight
+                FROM q, messages m JOIN threads t ON m.thread_id = t.id
+                WHERE (to_tsvector('english', m.content) @@ q.query OR (
+                    m.id = t.original_message_id AND to_tsvector('english', t.title) @@ q.query
+                ))
+                AND m.id < $3 AND m.id >= $4
+                AND ($5::BIGINT IS NULL OR m.author_id = $5)
+                AND ($6::BIGINT IS NULL OR t.category_id = $6)
+                AND ($7::BOOLEAN IS NULL OR EXISTS (SELECT 1 FROM attachments WHERE message_id = m.id) = $7)
+                AND ($8 OR (m.deleted_at IS NULL AND t.deleted_at IS NULL))
+                AND (t.type <> $9 OR $10 OR EXISTS (
+                    SELECT 1 FROM thread_members WHERE thread_id = t.id AND user_id = $11
+                ))
+                ORDER BY m.id DESC LIMIT $12"#,
+            filter.query, SearchFilter::headline_options(), before.map_or(i64::MAX, Into::into),
+            filter.since.map_or(i64::MIN, |since| Snowflake::from_timestamp(since).0),
+            filter.author_id.map(i64::from), filter.category_id.map(i64::from), filter.has_attachments, filter.deleted,
+            i32::from(ThreadType::Private), user.has_permission(Permissions::MANAGE_THREADS), user.id.0, i64::from(limit),
+            SearchFilter::highlight_markers()
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)?;
+
+        let mut messages = self.fetch_messages_by_ids(&rows.iter().map(|row| row.id).collect::<Vec<_>>()).await?
+            .into_iter()
+            .map(|message| (message.id, message))
+            .collect::<HashMap<_, _>>();
+
+        Ok(rows.into_iter()
+            .filter_map(|row| {
+                let message = messages.remove(&row.id.into())?;
+
+                Some(SearchResult::new(message, row.category_id.into(), row.title, &row.highlight, row.title_highlight.as_deref()))
+            })
+            .collect())
+    }
+
+    /// Fetch users who reacted to the message with the emoji.
+    ///
+    /// ### Arguments
+    ///
+    /// * `message_id` - The ID of the message.
+    /// * `emoji` - The emoji of the reaction.
+    /// * `limit` - The maximum number of users to fetch. Defaults to 25, capped at 100.
+    /// * `after` - Fetch users with ID after this ID.
+    ///
+    /// ### Returns
+    ///
+    /// [`Vec<User>`] - Users ordered by ID.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_reactors(&self, message_id: Snowflake, emoji: &Emoji, limit: Option<u16>, after: Option<Snowflake>) -> HttpResult<Vec<User>> {
+        let limit = limit.unwrap_or(25).min(100);
+        sqlx::query_as!(User, r#"
+                SELECT u.* FROM message_reactions r JOIN users u ON r.user_id = u.id
+                WHERE r.message_id = $1 AND COALESCE(r.emoji_id, 0) = COALESCE($2::BIGINT, 0) AND r.emoji_name = $3 AND u.id > $4
+                ORDER BY u.id LIMIT $5"#,
+            message_id.0, emoji.id.map(i64::from), emoji.name, after.map_or(i64::MIN, Into::into), i64::from(limit)
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)
+    }
+
+    /// Fetch previous contents of the message.
+    ///
+    /// ### Returns
+    ///
+    /// [`Vec<MessageRevision>`] - Revisions of the message from the oldest one.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_message_revisions(&self, message_id: Snowflake) -> HttpResult<Vec<MessageRevision>> {
+        sqlx::query_as!(MessageRevision, r#"SELECT * FROM message_revisions WHERE message_id = $1 ORDER BY revision"#,
+            message_id.0
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)
+    }
+
+    /// Fetch attachments of all messages in the thread.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_thread_attachments(&self, thread_id: Snowflake) -> HttpResult<Vec<Attachment>> {
+        sqlx::query_as!(Attachment, r#"
+                SELECT a.* FROM attachments a JOIN messages m ON a.message_id = m.id
+                WHERE m.thread_id = $1"#,
+            thread_id.0
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)
+    }
+
+    /// Fetch threads from the category.
+    ///
+    /// Pinned threads always go first, the rest are ordered by `sort`. The `before` and `after`
+    /// cursors carry the position the thread was listed at, so pages stay stable while threads
+    /// receive new messages or leave the listing. Every fetched thread has its own cursor.
+    ///
+    /// ### Arguments
+    ///
+    /// * `category_id` - The ID of the category the threads fetch from
+    /// * `user` - The user the threads are fetched for. Private threads are skipped unless the user can see them.
+    /// * `filter` - The order of the threads, tags, solved and deleted state they are filtered by.
+    /// * `limit` - The maximum number of threads to fetch. Defaults to 50, capped at 100.
+    /// * `before` - Fetch threads placed after this cursor in the chosen order.
+    /// * `after` - Fetch threads placed before this cursor in the chosen order.
+    ///
+    /// ### Returns
+    ///
+    /// [`Vec<Thread>`] - Fetched threads.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_threads(&self, category_id: Snowflake, user: &User, filter: &ThreadFilter, limit: Option<u16>, before: Option<ThreadCursor>, after: Option<ThreadCursor>) -> HttpResult<Vec<Thread>> {
+        let limit = limit.unwrap_or(50).min(100);
+        let mut tags = filter.tags.iter().map(|tag| tag.0).collect::<Vec<_>>();
+        tags.sort();
+        tags.dedup();
+
+        let rows = sqlx::query!(r#"
+               WITH t AS (
+                   SELECT id, (flags & 1) AS pinned, CASE $2::TEXT
+                       WHEN 'created' THEN id
+                       WHEN 'top' THEN message_count::BIGINT
+                       WHEN 'activity' THEN (EXTRACT(EPOCH FROM last_activity_at) * 1000000)::BIGINT
+                       ELSE COALESCE(last_message_id, id)
+                   END AS sort_key
+                   FROM threads WHERE category_id = $1 AND (type <> $6 OR $7 OR EXISTS (
+                       SELECT 1 FROM thread_members WHERE thread_id = threads.id AND user_id = $8
+                   )) AND (CARDINALITY($9::BIGINT[]) = 0 OR (
+                       SELECT COUNT(*) FROM thread_tags WHERE thread_id = threads.id AND tag_id = ANY($9)
+                   ) >= CASE WHEN $10 THEN CARDINALITY($9) ELSE 1 END)
+                   AND ($11::BOOLEAN IS NULL OR (accepted_answer_id IS NOT NULL) = $11)
+                   AND (deleted_at IS NOT NULL) = $12
+               )
+               SELECT id AS "id!", pinned AS "pinned!", sort_key AS "sort_key!" FROM t
+               WHERE ($3::BIGINT IS NULL OR (pinned, sort_key, id) < ($13::INT, $14::BIGINT, $3))
+               AND ($4::BIGINT IS NULL OR (pinned, sort_key, id) > ($15::INT, $16::BIGINT, $4))
+               ORDER BY pinned DESC, sort_key DESC, id DESC LIMIT $5"#,
+            category_id.0, filter.sort.as_str(), before.map(|cursor| cursor.id.0), after.map(|cursor| cursor.id.0), i64::from(limit),
+            i32::from(ThreadType::Private), user.has_permission(Permissions::MANAGE_THREADS), user.id.0,
+            &tags, filter.tag_mode == TagMode::All, filter.solved, filter.deleted,
+            before.map(|cursor| i32::from(cursor.pinned)), before.map(|cursor| cursor.sort_key),
+            after.map(|cursor| i32::from(cursor.pinned)), after.map(|cursor| cursor.sort_key)
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)?;
+
+        let mut cursors = rows.iter()
+            .map(|row| (Snowflake(row.id), ThreadCursor { sort: filter.sort, pinned: row.pinned != 0, sort_key: row.sort_key, id: row.id.into() }))
+            .collect::<HashMap<_, _>>();
+
+        Ok(self.fetch_threads_by_ids(&rows.iter().map(|row| row.id).collect::<Vec<_>>()).await?
+            .into_iter()
+            .map(|mut thread| {
+                thread.cursor = cursors.remove(&thread.id);
+                thread
+            })
+            .collect())
+    }
+
+    /// Fetch tags of the category.
+    ///
+    /// ### Arguments
+    ///
+    /// * `category_id` - The ID of the category.
+    ///
+    /// ### Returns
+    ///
+    /// [`Vec<Tag>`] - Tags of the category.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_tags(&self, category_id: Snowflake) -> HttpResult<Vec<Tag>> {
+        sqlx::query_as!(Tag, r#"SELECT * FROM tags WHERE category_id = $1 ORDER BY id"#,
+            category_id.0
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)
+    }
+
+    /// Fetch a tag of the category by ID.
+    ///
+    /// ### Arguments
+    ///
+    /// * `category_id` - The ID of the category.
+    /// * `tag_id` - The ID of the tag to fetch.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Tag`] if found, otherwise `None`.
+    pub async fn fetch_tag(&self, category_id: Snowflake, tag_id: Snowflake) -> Option<Tag> {
+        sqlx::query_as!(Tag, r#"SELECT * FROM tags WHERE category_id = $1 AND id = $2"#,
+            category_id.0, tag_id.0
+        )
+            .fetch_optional(&self.pool)
+            .await.ok()?
+    }
+
+    /// Fetch custom emoji.
+    ///
+    /// ### Arguments
+    ///
+    /// * `emoji_ids` - The IDs of the emoji to fetch, all emoji if `None`.
+    ///
+    /// ### Returns
+    ///
+    /// [`Vec<CustomEmoji>`] - Custom emoji ordered by name.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_emojis(&self, emoji_ids: Option<&[i64]>) -> HttpResult<Vec<CustomEmoji>> {
+        sqlx::query_as!(CustomEmoji, r#"
+                SELECT id, name, content_type, creator_id AS "creator_id: Snowflake", required_permissions AS "required_permissions: Permissions"
+                FROM emojis WHERE $1::BIGINT[] IS NULL OR id = ANY($1) ORDER BY name"#,
+            emoji_ids
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)
+    }
+
+    /// Fetch a custom emoji by ID.
+    ///
+    /// ### Returns
+    ///
+    /// * [`CustomEmoji`] if found, otherwise `None`.
+    pub async fn fetch_emoji(&self, emoji_id: Snowflake) -> Option<CustomEmoji> {
+        sqlx::query_as!(CustomEmoji, r#"
+                SELECT id, name, content_type, creator_id AS "creator_id: Snowflake", required_permissions AS "required_permissions: Permissions"
+                FROM emojis WHERE id = $1"#,
+            emoji_id.0
+        )
+            .fetch_optional(&self.pool)
+            .await.ok()?
+    }
+
+    /// Fetch members of the thread.
+    ///
+    /// ### Arguments
+    ///
+    /// * `thread_id` - The ID of the thread.
+    ///
+    /// ### Returns
+    ///
+    /// [`Vec<User>`] - Members of the thread.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_thread_members(&self, thread_id: Snowflake) -> HttpResult<Vec<User>> {
+        sqlx::query_as!(User, r#"
+                SELECT u.* FROM thread_members m JOIN users u ON m.user_id = u.id
+                WHERE m.thread_id = $1 ORDER BY u.id"#,
+            thread_id.0
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)
+    }
+
+    /// Checks whether the user can see the thread.
+    ///
+    /// Private threads are visible only to their members and users with [`Permissions::MANAGE_THREADS`].
+    pub async fn has_thread_access(&self, thread: &Thread, user: &User) -> bool {
+        if thread.kind != ThreadType::Private || user.has_permission(Permissions::MANAGE_THREADS) {
+            return true
+        }
+
+        self.is_thread_member(thread.id, user.id).await
+    }
+
+    /// Checks whether the user is a member of the thread.
+    pub async fn is_thread_member(&self, thread_id: Snowflake, user_id: Snowflake) -> bool {
+        sqlx::query!(r#"SELECT 1 AS "exists" FROM thread_members WHERE thread_id = $1 AND user_id = $2"#,
+            thread_id.0, user_id.0
+        )
+            .fetch_optional(&self.pool).await
+            .ok().flatten().is_some()
+    }
+
+    /// Fetch the join request of the user.
+    ///
+    /// ### Arguments
+    ///
+    /// * `thread_id` - The ID of the thread.
+    /// * `user_id` - The ID of the user who requested to join.
+    ///
+    /// ### Returns
+    ///
+    /// * [`JoinRequest`] if found, otherwise `None`.
+    pub async fn fetch_join_request(&self, thread_id: Snowflake, user_id: Snowflake) -> Option<JoinRequest> {
+        self.fetch_join_requests(thread_id, Some(user_id)).await.ok()?.pop()
+    }
+
+    /// Fetch pending join requests of the thread.
+    ///
+    /// ### Arguments
+    ///
+    /// * `thread_id` - The ID of the thread.
+    /// * `user_id` - Fetch only the request of this user, regardless of its status.
     ///
     /// ### Returns
     ///
-    /// [`Vec<Thread>`] - Fetched messages.
+    /// [`Vec<JoinRequest>`] - Fetched join requests.
     ///
     /// ### Errors
     ///
-    /// * [`sqlx::Error`] - If the database query fails.
-    pub async fn fetch_threads(&self, category_id: Snowflake, limit: Option<u16>, before: Option<Snowflake>, after: Option<Snowflake>) -> HttpResult<Vec<Thread>> {
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_join_requests(&self, thread_id: Snowflake, user_id: Option<Snowflake>) -> HttpResult<Vec<JoinRequest>> {
+        let rows = sqlx::query!(r#"
+                SELECT r.thread_id, r.answer, r.status, r.reviewer_id, ROW_TO_JSON(u.*) AS "user!: User"
+                FROM thread_join_requests r JOIN users u ON r.user_id = u.id
+                WHERE r.thread_id = $1 AND (r.user_id = $2 OR ($2 IS NULL AND r.status = 0)) ORDER BY u.id"#,
+            thread_id.0, user_id.map(i64::from)
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)?;
+
+        Ok(rows.into_iter()
+            .map(|row| JoinRequest {
+                thread_id: row.thread_id.into(),
+                user: row.user,
+                answer: row.answer,
+                status: row.status.into(),
+                reviewer_id: row.reviewer_id.map(Into::into)
+            })
+            .collect())
+    }
+
+    /// Fetch a report by ID.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Report`] if found, otherwise `None`.
+    pub async fn fetch_report(&self, report_id: Snowflake) -> Option<Report> {
+        self.fetch_reports(Some(report_id), None, None, None, None).await.ok()?.pop()
+    }
+
+    /// Fetch reports from the moderation queue, oldest first.
+    ///
+    /// ### Arguments
+    ///
+    /// * `report_id` - Fetch only the report with this ID.
+    /// * `status` - Fetch only reports with this status.
+    /// * `assignee_id` - Fetch only reports assigned to this moderator.
+    /// * `limit` - The maximum number of reports to fetch. Defaults to 50, capped at 100.
+    /// * `after` - Fetch reports after this ID.
+    ///
+    /// ### Returns
+    ///
+    /// [`Vec<Report>`] - Fetched reports.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_reports(&self, report_id: Option<Snowflake>, status: Option<ReportStatus>, assignee_id: Option<Snowflake>, limit: Option<u16>, after: Option<Snowflake>) -> HttpResult<Vec<Report>> {
         let limit = limit.unwrap_or(50).min(100);
-        let rows = if before.is_none() && after.is_none() {
-            sqlx::query_as!(Id, r#"
-                   SELECT id FROM threads WHERE category_id = $1 ORDER BY id DESC LIMIT $2"#,
-                category_id.0, i64::from(limit)
-            )
-                .fetch_all(&self.pool).await
-                .map_err(|_| HttpError::UnknownCategory)?
-        } else {
-            sqlx::query_as!(Id, r#"
-                   SELECT id FROM threads WHERE category_id = $1 AND id < $2 AND id > $3 ORDER BY id DESC LIMIT $4"#,
-                category_id.0, before.map_or(i64::MAX, Into::into), after.map_or(i64::MIN, Into::into), i64::from(limit)
-            )
-                .fetch_all(&self.pool).await
-                .map_err(|_| HttpError::UnknownCategory)?
-        };
-
-        let mut threads = vec![];
-        for id in rows {
-            let thread = self.fetch_thread(id.id).await?;
-            threads.push(thread);
+        let rows = sqlx::query!(r#"
+                SELECT r.*, m.thread_id FROM reports r JOIN messages m ON r.message_id = m.id
+                WHERE ($1::BIGINT IS NULL OR r.id = $1) AND ($2::INTEGER IS NULL OR r.status = $2)
+                    AND ($3::BIGINT IS NULL OR r.assignee_id = $3) AND r.id > $4
+                ORDER BY r.id LIMIT $5"#,
+            report_id.map(i64::from), status.map(i32::from), assignee_id.map(i64::from),
+            after.map_or(i64::MIN, Into::into), i64::from(limit)
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)?;
+
+        Ok(rows.into_iter()
+            .map(|row| Report {
+                id: row.id.into(),
+                thread_id: row.thread_id.into(),
+                message_id: row.message_id.into(),
+                reporter_id: row.reporter_id.into(),
+                reason: row.reason.into(),
+                details: row.details,
+                status: row.status.into(),
+                assignee_id: row.assignee_id.map(Into::into),
+                resolution_note: row.resolution_note,
+                resolved_by: row.resolved_by.map(Into::into),
+                resolved_at: row.resolved_at
+            })
+            .collect())
+    }
+
+    /// Fetch a notification of the user by ID.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Notification`] if found, otherwise `None`.
+    pub async fn fetch_notification(&self, user_id: Snowflake, notification_id: Snowflake) -> Option<Notification> {
+        self.fetch_notifications(user_id, Some(notification_id), false, None, None, None).await.ok()?.pop()
+    }
+
+    /// Fetch notifications of the user, newest first.
+    ///
+    /// ### Arguments
+    ///
+    /// * `user_id` - The ID of the notified user.
+    /// * `notification_id` - Fetch only the notification with this ID.
+    /// * `unread` - Fetch only unread notifications.
+    /// * `limit` - The maximum number of notifications to fetch, the route validates it to be between 1 and 100. Defaults to 50.
+    /// * `before` - Fetch notifications before this ID.
+    /// * `after` - Fetch notifications after this ID.
+    ///
+    /// ### Returns
+    ///
+    /// [`Vec<Notification>`] - Fetched notifications.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_notifications(&self, user_id: Snowflake, notification_id: Option<Snowflake>, unread: bool, limit: Option<u16>, before: Option<Snowflake>, after: Option<Snowflake>) -> HttpResult<Vec<Notification>> {
+        let limit = limit.unwrap_or(50);
+        let rows = sqlx::query!(r#"
+                SELECT * FROM notifications
+                WHERE user_id = $1 AND ($2::BIGINT IS NULL OR id = $2) AND (NOT $3 OR NOT read) AND id < $4 AND id > $5
+                ORDER BY id DESC LIMIT $6"#,
+            user_id.0, notification_id.map(i64::from), unread, before.map_or(i64::MAX, Into::into), after.map_or(i64::MIN, Into::into), i64::from(limit)
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)?;
+
+        Ok(rows.into_iter()
+            .map(|row| Notification {
+                id: row.id.into(),
+                user_id: row.user_id.into(),
+                kind: row.r#type.into(),
+                actor_id: row.actor_id.map(Into::into),
+                thread_id: row.thread_id.map(Into::into),
+                message_id: row.message_id.map(Into::into),
+                emoji: row.emoji_name.map(|name| Emoji { id: row.emoji_id.map(Into::into), name }),
+                read: row.read
+            })
+            .collect())
+    }
+
+    /// Fetch notification types the user receives.
+    ///
+    /// ### Returns
+    ///
+    /// [`NotificationSettings`] - Settings of all types, types the user didn't choose are enabled.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_notification_settings(&self, user_id: Snowflake) -> HttpResult<NotificationSettings> {
+        let rows = sqlx::query!(r#"SELECT type, enabled FROM notification_settings WHERE user_id = $1"#,
+            user_id.0
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)?;
+
+        let mut settings = NotificationSettings(NotificationType::ALL.into_iter().map(|kind| (kind, true)).collect());
+        for row in rows {
+            settings.0.insert(row.r#type.into(), row.enabled);
+        }
+
+        Ok(settings)
+    }
+
+    /// Fetch a bookmark of the user by ID, without the thread and message.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Bookmark`] if found, otherwise `None`.
+    pub async fn fetch_bookmark(&self, user_id: Snowflake, bookmark_id: Snowflake) -> Option<Bookmark> {
+        sqlx::query!(r#"
+                SELECT b.id, b.user_id, COALESCE(b.thread_id, m.thread_id) AS "thread_id!", b.message_id, b.folder, b.note
+                FROM bookmarks b LEFT JOIN messages m ON b.message_id = m.id
+                WHERE b.id = $1 AND b.user_id = $2"#,
+            bookmark_id.0, user_id.0
+        )
+            .fetch_optional(&self.pool).await
+            .ok()?
+            .map(|row| Bookmark {
+                id: row.id.into(),
+                user_id: row.user_id.into(),
+                thread_id: row.thread_id.into(),
+                message_id: row.message_id.map(Into::into),
+                folder: row.folder,
+                note: row.note,
+                thread: None,
+                message: None
+            })
+    }
+
+    /// Fetch bookmarks of the user, newest first.
+    ///
+    /// Bookmarks on threads the user can't see anymore are kept without their thread and message, deleted messages are
+    /// replaced with tombstones unless the user has [`Permissions::MANAGE_MESSAGES`].
+    ///
+    /// ### Arguments
+    ///
+    /// * `user` - The user who saved the bookmarks.
+    /// * `bookmark_id` - Fetch only the bookmark with this ID.
+    /// * `folder` - Fetch only bookmarks in this folder.
+    /// * `limit` - The maximum number of bookmarks to fetch, the route validates it to be between 1 and 100. Defaults to 50.
+    /// * `before` - Fetch bookmarks before this ID.
+    /// * `after` - Fetch bookmarks after this ID.
+    ///
+    /// ### Returns
+    ///
+    /// [`Vec<Bookmark>`] - Fetched bookmarks.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_bookmarks(&self, user: &User, bookmark_id: Option<Snowflake>, folder: Option<&str>, limit: Option<u16>, before: Option<Snowflake>, after: Option<Snowflake>) -> HttpResult<Vec<Bookmark>> {
+        let limit = limit.unwrap_or(50);
+        let rows = sqlx::query!(r#"
+                SELECT b.id, b.user_id, t.id AS thread_id, b.message_id, b.folder, b.note,
+                (t.deleted_at IS NULL OR $7) AND (t.type <> $8 OR $7 OR EXISTS (
+                    SELECT 1 FROM thread_members WHERE thread_id = t.id AND user_id = $1
+                )) AS "visible!"
+                FROM bookmarks b LEFT JOIN messages m ON b.message_id = m.id
+                JOIN threads t ON t.id = COALESCE(b.thread_id, m.thread_id)
+                WHERE b.user_id = $1 AND ($2::BIGINT IS NULL OR b.id = $2) AND ($3::TEXT IS NULL OR b.folder = $3)
+                AND b.id < $4 AND b.id > $5
+                ORDER BY b.id DESC LIMIT $6"#,
+            user.id.0, bookmark_id.map(i64::from), folder, before.map_or(i64::MAX, Into::into), after.map_or(i64::MIN, Into::into),
+            i64::from(limit), user.has_permission(Permissions::MANAGE_THREADS), i32::from(ThreadType::Private)
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)?;
+
+        let visible = rows.iter().filter(|row| row.visible);
+        let threads = self.fetch_threads_by_ids(&visible.clone().map(|row| row.thread_id).collect::<Vec<_>>()).await?
+            .into_iter()
+            .map(|thread| (thread.id, thread))
+            .collect::<HashMap<_, _>>();
+        let mut messages = self.fetch_messages_by_ids(&visible.filter_map(|row| row.message_id).collect::<Vec<_>>()).await?
+            .into_iter()
+            .map(|message| match message.is_deleted() && !user.has_permission(Permissions::MANAGE_MESSAGES) {
+                true => (message.id, message.into_tombstone()),
+                false => (message.id, message)
+            })
+            .collect::<HashMap<_, _>>();
+
+        Ok(rows.into_iter()
+            .map(|row| Bookmark {
+                id: row.id.into(),
+                user_id: row.user_id.into(),
+                thread_id: row.thread_id.into(),
+                message_id: row.message_id.map(Into::into),
+                folder: row.folder,
+                note: row.note,
+                thread: threads.get(&row.thread_id.into()).cloned(),
+                message: row.message_id.and_then(|id| messages.remove(&id.into()))
+            })
+            .collect())
+    }
+
+    /// Fetch bookmark folders of the user ordered by name.
+    ///
+    /// ### Returns
+    ///
+    /// [`Vec<BookmarkFolder>`] - Folders with the number of bookmarks in each.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_bookmark_folders(&self, user_id: Snowflake) -> HttpResult<Vec<BookmarkFolder>> {
+        sqlx::query_as!(BookmarkFolder, r#"
+                SELECT folder AS "name!", COUNT(*) AS "bookmark_count!" FROM bookmarks
+                WHERE user_id = $1 AND folder IS NOT NULL GROUP BY folder ORDER BY folder"#,
+            user_id.0
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)
+    }
+
+    /// Fetch the number of messages sent by the user.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_user_message_count(&self, user_id: Snowflake) -> HttpResult<i64> {
+        sqlx::query_scalar!(r#"SELECT COUNT(*) AS "count!" FROM messages WHERE author_id = $1 AND deleted_at IS NULL"#,
+            user_id.0
+        )
+            .fetch_one(&self.pool).await
+            .map_err(HttpError::Database)
+    }
+
+    /// Fetch the watch level the user chose for the thread.
+    ///
+    /// ### Returns
+    ///
+    /// * [`ThreadWatch`] with [`WatchLevel::Normal`] level if the user didn't choose any.
+    pub async fn fetch_thread_watch(&self, thread_id: Snowflake, user_id: Snowflake) -> ThreadWatch {
+        let level = sqlx::query_scalar!(r#"SELECT level FROM thread_watches WHERE thread_id = $1 AND user_id = $2"#,
+            thread_id.0, user_id.0
+        )
+            .fetch_optional(&self.pool).await
+            .ok().flatten().map(WatchLevel::from).unwrap_or_default();
+
+        ThreadWatch::new(thread_id, user_id, level)
+    }
+
+    /// Fetch the watch level the user chose for the category.
+    ///
+    /// ### Returns
+    ///
+    /// * [`CategoryWatch`] with [`WatchLevel::Normal`] level if the user didn't choose any.
+    pub async fn fetch_category_watch(&self, category_id: Snowflake, user_id: Snowflake) -> CategoryWatch {
+        let level = sqlx::query_scalar!(r#"SELECT level FROM category_watches WHERE category_id = $1 AND user_id = $2"#,
+            category_id.0, user_id.0
+        )
+            .fetch_optional(&self.pool).await
+            .ok().flatten().map(WatchLevel::from).unwrap_or_default();
+
+        CategoryWatch::new(category_id, user_id, level)
+    }
+
+    /// Fetch users who watch the thread and can see it.
+    ///
+    /// The watch level of the thread overrides the level of its category.
+    ///
+    /// ### Arguments
+    ///
+    /// * `thread` - The watched thread.
+    /// * `except` - The ID of the user to skip, usually the author of a new message.
+    ///
+    /// ### Returns
+    ///
+    /// [`Vec<Snowflake>`] - The IDs of the watchers.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_thread_watchers(&self, thread: &Thread, except: Snowflake) -> HttpResult<Vec<Snowflake>> {
+        let rows = sqlx::query_as!(Id, r#"
+                SELECT w.user_id AS "id!" FROM (
+                    SELECT user_id, level FROM thread_watches WHERE thread_id = $1
+                    UNION ALL
+                    SELECT user_id, level FROM category_watches c WHERE category_id = $2 AND NOT EXISTS (
+                        SELECT 1 FROM thread_watches t WHERE t.thread_id = $1 AND t.user_id = c.user_id
+                    )
+                ) w JOIN users u ON w.user_id = u.id
+                WHERE w.level = $3 AND w.user_id <> $4 AND ($5 OR u.permissions & $6 <> 0 OR EXISTS (
+                    SELECT 1 FROM thread_members m WHERE m.thread_id = $1 AND m.user_id = w.user_id
+                ))"#,
+            thread.id.0, thread.category_id.0, i32::from(WatchLevel::Watching), except.0,
+            thread.kind != ThreadType::Private, Permissions::MANAGE_THREADS.bits()
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)?;
+
+        Ok(rows.into_iter().map(|row| row.id).collect())
+    }
+
+    /// Fetch unread messages of the user in threads they have read before.
+    ///
+    /// Messages of the user are never unread, threads the user can't see anymore are skipped.
+    ///
+    /// ### Returns
+    ///
+    /// [`UnreadSummary`] - Unread and mention counts per category and thread.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_unread_summary(&self, user: &User) -> HttpResult<UnreadSummary> {
+        let threads = sqlx::query_as!(ThreadUnread, r#"
+                SELECT t.id AS thread_id, t.category_id, COUNT(*) AS "unread_count!",
+                COUNT(*) FILTER (WHERE EXISTS (
+                    SELECT 1 FROM message_mentions WHERE message_id = m.id AND user_id = $1
+                )) AS "mention_count!"
+                FROM read_states r
+                JOIN threads t ON r.thread_id = t.id
+                JOIN messages m ON m.thread_id = t.id AND m.id > r.last_read_message_id AND m.author_id <> $1 AND m.deleted_at IS NULL
+                WHERE r.user_id = $1 AND t.deleted_at IS NULL AND (t.type <> $2 OR $3 OR EXISTS (
+                    SELECT 1 FROM thread_members WHERE thread_id = t.id AND user_id = $1
+                ))
+                GROUP BY t.id ORDER BY t.category_id, t.id"#,
+            user.id.0, i32::from(ThreadType::Private), user.has_permission(Permissions::MANAGE_THREADS)
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)?;
+
+        Ok(UnreadSummary::new(threads))
+    }
+
+    /// Returns the [`DispatchTarget`] for events of the thread.
+    ///
+    /// ### Returns
+    ///
+    /// * [`DispatchTarget::Members`] for private threads, otherwise [`DispatchTarget::Global`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn fetch_dispatch_target(&self, thread: &Thread) -> HttpResult<DispatchTarget> {
+        if thread.kind != ThreadType::Private {
+            return Ok(DispatchTarget::Global)
         }
 
-        Ok(threads)
+        let members = sqlx::query_as!(Id, r#"SELECT user_id AS id FROM thread_members WHERE thread_id = $1"#,
+            thread.id.0
+        )
+            .fetch_all(&self.pool).await
+            .map_err(HttpError::Database)?;
+
+        Ok(DispatchTarget::Members(members.into_iter().map(|row| row.id).collect()))
     }
 
     /// Fetch session by ID.
@@ -0,0 +1,204 @@
+use {
+    std::sync::LazyLock,
+    regex::Regex,
+    serde::{Serialize, Deserialize},
+    sqlx::{
+        Decode, Postgres, PgExecutor,
+        postgres::PgValueRef
+    },
+    crate::{
+        models::user::{User, Permissions},
+        utils::snowflake::Snowflake,
+        routes::{HttpError, Result as HttpResult}
+    }
+};
+
+/// The maximum size of a custom emoji image in bytes
+pub const MAX_EMOJI_SIZE: usize = 256 * 1024;
+
+/// Content types custom emoji images can have
+pub const EMOJI_CONTENT_TYPES: [&str; 4] = ["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+/// Custom emoji in message content, e.g. `<:party:1234>`
+pub(crate) static CUSTOM_EMOJI_PATTERN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<:([A-Za-z0-9_]{2,32}):(\d{1,19})>").unwrap());
+
+/// Unicode emoji or custom emoji
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Emoji {
+    /// The ID of the custom emoji, `None` for unicode emoji
+    pub id: Option<Snowflake>,
+    /// The name of the custom emoji or the unicode emoji itself
+    pub name: String
+}
+
+impl Emoji {
+    /// Parses an emoji from a unicode emoji or a custom emoji in `name:id` format
+    pub fn parse(value: &str) -> Option<Self> {
+        if let Some((name, id)) = value.split_once(':') {
+            return match (is_valid_name(name), id.parse::<i64>()) {
+                (true, Ok(id)) => Some(Self { id: Some(id.into()), name: name.to_string() }),
+                _ => None
+            }
+        }
+
+        let is_unicode_emoji = !value.is_empty()
+            && value.len() <= 64
+            && !value.is_ascii()
+            && value.chars().all(|c| !c.is_alphabetic() && !c.is_whitespace() && !c.is_control());
+
+        is_unicode_emoji.then(|| Self { id: None, name: value.to_string() })
+    }
+
+    /// Returns custom emoji used in the content with `<:name:id>` syntax, without duplicates
+    pub fn parse_content(content: &str) -> Vec<Self> {
+        let mut emojis = Vec::<Self>::new();
+
+        for captures in CUSTOM_EMOJI_PATTERN.captures_iter(content) {
+            let Ok(id) = captures[2].parse::<i64>() else {
+                continue
+            };
+
+            if !emojis.iter().any(|emoji| emoji.id == Some(id.into())) {
+                emojis.push(Self { id: Some(id.into()), name: captures[1].to_string() });
+            }
+        }
+
+        emojis
+    }
+}
+
+/// Checks whether the name can be given to a custom emoji
+pub fn is_valid_name(name: &str) -> bool {
+    (2..=32).contains(&name.len())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Custom emoji used in a message content
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(transparent)]
+pub struct Emojis(pub Vec<Emoji>);
+
+impl Decode<'_, Postgres> for Emojis {
+    fn decode(
+        value: PgValueRef<'_>,
+    ) -> Result<Self, Box<dyn std::error::Error + 'static + Send + Sync>> {
+        let s: sqlx::types::Json<Emojis> =  sqlx::Decode::<'_, Postgres>::decode(value)?;
+        Ok(s.0)
+    }
+}
+
+/// Emoji uploaded by staff
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CustomEmoji {
+    /// The ID of the emoji
+    pub id: Snowflake,
+    /// The name of the emoji
+    pub name: String,
+    /// The media type of the emoji image
+    pub content_type: String,
+    /// The ID of the user who uploaded the emoji, `None` if they were deleted
+    pub creator_id: Option<Snowflake>,
+    /// Permissions required to use the emoji, `None` if anyone can use it
+    pub required_permissions: Option<Permissions>
+}
+
+impl From<&CustomEmoji> for Emoji {
+    fn from(emoji: &CustomEmoji) -> Self {
+        Self {
+            id: Some(emoji.id),
+            name: emoji.name.clone()
+        }
+    }
+}
+
+impl CustomEmoji {
+    /// Create a new [`CustomEmoji`] object
+    pub fn new(id: Snowflake, name: &str, content_type: &str, creator_id: Snowflake) -> Self {
+        Self {
+            id,
+            name: name.to_string(),
+            content_type: content_type.to_string(),
+            creator_id: Some(creator_id),
+            required_permissions: None
+        }
+    }
+
+    /// Set permissions required to use the emoji
+    pub fn with_required_permissions(mut self, permissions: Option<Permissions>) -> Self {
+        self.required_permissions = permissions.filter(|permissions| !permissions.is_empty());
+        self
+    }
+
+    /// Checks whether the user can use the emoji in messages and reactions
+    pub fn can_use(&self, user: &User) -> bool {
+        self.required_permissions.is_none_or(|permissions| user.has_permission(permissions))
+    }
+
+    /// The key the image is stored under in [`Storage`]
+    ///
+    /// [`Storage`]: crate::storage::Storage
+    pub fn key(&self) -> String {
+        format!("emojis/{}", self.id.0)
+    }
+
+    /// Save a new emoji in the database.
+    ///
+    /// ### Returns
+    ///
+    /// * [`CustomEmoji`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::InvalidOperation`] - If there is an emoji with the same name.
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn save<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Self> {
+        sqlx::query!(r#"INSERT INTO emojis(id, name, content_type, creator_id, required_permissions) VALUES ($1, $2, $3, $4, $5)"#,
+            self.id.0, self.name, self.content_type, self.creator_id.map(i64::from),
+            self.required_permissions.map(|permissions| permissions.bits())
+        )
+            .execute(executor).await
+            .map(|_| self)
+            .map_err(|err| match err.as_database_error() {
+                Some(err) if err.is_unique_violation() => HttpError::InvalidOperation("There is already an emoji with this name".to_string()),
+                _ => HttpError::Database(err)
+            })
+    }
+
+    /// Save changes of the emoji in the database, renaming reactions with it.
+    ///
+    /// ### Returns
+    ///
+    /// * [`CustomEmoji`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::InvalidOperation`] - If there is an emoji with the same name.
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn edit<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Self> {
+        sqlx::query!(r#"
+                WITH emoji AS (UPDATE emojis SET name = $1, required_permissions = $2 WHERE id = $3 RETURNING id)
+                UPDATE message_reactions SET emoji_name = $1 WHERE emoji_id = (SELECT id FROM emoji)"#,
+            self.name, self.required_permissions.map(|permissions| permissions.bits()), self.id.0
+        )
+            .execute(executor).await
+            .map(|_| self)
+            .map_err(|err| match err.as_database_error() {
+                Some(err) if err.is_unique_violation() => HttpError::InvalidOperation("There is already an emoji with this name".to_string()),
+                _ => HttpError::Database(err)
+            })
+    }
+
+    /// Delete the emoji with reactions using it.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn delete<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<()> {
+        sqlx::query!(r#"DELETE FROM emojis WHERE id = $1"#,
+            self.id.0
+        )
+            .execute(executor).await
+            .map(|_| ())
+            .map_err(HttpError::Database)
+    }
+}
@@ -6,6 +6,12 @@ use {
     crate::{
         models::{
             message::Message,
+            poll::Poll,
+            read_state::ReadState,
+            report::Report,
+            notification::Notification,
+            bookmark::Bookmark,
+            emoji::Emoji,
             thread::Thread,
             user::User
         },
@@ -94,6 +100,7 @@ pub enum IncomingGatewayPacket {
 }
 
 #[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum OutgoingGatewayPacket {
     Hello(GatewayHelloPacket),
     Event(GatewayEvent),
@@ -108,6 +115,7 @@ impl Serialize for OutgoingGatewayPacket {
     {
         #[derive(Debug, Serialize)]
         #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+        #[allow(clippy::large_enum_variant)]
         enum InternalOutgoingGatewayPacket {
             Hello { d: GatewayHelloPacket },
             Event(GatewayEvent),
@@ -132,12 +140,21 @@ pub struct Ready {
     pub users: HashMap<Snowflake, Option<User>>,
 }
 
-impl Into<GatewayEvent> for Ready {
-    fn into(self) -> GatewayEvent {
-        GatewayEvent::Ready(self)
+impl From<Ready> for GatewayEvent {
+    fn from(value: Ready) -> Self {
+        GatewayEvent::Ready(value)
     }
 }
 
+/// Messages deleted at once from a thread.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DeletedMessages {
+    /// The thread the messages were deleted from.
+    pub thread_id: Snowflake,
+    /// IDs of the deleted messages.
+    pub message_ids: Vec<Snowflake>,
+}
+
 #[derive(Clone, Debug, Deserialize, Hash, Serialize)]
 pub struct GatewayHelloPacket {
     pub heartbeat_interval: u64,
@@ -156,12 +173,40 @@ pub enum GatewayEvent {
     ThreadDelete {
         thread_id: Snowflake,
     },
+    ThreadMemberAdd {
+        thread_id: Snowflake,
+        user: User,
+    },
+    ThreadMemberRemove {
+        thread_id: Snowflake,
+        user_id: Snowflake,
+    },
+    PollUpdate(Poll),
     MessageCreate(Message),
+    WatchedMessageCreate(Message),
+    ReplyCreate(Message),
+    MentionCreate(Message),
+    ReadStateUpdate(ReadState),
     MessageUpdate(Message),
     MessageDelete {
         thread_id: Snowflake,
         message_id: Snowflake,
     },
+    MessageDeleteBulk {
+        messages: Vec<DeletedMessages>,
+    },
+    MessageReactionAdd {
+        thread_id: Snowflake,
+        message_id: Snowflake,
+        user_id: Snowflake,
+        emoji: Emoji,
+    },
+    MessageReactionRemove {
+        thread_id: Snowflake,
+        message_id: Snowflake,
+        user_id: Snowflake,
+        emoji: Emoji,
+    },
     ThreadTypingStart {
         thread_id: Snowflake,
         user_id: Snowflake,
@@ -171,4 +216,12 @@ pub enum GatewayEvent {
         user_id: Snowflake,
     },
     UserUpdate(User),
+    ReportCreate(Report),
+    ReportUpdate(Report),
+    NotificationCreate(Notification),
+    BookmarkCreate(Bookmark),
+    BookmarkUpdate(Bookmark),
+    BookmarkDelete {
+        bookmark_id: Snowflake,
+    },
 }
\ No newline at end of file
@@ -0,0 +1,117 @@
+use {
+    serde::{Serialize, Deserialize},
+    sqlx::PgExecutor,
+    crate::{
+        models::user::User,
+        utils::snowflake::Snowflake,
+        routes::{HttpError, Result as HttpResult}
+    }
+};
+
+/// The review status of the join request
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum JoinRequestStatus {
+    /// The answer is waiting for review
+    #[default]
+    Pending,
+    /// The answer was accepted and the user became a member of the thread
+    Approved,
+    /// The answer was rejected
+    Rejected
+}
+
+impl From<i32> for JoinRequestStatus {
+    fn from(x: i32) -> Self {
+        match x {
+            1 => JoinRequestStatus::Approved,
+            2 => JoinRequestStatus::Rejected,
+            _ => JoinRequestStatus::Pending
+        }
+    }
+}
+
+impl From<JoinRequestStatus> for i32 {
+    fn from(x: JoinRequestStatus) -> Self {
+        match x {
+            JoinRequestStatus::Pending => 0,
+            JoinRequestStatus::Approved => 1,
+            JoinRequestStatus::Rejected => 2
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JoinRequest {
+    /// The ID of the thread the user wants to join
+    pub thread_id: Snowflake,
+    /// The user who wants to join
+    pub user: User,
+    /// The answer to the thread's question
+    pub answer: String,
+    /// The review status of the request
+    pub status: JoinRequestStatus,
+    /// The ID of the user who reviewed the request
+    pub reviewer_id: Option<Snowflake>
+}
+
+impl JoinRequest {
+    /// Create a new [`JoinRequest`] object
+    pub fn new(thread_id: Snowflake, user: User, answer: &str) -> Self {
+        Self {
+            thread_id,
+            user,
+            answer: answer.to_string(),
+            status: JoinRequestStatus::Pending,
+            reviewer_id: None
+        }
+    }
+
+    /// Save a new join request in the database, replacing previous request of the user unless it was rejected.
+    ///
+    /// ### Returns
+    ///
+    /// * [`JoinRequest`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::InvalidOperation`] - If the previous request of the user was rejected.
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn save<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Self> {
+        let result = sqlx::query!(r#"
+                INSERT INTO thread_join_requests(thread_id, user_id, answer, status) VALUES ($1, $2, $3, $4)
+                ON CONFLICT (thread_id, user_id) DO UPDATE SET answer = $3, status = $4, reviewer_id = NULL
+                WHERE thread_join_requests.status <> $5"#,
+            self.thread_id.0, self.user.id.0, self.answer, i32::from(self.status), i32::from(JoinRequestStatus::Rejected)
+        )
+            .execute(executor).await
+            .map_err(HttpError::Database)?;
+
+        if result.rows_affected() == 0 {
+            return Err(HttpError::InvalidOperation("Your answer to the thread's question was rejected".to_string()))
+        }
+
+        Ok(self)
+    }
+
+    /// Review the join request.
+    ///
+    /// ### Returns
+    ///
+    /// * [`JoinRequest`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn review<'a, E: PgExecutor<'a>>(mut self, executor: E, status: JoinRequestStatus, reviewer_id: Snowflake) -> HttpResult<Self> {
+        sqlx::query!(r#"UPDATE thread_join_requests SET status = $1, reviewer_id = $2 WHERE thread_id = $3 AND user_id = $4"#,
+            i32::from(status), reviewer_id.0, self.thread_id.0, self.user.id.0
+        )
+            .execute(executor).await
+            .map_err(HttpError::Database)?;
+
+        self.status = status;
+        self.reviewer_id = Some(reviewer_id);
+        Ok(self)
+    }
+}
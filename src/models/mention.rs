@@ -0,0 +1,72 @@
+use {
+    chrono::Duration,
+    serde::{Serialize, Deserialize},
+    sqlx::{
+        Decode, Postgres, PgExecutor,
+        postgres::PgValueRef
+    },
+    crate::{
+        models::user::User,
+        utils::snowflake::Snowflake,
+        routes::{HttpError, Result as HttpResult}
+    }
+};
+
+/// The maximum number of users a message can mention
+pub const MAX_MESSAGE_MENTIONS: usize = 20;
+
+/// The maximum number of mentions a user can send within [`MENTION_RATE_LIMIT_WINDOW`]
+pub const MENTION_RATE_LIMIT: i64 = 50;
+
+/// The period mentions are counted in for the rate limit
+pub const MENTION_RATE_LIMIT_WINDOW: Duration = Duration::minutes(10);
+
+/// Users mentioned in a message ordered by ID
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(transparent)]
+pub struct Mentions(pub Vec<User>);
+
+impl Mentions {
+    /// Checks whether the user is mentioned
+    pub fn contains(&self, user_id: Snowflake) -> bool {
+        self.0.iter().any(|user| user.id == user_id)
+    }
+
+    /// Replace mentions of the message in the database.
+    ///
+    /// ### Returns
+    ///
+    /// * IDs of users who weren't mentioned in the message before on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::UnknownMessage`] - If the message is not found.
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn save<'a, E: PgExecutor<'a>>(&self, executor: E, message_id: Snowflake) -> HttpResult<Vec<Snowflake>> {
+        let user_ids = self.0.iter().map(|user| user.id.0).collect::<Vec<_>>();
+
+        sqlx::query_scalar!(r#"
+                WITH removed AS (
+                    DELETE FROM message_mentions WHERE message_id = $1 AND user_id <> ALL($2)
+                )
+                INSERT INTO message_mentions(message_id, user_id) SELECT $1, UNNEST($2::BIGINT[])
+                ON CONFLICT DO NOTHING RETURNING user_id"#,
+            message_id.0, &user_ids
+        )
+            .fetch_all(executor).await
+            .map(|user_ids| user_ids.into_iter().map(Into::into).collect())
+            .map_err(|err| match err.as_database_error().and_then(|err| err.constraint()) {
+                Some("message_mentions_message_id_fkey") => HttpError::UnknownMessage,
+                _ => HttpError::Database(err)
+            })
+    }
+}
+
+impl Decode<'_, Postgres> for Mentions {
+    fn decode(
+        value: PgValueRef<'_>,
+    ) -> Result<Self, Box<dyn std::error::Error + 'static + Send + Sync>> {
+        let s: sqlx::types::Json<Mentions> =  sqlx::Decode::<'_, Postgres>::decode(value)?;
+        Ok(s.0)
+    }
+}
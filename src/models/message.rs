@@ -8,8 +8,14 @@ use {
     serde::{Serialize, Deserialize},
     crate::{
         bitflags_convector,
-        models::user::User,
-        utils::snowflake::Snowflake,
+        models::{
+            user::User,
+            reaction::{Reactions, MAX_MESSAGE_REACTIONS},
+            emoji::{Emoji, Emojis},
+            mention::Mentions,
+            attachment::{Attachment, Attachments}
+        },
+        utils::{snowflake::Snowflake, markdown},
         routes::{HttpError, Result as HttpResult}
     }
 };
@@ -26,6 +32,39 @@ bitflags! {
 
 bitflags_convector!(MessageFlags, i32);
 
+/// The maximum length of the content in [`MessagePreview`]
+pub const MESSAGE_PREVIEW_LENGTH: usize = 100;
+
+/// Compact preview of a message a reply refers to
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessagePreview {
+    /// The ID of the message
+    pub id: Snowflake,
+    /// The author of the message
+    pub author: User,
+    /// Contents of the message, cut to [`MESSAGE_PREVIEW_LENGTH`] characters
+    pub content: String
+}
+
+impl From<&Message> for MessagePreview {
+    fn from(message: &Message) -> Self {
+        Self {
+            id: message.id,
+            author: message.author.clone(),
+            content: message.content.chars().take(MESSAGE_PREVIEW_LENGTH).collect()
+        }
+    }
+}
+
+impl Decode<'_, Postgres> for MessagePreview {
+    fn decode(
+        value: PgValueRef<'_>,
+    ) -> Result<Self, Box<dyn std::error::Error + 'static + Send + Sync>> {
+        let s: sqlx::types::Json<MessagePreview> =  sqlx::Decode::<'_, Postgres>::decode(value)?;
+        Ok(s.0)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Message {
     /// The ID of the message
@@ -39,9 +78,64 @@ pub struct Message {
     /// The message's flags
     pub flags: MessageFlags,
     /// The source of a reply message
-    pub referenced_message_id: Option<i64>,
+    pub referenced_message_id: Option<Snowflake>,
+    /// Preview of the source of a reply message, `None` if the source was deleted
+    pub referenced_message: Option<MessagePreview>,
+    /// Custom emoji used in the content
+    pub emojis: Emojis,
+    /// Users mentioned in the content
+    pub mentions: Mentions,
+    /// Reactions of the message aggregated by emoji
+    pub reactions: Reactions,
+    /// Files attached to the message
+    pub attachments: Attachments,
+    /// The number of times the content was edited
+    pub edit_count: i32,
     /// When this message was last edited
-    pub updated_at: Option<DateTime<Utc>>
+    pub updated_at: Option<DateTime<Utc>>,
+    /// When this message was deleted, it's purged after the retention period
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// The ID of the user who deleted the message
+    pub deleted_by: Option<Snowflake>
+}
+
+/// The form message content is returned in, besides the raw `content`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentFormat {
+    /// Only the raw Markdown content
+    #[default]
+    Markdown,
+    /// Sanitised HTML rendered from the content
+    Html,
+    /// The content without any formatting
+    Text
+}
+
+/// Message with its content rendered to the requested [`ContentFormat`]
+#[derive(Serialize, Debug, Clone)]
+pub struct RenderedMessage {
+    #[serde(flatten)]
+    pub message: Message,
+    /// The content rendered to HTML, see [`markdown::to_html`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    /// The content without formatting, see [`markdown::to_text`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>
+}
+
+/// Previous content of an edited message
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessageRevision {
+    /// The ID of the message
+    pub message_id: Snowflake,
+    /// The number of the edit that replaced the content, starting from 1
+    pub revision: i32,
+    /// Contents of the message before the edit
+    pub content: String,
+    /// When the content was replaced
+    pub edited_at: DateTime<Utc>
 }
 
 impl Message {
@@ -54,10 +148,52 @@ impl Message {
             flags: flags.unwrap_or(MessageFlags::empty()),
             content: content.to_string(),
             referenced_message_id: None,
-            updated_at: None
+            referenced_message: None,
+            emojis: Emojis::default(),
+            mentions: Mentions::default(),
+            reactions: Reactions::default(),
+            attachments: Attachments::default(),
+            edit_count: 0,
+            updated_at: None,
+            deleted_at: None,
+            deleted_by: None
         }
     }
 
+    /// Set the message the message replies to
+    pub fn with_reference(mut self, message: &Message) -> Self {
+        self.referenced_message_id = Some(message.id);
+        self.referenced_message = Some(message.into());
+        self
+    }
+
+    /// Checks whether the message was deleted
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    /// Hide contents of the deleted message, leaving a tombstone for users who can't see deleted messages
+    pub fn into_tombstone(mut self) -> Self {
+        self.content = String::new();
+        self.referenced_message = None;
+        self.emojis = Emojis::default();
+        self.mentions = Mentions::default();
+        self.reactions = Reactions::default();
+        self.attachments = Attachments::default();
+        self
+    }
+
+    /// Render the content to the requested format
+    pub fn render(self, format: ContentFormat) -> RenderedMessage {
+        let (html, text) = match format {
+            ContentFormat::Markdown => (None, None),
+            ContentFormat::Html => (Some(markdown::to_html(&self.content, &self.mentions)), None),
+            ContentFormat::Text => (None, Some(markdown::to_text(&self.content, &self.mentions)))
+        };
+
+        RenderedMessage { message: self, html, text }
+    }
+
     /// Checks whether message has required [`MessageFlags`]
     pub fn is(self, flag: MessageFlags) -> bool {
         self.flags.contains(flag)
@@ -74,45 +210,226 @@ impl Message {
     /// * [`HttpError::UnknownMessage`] - If the referenced message is not found.
     pub async fn save<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Self> {
         sqlx::query!(r#"INSERT INTO messages(id, author_id, content, thread_id, referenced_message_id, flags) VALUES ($1, $2, $3, $4, $5, $6)"#,
-            self.id.0, self.author.id.0, self.content, self.thread_id.0, self.referenced_message_id, self.flags.bits()
+            self.id.0, self.author.id.0, self.content, self.thread_id.0, self.referenced_message_id.map(i64::from), self.flags.bits()
         )
             .execute(executor).await
             .map(|_| self)
             .map_err(|_| HttpError::UnknownMessage)
     }
 
-    /// Edit an old message in the database.
+    /// Edit an old message in the database, keeping the previous content as a [`MessageRevision`].
     ///
     /// ## Returns
     ///
-    /// * [`Message`] on success, otherwise [`HttpError`].
+    /// * [`Message`] with the new content and edit time on success, otherwise [`HttpError`].
     ///
     /// ## Errors
     ///
     /// * [`HttpError::UnknownMessage`] - If the message is not found.
-    pub async fn edit<'a, E: PgExecutor<'a>>(self, executor: E, content: &str) -> HttpResult<Self> {
-        sqlx::query!(r#"UPDATE messages SET content = $1 WHERE id = $2"#,
+    pub async fn edit<'a, E: PgExecutor<'a>>(mut self, executor: E, content: &str) -> HttpResult<Self> {
+        let row = sqlx::query!(r#"
+                UPDATE messages SET content = $1 WHERE id = $2
+                RETURNING content, edit_count, updated_at, message_emojis_json(content) AS "emojis!: Emojis""#,
             content, self.id.0
+        )
+            .fetch_one(executor).await
+            .map_err(|_| HttpError::UnknownMessage)?;
+
+        self.content = row.content;
+        self.edit_count = row.edit_count;
+        self.updated_at = row.updated_at;
+        self.emojis = row.emojis;
+        Ok(self)
+    }
+
+    /// Replace flags of the message.
+    ///
+    /// ## Returns
+    ///
+    /// * [`Message`] on success, otherwise [`HttpError`].
+    ///
+    /// ## Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn set_flags<'a, E: PgExecutor<'a>>(mut self, executor: E, flags: MessageFlags) -> HttpResult<Self> {
+        sqlx::query!(r#"UPDATE messages SET flags = $1 WHERE id = $2"#,
+            flags.bits(), self.id.0
         )
             .execute(executor).await
-            .map(|_| self)
-            .map_err(|_| HttpError::UnknownMessage)
+            .map_err(HttpError::Database)?;
+
+        self.flags = flags;
+        Ok(self)
+    }
+
+    /// Add a reaction of the user to the message.
+    ///
+    /// ## Returns
+    ///
+    /// * `true` if the reaction was added, `false` if the user already reacted with the emoji.
+    ///
+    /// The limit of distinct reactions is enforced by the database, so concurrent reactions can't exceed it.
+    ///
+    /// ## Errors
+    ///
+    /// * [`HttpError::InvalidOperation`] - If the message has too many distinct reactions.
+    /// * [`HttpError::UnknownEmoji`] - If the custom emoji is not found.
+    /// * [`HttpError::UnknownMessage`] - If the message is not found.
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn add_reaction<'a, E: PgExecutor<'a>>(&self, executor: E, user_id: Snowflake, emoji: &Emoji) -> HttpResult<bool> {
+        sqlx::query!(r#"
+                INSERT INTO message_reactions(message_id, user_id, emoji_id, emoji_name) VALUES ($1, $2, $3, $4)
+                ON CONFLICT (message_id, emoji_name, COALESCE(emoji_id, 0), user_id) DO NOTHING"#,
+            self.id.0, user_id.0, emoji.id.map(i64::from), emoji.name
+        )
+            .execute(executor).await
+            .map(|result| result.rows_affected() > 0)
+            .map_err(|err| match err.as_database_error().and_then(|err| err.constraint()) {
+                Some("message_reactions_limit") => HttpError::InvalidOperation(
+                    format!("Message can't have more than {} distinct reactions", MAX_MESSAGE_REACTIONS)
+                ),
+                Some("fk_message_reactions_emoji") => HttpError::UnknownEmoji,
+                Some("message_reactions_message_id_fkey") => HttpError::UnknownMessage,
+                _ => HttpError::Database(err)
+            })
     }
 
-    /// Delete the message.
+    /// Remove a reaction of the user from the message.
+    ///
+    /// ## Returns
+    ///
+    /// * `true` if the reaction was removed, `false` if the user didn't react with the emoji.
     ///
     /// ## Errors
     ///
     /// * [`HttpError::Database`] - If the database query fails.
-    pub async fn delete<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<()> {
-        sqlx::query!(r#"DELETE FROM messages WHERE id = $1 AND thread_id = $2"#,
-            self.id.0, self.thread_id.0
+    pub async fn remove_reaction<'a, E: PgExecutor<'a>>(&self, executor: E, user_id: Snowflake, emoji: &Emoji) -> HttpResult<bool> {
+        sqlx::query!(r#"
+                DELETE FROM message_reactions
+                WHERE message_id = $1 AND user_id = $2 AND COALESCE(emoji_id, 0) = COALESCE($3::BIGINT, 0) AND emoji_name = $4"#,
+            self.id.0, user_id.0, emoji.id.map(i64::from), emoji.name
         )
             .execute(executor).await
-            .map(|_| ())
+            .map(|result| result.rows_affected() > 0)
             .map_err(HttpError::Database)
     }
 
+    /// Mark the message as deleted, keeping it until the retention period ends.
+    ///
+    /// ## Returns
+    ///
+    /// * [`Message`] on success, otherwise [`HttpError`].
+    ///
+    /// ## Errors
+    ///
+    /// * [`HttpError::UnknownMessage`] - If the message is not found or already deleted.
+    pub async fn soft_delete<'a, E: PgExecutor<'a>>(mut self, executor: E, user_id: Snowflake) -> HttpResult<Self> {
+        let deleted_at = sqlx::query_scalar!(r#"
+                UPDATE messages SET deleted_at = CURRENT_TIMESTAMP, deleted_by = $1
+                WHERE id = $2 AND thread_id = $3 AND deleted_at IS NULL RETURNING deleted_at AS "deleted_at!""#,
+            user_id.0, self.id.0, self.thread_id.0
+        )
+            .fetch_optional(executor).await
+            .map_err(HttpError::Database)?
+            .ok_or(HttpError::UnknownMessage)?;
+
+        self.deleted_at = Some(deleted_at);
+        self.deleted_by = Some(user_id);
+        Ok(self)
+    }
+
+    /// Mark messages of the thread as deleted.
+    ///
+    /// Should be run in a transaction, so none of the messages is deleted on errors.
+    ///
+    /// ## Returns
+    ///
+    /// * The IDs of the deleted messages with IDs of their authors on success, otherwise [`HttpError`].
+    ///
+    /// ## Errors
+    ///
+    /// * [`HttpError::UnknownMessage`] - If any of the messages is not found or already deleted.
+    /// * [`HttpError::Undeletable`] - If any of the messages has [`MessageFlags::UNDELETEABLE`].
+    pub async fn soft_delete_bulk<'a, E: PgExecutor<'a>>(executor: E, thread_id: Snowflake, message_ids: &[i64], user_id: Snowflake) -> HttpResult<Vec<(Snowflake, Snowflake)>> {
+        let rows = sqlx::query!(r#"
+                UPDATE messages SET deleted_at = CURRENT_TIMESTAMP, deleted_by = $1
+                WHERE thread_id = $2 AND id = ANY($3) AND deleted_at IS NULL RETURNING id, author_id, flags"#,
+            user_id.0, thread_id.0, message_ids
+        )
+            .fetch_all(executor).await
+            .map_err(HttpError::Database)?;
+
+        if rows.iter().any(|row| MessageFlags::from(row.flags).contains(MessageFlags::UNDELETEABLE)) {
+            return Err(HttpError::Undeletable)
+        }
+
+        if rows.len() != message_ids.len() {
+            return Err(HttpError::UnknownMessage)
+        }
+
+        Ok(rows.into_iter().map(|row| (row.id.into(), row.author_id.into())).collect())
+    }
+
+    /// Mark messages the user sent since the given time as deleted, except ones with [`MessageFlags::UNDELETEABLE`].
+    ///
+    /// ## Returns
+    ///
+    /// * The IDs of the threads and deleted messages on success, otherwise [`HttpError`].
+    ///
+    /// ## Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn soft_delete_by_author<'a, E: PgExecutor<'a>>(executor: E, author_id: Snowflake, since: DateTime<Utc>, user_id: Snowflake) -> HttpResult<Vec<(Snowflake, Snowflake)>> {
+        sqlx::query!(r#"
+                UPDATE messages SET deleted_at = CURRENT_TIMESTAMP, deleted_by = $1
+                WHERE author_id = $2 AND id >= $3 AND deleted_at IS NULL AND flags & $4 = 0 RETURNING thread_id, id"#,
+            user_id.0, author_id.0, Snowflake::from_timestamp(since).0, MessageFlags::UNDELETEABLE.bits()
+        )
+            .fetch_all(executor).await
+            .map(|rows| rows.into_iter().map(|row| (row.thread_id.into(), row.id.into())).collect())
+            .map_err(HttpError::Database)
+    }
+
+    /// Restore the deleted message.
+    ///
+    /// ## Returns
+    ///
+    /// * [`Message`] on success, otherwise [`HttpError`].
+    ///
+    /// ## Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn restore<'a, E: PgExecutor<'a>>(mut self, executor: E) -> HttpResult<Self> {
+        sqlx::query!(r#"UPDATE messages SET deleted_at = NULL, deleted_by = NULL WHERE id = $1"#,
+            self.id.0
+        )
+            .execute(executor).await
+            .map_err(HttpError::Database)?;
+
+        self.deleted_at = None;
+        self.deleted_by = None;
+        Ok(self)
+    }
+
+    /// Permanently delete messages deleted before the given time.
+    ///
+    /// ## Returns
+    ///
+    /// * Attachments of the purged messages, their files have to be removed from the storage.
+    ///
+    /// ## Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn purge_deleted<'a, E: PgExecutor<'a>>(executor: E, before: DateTime<Utc>) -> HttpResult<Vec<Attachment>> {
+        // The outer query sees attachments as they were before the messages were deleted
+        sqlx::query_as!(Attachment, r#"
+                WITH purged AS (DELETE FROM messages WHERE deleted_at < $1 RETURNING id)
+                SELECT a.* FROM attachments a WHERE a.message_id IN (SELECT id FROM purged)"#,
+            before
+        )
+            .fetch_all(executor).await
+            .map_err(HttpError::Database)
+    }
 }
 
 impl Decode<'_, Postgres> for Message {
@@ -16,6 +16,19 @@ pub mod message;
 pub mod requests;
 pub mod gateway;
 pub mod session;
+pub mod join_request;
+pub mod tag;
+pub mod poll;
+pub mod watch;
+pub mod read_state;
+pub mod reaction;
+pub mod emoji;
+pub mod attachment;
+pub mod report;
+pub mod mention;
+pub mod notification;
+pub mod search;
+pub mod bookmark;
 
 #[derive(Serialize, Debug, Clone, PartialEq)]
 pub struct UserCredentials(pub Session, pub User);
@@ -0,0 +1,210 @@
+use {
+    std::collections::BTreeMap,
+    serde::{Serialize, Deserialize},
+    sqlx::PgExecutor,
+    crate::{
+        models::emoji::Emoji,
+        utils::snowflake::Snowflake,
+        routes::{HttpError, Result as HttpResult}
+    }
+};
+
+/// What the notification is about
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationType {
+    /// Someone replied to a message of the user
+    Reply,
+    /// Someone mentioned the user
+    Mention,
+    /// Someone reacted to a message of the user
+    Reaction,
+    /// A moderator deleted messages of the user
+    ModerationAction,
+    /// Someone moved a thread of the user to another category
+    ThreadMove
+}
+
+impl NotificationType {
+    /// All notification types
+    pub const ALL: [NotificationType; 5] = [
+        NotificationType::Reply,
+        NotificationType::Mention,
+        NotificationType::Reaction,
+        NotificationType::ModerationAction,
+        NotificationType::ThreadMove
+    ];
+}
+
+impl From<i32> for NotificationType {
+    fn from(x: i32) -> Self {
+        match x {
+            1 => NotificationType::Mention,
+            2 => NotificationType::Reaction,
+            3 => NotificationType::ModerationAction,
+            4 => NotificationType::ThreadMove,
+            _ => NotificationType::Reply
+        }
+    }
+}
+
+impl From<NotificationType> for i32 {
+    fn from(x: NotificationType) -> Self {
+        match x {
+            NotificationType::Reply => 0,
+            NotificationType::Mention => 1,
+            NotificationType::Reaction => 2,
+            NotificationType::ModerationAction => 3,
+            NotificationType::ThreadMove => 4
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Notification {
+    /// The ID of the notification
+    pub id: Snowflake,
+    /// The ID of the notified user
+    pub user_id: Snowflake,
+    /// What the notification is about
+    #[serde(rename = "type")]
+    pub kind: NotificationType,
+    /// The ID of the user who caused the notification
+    pub actor_id: Option<Snowflake>,
+    /// The ID of the thread the notification is about
+    pub thread_id: Option<Snowflake>,
+    /// The ID of the message the notification is about
+    pub message_id: Option<Snowflake>,
+    /// The emoji of the reaction
+    pub emoji: Option<Emoji>,
+    /// Whether the user has read the notification
+    pub read: bool
+}
+
+impl Notification {
+    /// Create a new [`Notification`] object
+    pub fn new(id: Snowflake, user_id: Snowflake, kind: NotificationType, actor_id: Snowflake) -> Self {
+        Self {
+            id,
+            user_id,
+            kind,
+            actor_id: Some(actor_id),
+            thread_id: None,
+            message_id: None,
+            emoji: None,
+            read: false
+        }
+    }
+
+    /// Set the thread the notification is about
+    pub fn with_thread(mut self, thread_id: Snowflake) -> Self {
+        self.thread_id = Some(thread_id);
+        self
+    }
+
+    /// Set the message the notification is about
+    pub fn with_message(mut self, thread_id: Snowflake, message_id: Snowflake) -> Self {
+        self.thread_id = Some(thread_id);
+        self.message_id = Some(message_id);
+        self
+    }
+
+    /// Set the emoji of the reaction
+    pub fn with_emoji(mut self, emoji: Emoji) -> Self {
+        self.emoji = Some(emoji);
+        self
+    }
+
+    /// Save a new notification in the database, unless the user disabled notifications of its type.
+    ///
+    /// Reactions the user was already notified about are skipped, so reacting again after removing the reaction
+    /// doesn't notify twice.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Notification`] if saved, `None` if the user disabled notifications of its type or was already notified,
+    ///   otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn save<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Option<Self>> {
+        let result = sqlx::query!(r#"
+                INSERT INTO notifications(id, user_id, type, actor_id, thread_id, message_id, emoji_id, emoji_name)
+                SELECT $1, $2, $3, $4, $5, $6, $7, $8 WHERE NOT EXISTS (
+                    SELECT 1 FROM notification_settings WHERE user_id = $2 AND type = $3 AND NOT enabled
+                ) AND NOT ($9 AND EXISTS (
+                    SELECT 1 FROM notifications WHERE user_id = $2 AND type = $3 AND actor_id = $4 AND message_id = $6
+                        AND COALESCE(emoji_id, 0) = COALESCE($7::BIGINT, 0) AND emoji_name = $8::VARCHAR
+                ))"#,
+            self.id.0, self.user_id.0, i32::from(self.kind), self.actor_id.map(i64::from), self.thread_id.map(i64::from),
+            self.message_id.map(i64::from), self.emoji.as_ref().and_then(|emoji| emoji.id.map(i64::from)),
+            self.emoji.as_ref().map(|emoji| emoji.name.clone()), self.kind == NotificationType::Reaction
+        )
+            .execute(executor).await
+            .map_err(HttpError::Database)?;
+
+        Ok((result.rows_affected() > 0).then_some(self))
+    }
+
+    /// Mark the notification as read or unread.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Notification`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn set_read<'a, E: PgExecutor<'a>>(mut self, executor: E, read: bool) -> HttpResult<Self> {
+        sqlx::query!(r#"UPDATE notifications SET read = $1 WHERE id = $2"#,
+            read, self.id.0
+        )
+            .execute(executor).await
+            .map_err(HttpError::Database)?;
+
+        self.read = read;
+        Ok(self)
+    }
+
+    /// Mark all notifications of the user as read, up to the given notification if any.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn read_all<'a, E: PgExecutor<'a>>(executor: E, user_id: Snowflake, until: Option<Snowflake>) -> HttpResult<()> {
+        sqlx::query!(r#"UPDATE notifications SET read = TRUE WHERE user_id = $1 AND id <= $2 AND NOT read"#,
+            user_id.0, until.map_or(i64::MAX, Into::into)
+        )
+            .execute(executor).await
+            .map(|_| ())
+            .map_err(HttpError::Database)
+    }
+}
+
+/// Notification types the user receives, types which are missing are enabled
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(transparent)]
+pub struct NotificationSettings(pub BTreeMap<NotificationType, bool>);
+
+impl NotificationSettings {
+    /// Save the settings of the user in the database, replacing only the types present in the settings.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn save<'a, E: PgExecutor<'a>>(&self, executor: E, user_id: Snowflake) -> HttpResult<()> {
+        let (types, enabled): (Vec<i32>, Vec<bool>) = self.0.iter()
+            .map(|(kind, enabled)| (i32::from(*kind), *enabled))
+            .unzip();
+
+        sqlx::query!(r#"
+                INSERT INTO notification_settings(user_id, type, enabled) SELECT $1, * FROM UNNEST($2::INTEGER[], $3::BOOLEAN[])
+                ON CONFLICT (user_id, type) DO UPDATE SET enabled = EXCLUDED.enabled"#,
+            user_id.0, &types, &enabled
+        )
+            .execute(executor).await
+            .map(|_| ())
+            .map_err(HttpError::Database)
+    }
+}
@@ -0,0 +1,152 @@
+use {
+    chrono::{DateTime, Utc},
+    serde::{Serialize, Deserialize},
+    sqlx::PgExecutor,
+    crate::{
+        utils::snowflake::Snowflake,
+        routes::{HttpError, Result as HttpResult}
+    }
+};
+
+/// The minimum number of options a poll can have
+pub const MIN_POLL_OPTIONS: u64 = 2;
+/// The maximum number of options a poll can have
+pub const MAX_POLL_OPTIONS: u64 = 10;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PollOption {
+    /// The ID of the option
+    pub id: Snowflake,
+    /// The text of the option
+    pub text: String,
+    /// The number of votes for the option
+    pub vote_count: i64,
+    /// The IDs of the users who voted for the option, `None` if the poll is anonymous
+    pub voters: Option<Vec<Snowflake>>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Poll {
+    /// The ID of the thread the poll is attached to
+    pub thread_id: Snowflake,
+    /// The question of the poll
+    pub question: String,
+    /// The options of the poll
+    pub options: Vec<PollOption>,
+    /// Whether users can vote for several options
+    pub multiple_choice: bool,
+    /// Whether voters are hidden
+    pub anonymous: bool,
+    /// When the poll stops accepting votes
+    pub closes_at: Option<DateTime<Utc>>,
+    /// Whether the poll stopped accepting votes
+    pub closed: bool
+}
+
+impl PollOption {
+    /// Create a new [`PollOption`] object
+    pub fn new(id: Snowflake, text: &str) -> Self {
+        Self {
+            id,
+            text: text.to_string(),
+            vote_count: 0,
+            voters: None
+        }
+    }
+}
+
+impl Poll {
+    /// Create a new [`Poll`] object
+    pub fn new(thread_id: Snowflake, question: &str, options: Vec<PollOption>, multiple_choice: bool, anonymous: bool, closes_at: Option<DateTime<Utc>>) -> Self {
+        Self {
+            thread_id,
+            question: question.to_string(),
+            options: options.into_iter()
+                .map(|option| PollOption { voters: (!anonymous).then(Vec::new), ..option })
+                .collect(),
+            multiple_choice,
+            anonymous,
+            closes_at,
+            closed: false
+        }
+    }
+
+    /// Checks whether the poll accepts votes
+    pub fn is_open(&self) -> bool {
+        !self.closed && self.closes_at.is_none_or(|closes_at| closes_at > Utc::now())
+    }
+
+    /// Save a new poll with its options in the database.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Poll`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::UnknownThread`] - If the thread the poll is attached to is not found.
+    pub async fn save<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Self> {
+        sqlx::query!(r#"
+                WITH poll AS (
+                    INSERT INTO polls(thread_id, question, multiple_choice, anonymous, closes_at) VALUES ($1, $2, $3, $4, $5)
+                    RETURNING thread_id
+                )
+                INSERT INTO poll_options(id, thread_id, text, position)
+                SELECT o.id, poll.thread_id, o.text, o.position FROM poll,
+                UNNEST($6::BIGINT[], $7::VARCHAR[]) WITH ORDINALITY AS o(id, text, position)"#,
+            self.thread_id.0, self.question, self.multiple_choice, self.anonymous, self.closes_at,
+            &self.options.iter().map(|option| option.id.0).collect::<Vec<_>>(),
+            &self.options.iter().map(|option| option.text.clone()).collect::<Vec<_>>()
+        )
+            .execute(executor).await
+            .map(|_| self)
+            .map_err(|_| HttpError::UnknownThread) // thread_id references threads table
+    }
+
+    /// Replace votes of the user, removing them all if `option_ids` is empty.
+    ///
+    /// A vote in a single choice poll replaces the previous one in a single statement, so concurrent votes of the user
+    /// can't leave several options chosen.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn vote<'a, E: PgExecutor<'a>>(&self, executor: E, user_id: Snowflake, option_ids: &[Snowflake]) -> HttpResult<()> {
+        if let (false, [option_id]) = (self.multiple_choice, option_ids) {
+            return sqlx::query!(r#"
+                    INSERT INTO poll_votes(thread_id, option_id, user_id, multiple_choice) VALUES ($1, $2, $3, FALSE)
+                    ON CONFLICT (thread_id, user_id) WHERE NOT multiple_choice DO UPDATE SET option_id = EXCLUDED.option_id"#,
+                self.thread_id.0, option_id.0, user_id.0
+            )
+                .execute(executor).await
+                .map(|_| ())
+                .map_err(HttpError::Database)
+        }
+
+        sqlx::query!(r#"
+                WITH removed AS (DELETE FROM poll_votes WHERE thread_id = $1 AND user_id = $2 AND option_id <> ALL($3))
+                INSERT INTO poll_votes(thread_id, option_id, user_id, multiple_choice) SELECT $1, UNNEST($3::BIGINT[]), $2, $4
+                ON CONFLICT DO NOTHING"#,
+            self.thread_id.0, user_id.0, &option_ids.iter().map(|option| option.0).collect::<Vec<_>>(), self.multiple_choice
+        )
+            .execute(executor).await
+            .map(|_| ())
+            .map_err(HttpError::Database)
+    }
+
+    /// Close polls which reached their closing time.
+    ///
+    /// ### Returns
+    ///
+    /// * The IDs of the threads of closed polls on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn close_expired<'a, E: PgExecutor<'a>>(executor: E) -> HttpResult<Vec<Snowflake>> {
+        sqlx::query_scalar!(r#"UPDATE polls SET closed = TRUE WHERE NOT closed AND closes_at <= CURRENT_TIMESTAMP RETURNING thread_id"#)
+            .fetch_all(executor).await
+            .map(|rows| rows.into_iter().map(Into::into).collect())
+            .map_err(HttpError::Database)
+    }
+}
@@ -0,0 +1,51 @@
+use {
+    serde::{Serialize, Deserialize},
+    sqlx::{
+        Decode, Postgres,
+        postgres::PgValueRef
+    },
+    crate::models::emoji::Emoji
+};
+
+/// The maximum number of distinct emoji a message can be reacted with
+pub const MAX_MESSAGE_REACTIONS: usize = 20;
+
+/// Reactions of a message with the same emoji
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Reaction {
+    /// The emoji of the reaction
+    pub emoji: Emoji,
+    /// The number of users who reacted with the emoji
+    pub count: i64
+}
+
+/// Reactions of a message in order they were first added
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(transparent)]
+pub struct Reactions(pub Vec<Reaction>);
+
+impl Decode<'_, Postgres> for Reactions {
+    fn decode(
+        value: PgValueRef<'_>,
+    ) -> Result<Self, Box<dyn std::error::Error + 'static + Send + Sync>> {
+        let s: sqlx::types::Json<Reactions> =  sqlx::Decode::<'_, Postgres>::decode(value)?;
+        Ok(s.0)
+    }
+}
+
+impl Reactions {
+    /// Checks whether the message was reacted with the emoji
+    pub fn contains(&self, emoji: &Emoji) -> bool {
+        self.0.iter().any(|reaction| reaction.emoji == *emoji)
+    }
+
+    /// Returns the number of distinct emoji
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Checks whether the message has no reactions
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
@@ -0,0 +1,105 @@
+use {
+    serde::{Serialize, Deserialize},
+    sqlx::PgExecutor,
+    crate::{
+        utils::snowflake::Snowflake,
+        routes::{HttpError, Result as HttpResult}
+    }
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReadState {
+    /// The ID of the thread
+    pub thread_id: Snowflake,
+    /// The ID of the user who read the thread
+    pub user_id: Snowflake,
+    /// The ID of the last message the user has read
+    pub last_read_message_id: Snowflake
+}
+
+/// Unread messages of a thread
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ThreadUnread {
+    /// The ID of the thread
+    pub thread_id: Snowflake,
+    /// The ID of the category of the thread
+    pub category_id: Snowflake,
+    /// The number of unread messages
+    pub unread_count: i64,
+    /// The number of unread messages mentioning the user
+    pub mention_count: i64
+}
+
+/// Unread messages of a category
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CategoryUnread {
+    /// The ID of the category
+    pub category_id: Snowflake,
+    /// The number of unread messages
+    pub unread_count: i64,
+    /// The number of unread messages mentioning the user
+    pub mention_count: i64
+}
+
+/// Unread messages of the user, only threads with unread messages are listed
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UnreadSummary {
+    pub categories: Vec<CategoryUnread>,
+    pub threads: Vec<ThreadUnread>
+}
+
+impl ReadState {
+    /// Create a new [`ReadState`] object
+    pub fn new(thread_id: Snowflake, user_id: Snowflake, last_read_message_id: Snowflake) -> Self {
+        Self {
+            thread_id,
+            user_id,
+            last_read_message_id
+        }
+    }
+
+    /// Save the read state in the database, replacing the previous state of the user.
+    ///
+    /// ### Returns
+    ///
+    /// * [`ReadState`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn save<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Self> {
+        sqlx::query!(r#"
+                INSERT INTO read_states(thread_id, user_id, last_read_message_id) VALUES ($1, $2, $3)
+                ON CONFLICT (user_id, thread_id) DO UPDATE SET last_read_message_id = $3"#,
+            self.thread_id.0, self.user_id.0, self.last_read_message_id.0
+        )
+            .execute(executor).await
+            .map(|_| self)
+            .map_err(HttpError::Database)
+    }
+}
+
+impl UnreadSummary {
+    /// Create a new [`UnreadSummary`] object, summing unread messages of the threads per category
+    pub fn new(threads: Vec<ThreadUnread>) -> Self {
+        let mut categories: Vec<CategoryUnread> = vec![];
+        for thread in &threads {
+            match categories.iter_mut().find(|category| category.category_id == thread.category_id) {
+                Some(category) => {
+                    category.unread_count += thread.unread_count;
+                    category.mention_count += thread.mention_count;
+                },
+                None => categories.push(CategoryUnread {
+                    category_id: thread.category_id,
+                    unread_count: thread.unread_count,
+                    mention_count: thread.mention_count
+                })
+            }
+        }
+
+        Self {
+            categories,
+            threads
+        }
+    }
+}
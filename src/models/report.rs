@@ -0,0 +1,203 @@
+use {
+    chrono::{DateTime, Utc},
+    serde::{Serialize, Deserialize},
+    sqlx::PgExecutor,
+    crate::{
+        utils::snowflake::Snowflake,
+        routes::{HttpError, Result as HttpResult}
+    }
+};
+
+/// Why the message was reported
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportReason {
+    /// Unsolicited advertising or repeated messages
+    Spam,
+    /// Insults or threats towards other users
+    Harassment,
+    /// Attacks on people based on who they are
+    Hate,
+    /// Sexual or graphic content outside of NSFW threads
+    Nsfw,
+    /// Content that breaks the law
+    Illegal,
+    /// Any other reason, explained in the details
+    Other
+}
+
+impl From<i32> for ReportReason {
+    fn from(x: i32) -> Self {
+        match x {
+            0 => ReportReason::Spam,
+            1 => ReportReason::Harassment,
+            2 => ReportReason::Hate,
+            3 => ReportReason::Nsfw,
+            4 => ReportReason::Illegal,
+            _ => ReportReason::Other
+        }
+    }
+}
+
+impl From<ReportReason> for i32 {
+    fn from(x: ReportReason) -> Self {
+        match x {
+            ReportReason::Spam => 0,
+            ReportReason::Harassment => 1,
+            ReportReason::Hate => 2,
+            ReportReason::Nsfw => 3,
+            ReportReason::Illegal => 4,
+            ReportReason::Other => 5
+        }
+    }
+}
+
+/// The moderation status of the report
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportStatus {
+    /// The report is waiting in the moderation queue
+    #[default]
+    Open,
+    /// Moderators took action against the message or its author
+    Actioned,
+    /// Moderators found nothing wrong with the message
+    Dismissed
+}
+
+impl From<i32> for ReportStatus {
+    fn from(x: i32) -> Self {
+        match x {
+            1 => ReportStatus::Actioned,
+            2 => ReportStatus::Dismissed,
+            _ => ReportStatus::Open
+        }
+    }
+}
+
+impl From<ReportStatus> for i32 {
+    fn from(x: ReportStatus) -> Self {
+        match x {
+            ReportStatus::Open => 0,
+            ReportStatus::Actioned => 1,
+            ReportStatus::Dismissed => 2
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Report {
+    /// The ID of the report
+    pub id: Snowflake,
+    /// The ID of the thread the reported message is in
+    pub thread_id: Snowflake,
+    /// The ID of the reported message
+    pub message_id: Snowflake,
+    /// The ID of the user who reported the message
+    pub reporter_id: Snowflake,
+    /// Why the message was reported
+    pub reason: ReportReason,
+    /// The reporter's explanation
+    pub details: Option<String>,
+    /// The moderation status of the report
+    pub status: ReportStatus,
+    /// The ID of the moderator the report is assigned to
+    pub assignee_id: Option<Snowflake>,
+    /// The moderator's note on how the report was resolved
+    pub resolution_note: Option<String>,
+    /// The ID of the moderator who resolved the report
+    pub resolved_by: Option<Snowflake>,
+    /// When the report was resolved
+    pub resolved_at: Option<DateTime<Utc>>
+}
+
+impl Report {
+    /// Create a new [`Report`] object
+    pub fn new(id: Snowflake, thread_id: Snowflake, message_id: Snowflake, reporter_id: Snowflake, reason: ReportReason, details: Option<String>) -> Self {
+        Self {
+            id,
+            thread_id,
+            message_id,
+            reporter_id,
+            reason,
+            details,
+            status: ReportStatus::Open,
+            assignee_id: None,
+            resolution_note: None,
+            resolved_by: None,
+            resolved_at: None
+        }
+    }
+
+    /// Checks whether the report left the moderation queue
+    pub fn is_resolved(&self) -> bool {
+        self.status != ReportStatus::Open
+    }
+
+    /// Set the status of the report, remembering who resolved it.
+    pub fn with_status(mut self, status: ReportStatus, moderator_id: Snowflake) -> Self {
+        if status != self.status {
+            self.status = status;
+            (self.resolved_by, self.resolved_at) = match self.is_resolved() {
+                true => (Some(moderator_id), Some(Utc::now())),
+                false => (None, None)
+            };
+        }
+        self
+    }
+
+    /// Save a new report in the database.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Report`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::UnknownMessage`] - If the message is not found.
+    /// * [`HttpError::InvalidOperation`] - If the user already reported the message.
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn save<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Self> {
+        let result = sqlx::query!(r#"
+                INSERT INTO reports(id, message_id, reporter_id, reason, details) VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (message_id, reporter_id) DO NOTHING"#,
+            self.id.0, self.message_id.0, self.reporter_id.0, i32::from(self.reason), self.details
+        )
+            .execute(executor).await
+            .map_err(|err| match err.as_database_error().and_then(|err| err.constraint()) {
+                Some("reports_message_id_fkey") => HttpError::UnknownMessage,
+                _ => HttpError::Database(err)
+            })?;
+
+        if result.rows_affected() == 0 {
+            return Err(HttpError::InvalidOperation("You have already reported this message".to_string()))
+        }
+
+        Ok(self)
+    }
+
+    /// Save changes of the report in the database.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Report`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::UnknownUser`] - If the assignee is not found.
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn edit<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Self> {
+        sqlx::query!(r#"
+                UPDATE reports SET status = $1, assignee_id = $2, resolution_note = $3, resolved_by = $4, resolved_at = $5
+                WHERE id = $6"#,
+            i32::from(self.status), self.assignee_id.map(i64::from), self.resolution_note,
+            self.resolved_by.map(i64::from), self.resolved_at, self.id.0
+        )
+            .execute(executor).await
+            .map(|_| self)
+            .map_err(|err| match err.as_database_error().and_then(|err| err.constraint()) {
+                Some("reports_assignee_id_fkey") => HttpError::UnknownUser,
+                _ => HttpError::Database(err)
+            })
+    }
+}
@@ -1,7 +1,19 @@
 use {
+    chrono::{DateTime, Utc},
     serde::Deserialize,
-    validator::Validate,
-    crate::utils::snowflake::Snowflake
+    validator::{Validate, ValidationError},
+    crate::{
+        models::{
+            thread::{ThreadType, ThreadRequirements, MAX_THREAD_TAGS, MAX_ACCOUNT_AGE},
+            poll::{MIN_POLL_OPTIONS, MAX_POLL_OPTIONS},
+            join_request::JoinRequestStatus,
+            report::{ReportReason, ReportStatus},
+            watch::WatchLevel,
+            emoji::is_valid_name,
+            user::Permissions
+        },
+        utils::{snowflake::Snowflake, convectors::deserialize_some}
+    }
 };
 
 #[derive(Deserialize, Validate)]
@@ -20,7 +32,9 @@ pub struct CreateCategoryPayload {
     pub title: String,
     #[validate(length(min = 16, max = 2048, message="Description length must be between 16 and 2048 characters"))]
     pub description: String,
-    pub is_locked: bool
+    pub is_locked: bool,
+    #[serde(default)]
+    pub is_question_mode: bool
 }
 
 #[derive(Deserialize, Validate)]
@@ -29,7 +43,8 @@ pub struct ModifyCategoryPayload {
     pub title: Option<String>,
     #[validate(length(min = 16, max = 2048, message="Description length must be between 16 and 2048 characters"))]
     pub description: Option<String>,
-    pub is_locked: Option<bool>
+    pub is_locked: Option<bool>,
+    pub is_question_mode: Option<bool>
 }
 
 #[derive(Deserialize, Validate)]
@@ -38,7 +53,223 @@ pub struct CreateThreadPayload {
     pub title: String,
     #[validate(length(min = 1, max = 4096, message="Message content length must be between 1 and 4096 characters"))]
     pub content: String,
-    pub is_nsfw: bool
+    pub is_nsfw: bool,
+    #[serde(default, rename = "type")]
+    pub kind: ThreadType,
+    #[validate(nested)]
+    pub requirements: Option<ThreadRequirementsPayload>,
+    #[serde(default)]
+    #[validate(custom(function = "validate_thread_tags"))]
+    pub tags: Vec<Snowflake>,
+    #[validate(nested)]
+    pub poll: Option<CreatePollPayload>
+}
+
+#[derive(Deserialize, Validate)]
+pub struct CreatePollPayload {
+    #[validate(length(min = 1, max = 300, message="Question length must be between 1 and 300 characters"))]
+    pub question: String,
+    #[validate(
+        length(min = MIN_POLL_OPTIONS, max = MAX_POLL_OPTIONS, message="Poll must have between 2 and 10 options"),
+        custom(function = "validate_poll_options")
+    )]
+    pub options: Vec<String>,
+    #[serde(default)]
+    pub multiple_choice: bool,
+    #[serde(default)]
+    pub anonymous: bool,
+    #[validate(custom(function = "validate_future_time"))]
+    pub closes_at: Option<DateTime<Utc>>
+}
+
+fn validate_poll_options(options: &[String]) -> Result<(), ValidationError> {
+    if options.iter().any(|option| option.is_empty() || option.chars().count() > 100) {
+        return Err(ValidationError::new("length").with_message("Option length must be between 1 and 100 characters".into()))
+    }
+
+    Ok(())
+}
+
+fn validate_future_time(time: &DateTime<Utc>) -> Result<(), ValidationError> {
+    if *time <= Utc::now() {
+        return Err(ValidationError::new("range").with_message("Closing time must be in the future".into()))
+    }
+
+    Ok(())
+}
+
+#[derive(Deserialize, Validate)]
+pub struct VotePollPayload {
+    #[validate(length(min = 1, max = MAX_POLL_OPTIONS, message="Vote must have between 1 and 10 options"))]
+    pub option_ids: Vec<Snowflake>
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ModifyThreadTagsPayload {
+    #[validate(custom(function = "validate_thread_tags"))]
+    pub tags: Vec<Snowflake>
+}
+
+fn validate_thread_tags(tags: &[Snowflake]) -> Result<(), ValidationError> {
+    if tags.len() as u64 > MAX_THREAD_TAGS {
+        return Err(ValidationError::new("length").with_message(format!("Thread can't have more than {MAX_THREAD_TAGS} tags").into()))
+    }
+
+    Ok(())
+}
+
+#[derive(Deserialize, Validate)]
+pub struct CreateTagPayload {
+    #[validate(length(min = 1, max = 32, message="Name length must be between 1 and 32 characters"))]
+    pub name: String,
+    #[serde(default)]
+    #[validate(range(min = 0, max = 0xFFFFFF, message="Color must be RGB integer"))]
+    pub color: i32,
+    #[serde(default)]
+    pub moderated: bool
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ModifyTagPayload {
+    #[validate(length(min = 1, max = 32, message="Name length must be between 1 and 32 characters"))]
+    pub name: Option<String>,
+    #[validate(range(min = 0, max = 0xFFFFFF, message="Color must be RGB integer"))]
+    pub color: Option<i32>,
+    pub moderated: Option<bool>
+}
+
+#[derive(Deserialize, Validate)]
+pub struct CreateEmojiPayload {
+    #[validate(custom(function = "validate_emoji_name"))]
+    pub name: String,
+    /// The image as data URI, e.g. `data:image/png;base64,...`
+    pub image: String,
+    pub required_permissions: Option<Permissions>
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ModifyEmojiPayload {
+    #[validate(custom(function = "validate_emoji_name"))]
+    pub name: Option<String>,
+    pub required_permissions: Option<Permissions>
+}
+
+fn validate_emoji_name(name: &str) -> Result<(), ValidationError> {
+    if !is_valid_name(name) {
+        return Err(ValidationError::new("name").with_message("Name must be 2-32 characters long and contain only letters, digits and underscores".into()))
+    }
+
+    Ok(())
+}
+
+#[derive(Deserialize, Validate, Clone)]
+pub struct ThreadRequirementsPayload {
+    #[validate(range(min = 0, max = MAX_ACCOUNT_AGE, message="Minimum account age must be between 0 and 10 years"))]
+    pub min_account_age: Option<i64>,
+    #[validate(range(min = 0, message="Minimum message count can't be negative"))]
+    pub min_message_count: Option<i32>,
+    pub required_permissions: Option<Permissions>,
+    #[validate(length(min = 1, max = 512, message="Question length must be between 1 and 512 characters"))]
+    pub question: Option<String>
+}
+
+impl From<ThreadRequirementsPayload> for ThreadRequirements {
+    fn from(payload: ThreadRequirementsPayload) -> Self {
+        Self {
+            min_account_age: payload.min_account_age,
+            min_message_count: payload.min_message_count,
+            required_permissions: payload.required_permissions,
+            question: payload.question
+        }
+    }
+}
+
+#[derive(Deserialize, Validate)]
+pub struct JoinThreadPayload {
+    #[validate(length(min = 1, max = 2048, message="Answer length must be between 1 and 2048 characters"))]
+    pub answer: Option<String>
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ReviewJoinRequestPayload {
+    pub status: JoinRequestStatus
+}
+
+#[derive(Deserialize, Validate)]
+pub struct MoveThreadPayload {
+    pub category_id: Snowflake
+}
+
+#[derive(Deserialize, Validate)]
+pub struct MergeThreadPayload {
+    pub thread_id: Snowflake
+}
+
+#[derive(Deserialize, Validate)]
+pub struct AckThreadPayload {
+    pub message_id: Option<Snowflake>
+}
+
+#[derive(Deserialize, Validate)]
+pub struct AckNotificationsPayload {
+    pub notification_id: Option<Snowflake>
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ModifyNotificationPayload {
+    pub read: bool
+}
+
+#[derive(Deserialize, Validate)]
+pub struct CreateBookmarkPayload {
+    pub thread_id: Snowflake,
+    pub message_id: Option<Snowflake>,
+    #[validate(length(min = 1, max = 64, message="Folder name length must be between 1 and 64 characters"))]
+    pub folder: Option<String>,
+    #[validate(length(min = 1, max = 512, message="Note length must be between 1 and 512 characters"))]
+    pub note: Option<String>
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ModifyBookmarkPayload {
+    /// `null` moves the bookmark out of its folder
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[validate(length(min = 1, max = 64, message="Folder name length must be between 1 and 64 characters"))]
+    pub folder: Option<Option<String>>,
+    /// `null` removes the note
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[validate(length(min = 1, max = 512, message="Note length must be between 1 and 512 characters"))]
+    pub note: Option<Option<String>>
+}
+
+#[derive(Deserialize, Validate)]
+pub struct WatchPayload {
+    pub level: WatchLevel
+}
+
+#[derive(Deserialize, Validate)]
+pub struct AcceptAnswerPayload {
+    pub message_id: Snowflake
+}
+
+#[derive(Deserialize, Validate)]
+pub struct SplitThreadPayload {
+    #[validate(length(min = 4, max = 128, message="Title length must be between 4 and 128 characters"))]
+    pub title: String,
+    pub first_message_id: Snowflake,
+    pub last_message_id: Snowflake
+}
+
+#[derive(Deserialize, Validate)]
+pub struct BulkDeleteMessagesPayload {
+    #[validate(length(min = 1, max = 100, message="Between 1 and 100 messages can be deleted at once"))]
+    pub messages: Vec<Snowflake>
+}
+
+#[derive(Deserialize, Validate)]
+pub struct PurgeUserMessagesPayload {
+    #[validate(range(min = 1, max = 168, message="Hours must be between 1 and 168"))]
+    pub hours: i64
 }
 
 #[derive(Deserialize, Validate)]
@@ -54,6 +285,23 @@ pub struct ModifyMessagePayload {
     pub content: String,
 }
 
+#[derive(Deserialize, Validate)]
+pub struct CreateReportPayload {
+    pub reason: ReportReason,
+    #[validate(length(min = 1, max = 1024, message="Details length must be between 1 and 1024 characters"))]
+    pub details: Option<String>
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ModifyReportPayload {
+    pub status: Option<ReportStatus>,
+    /// `null` unassigns the report
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub assignee_id: Option<Option<Snowflake>>,
+    #[validate(length(min = 1, max = 1024, message="Resolution note length must be between 1 and 1024 characters"))]
+    pub resolution_note: Option<String>
+}
+
 #[derive(Deserialize, Validate)]
 pub struct LoginPayload {
     pub username: String,
@@ -0,0 +1,84 @@
+use {
+    chrono::{DateTime, Utc},
+    serde::{Serialize, Deserialize},
+    crate::{
+        models::message::Message,
+        utils::{
+            snowflake::Snowflake,
+            markdown::escape
+        }
+    }
+};
+
+/// Marks the start of a matched word in the snippets returned by the database
+const HIGHLIGHT_START: char = '\u{E000}';
+
+/// Marks the end of a matched word in the snippets returned by the database
+const HIGHLIGHT_END: char = '\u{E001}';
+
+/// Filters of a full-text search
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilter {
+    /// The search query, supports quoted phrases, `or` and `-` to exclude words
+    pub query: String,
+    /// The ID of the user messages are filtered by
+    pub author_id: Option<Snowflake>,
+    /// The ID of the category messages are filtered by
+    pub category_id: Option<Snowflake>,
+    /// Only messages sent after this time are searched
+    pub since: Option<DateTime<Utc>>,
+    /// Only messages sent before this time are searched
+    pub until: Option<DateTime<Utc>>,
+    /// Whether only messages with (or without) attachments are searched
+    pub has_attachments: Option<bool>,
+    /// Whether deleted messages and threads are searched too
+    pub deleted: bool
+}
+
+impl SearchFilter {
+    /// Options of the snippets generated by the database
+    pub fn headline_options() -> String {
+        format!("StartSel={HIGHLIGHT_START}, StopSel={HIGHLIGHT_END}, MaxFragments=2, MaxWords=30, MinWords=10")
+    }
+
+    /// Characters marking matched words, stripped from texts before generating snippets so they can't fake highlights
+    pub fn highlight_markers() -> String {
+        format!("{HIGHLIGHT_START}{HIGHLIGHT_END}")
+    }
+}
+
+/// A message matching a search
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchResult {
+    /// The matching message
+    pub message: Message,
+    /// The ID of the category of the thread
+    pub category_id: Snowflake,
+    /// The title of the thread
+    pub thread_title: String,
+    /// Escaped snippet of the message content with matches wrapped in `<mark>`
+    pub highlight: String,
+    /// Escaped thread title with matches wrapped in `<mark>`, present only when the title matches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title_highlight: Option<String>
+}
+
+impl SearchResult {
+    /// Create a new [`SearchResult`] object from the raw snippets returned by the database
+    pub fn new(message: Message, category_id: Snowflake, thread_title: String, highlight: &str, title_highlight: Option<&str>) -> Self {
+        Self {
+            message,
+            category_id,
+            thread_title,
+            highlight: to_html(highlight),
+            title_highlight: title_highlight.map(to_html)
+        }
+    }
+}
+
+/// Escape the snippet and replace the match markers with `<mark>` tags
+fn to_html(snippet: &str) -> String {
+    escape(snippet)
+        .replace(HIGHLIGHT_START, "<mark>")
+        .replace(HIGHLIGHT_END, "</mark>")
+}
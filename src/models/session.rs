@@ -127,7 +127,7 @@ pub fn serialize_user_secret(s1: i64, s2: i64, uid: i64) -> String {
         t2 ^= t1;
     }
 
-    return format!(
+    format!(
         "{}{}{}{}",
         to_string_radix_signed(s1, 36).to_uppercase(),
         to_string_radix_signed(s2, 36)
@@ -141,7 +141,7 @@ pub fn serialize_user_secret(s1: i64, s2: i64, uid: i64) -> String {
             .rev()
             .collect::<String>()
             .to_uppercase(),
-    );
+    )
 }
 
 /// Serializes the secret timestamp.
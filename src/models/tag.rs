@@ -0,0 +1,88 @@
+use {
+    serde::{Serialize, Deserialize},
+    sqlx::PgExecutor,
+    crate::{
+        utils::snowflake::Snowflake,
+        routes::{HttpError, Result as HttpResult}
+    }
+};
+
+/// The maximum number of tags a category can have
+pub const MAX_CATEGORY_TAGS: usize = 20;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Tag {
+    /// The ID of the tag
+    pub id: Snowflake,
+    /// The ID of the category the tag belongs to
+    pub category_id: Snowflake,
+    /// The name of the tag
+    pub name: String,
+    /// The color of the tag as RGB integer
+    pub color: i32,
+    /// Whether the tag can be applied only by users with `MANAGE_THREADS` permission
+    pub moderated: bool
+}
+
+impl Tag {
+    /// Create a new [`Tag`] object
+    pub fn new(id: Snowflake, category_id: Snowflake, name: &str, color: i32, moderated: bool) -> Self {
+        Self {
+            id,
+            category_id,
+            name: name.to_string(),
+            color,
+            moderated
+        }
+    }
+
+    /// Save a new tag in the database.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Tag`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::InvalidOperation`] - If the category already has a tag with the same name.
+    pub async fn save<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Self> {
+        sqlx::query!(r#"INSERT INTO tags(id, category_id, name, color, moderated) VALUES ($1, $2, $3, $4, $5)"#,
+            self.id.0, self.category_id.0, self.name, self.color, self.moderated
+        )
+            .execute(executor).await
+            .map(|_| self)
+            .map_err(|_| HttpError::InvalidOperation("The category already has a tag with this name".to_string()))
+    }
+
+    /// Save changes of the tag in the database.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Tag`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::InvalidOperation`] - If the category already has a tag with the same name.
+    pub async fn edit<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Self> {
+        sqlx::query!(r#"UPDATE tags SET name = $1, color = $2, moderated = $3 WHERE id = $4"#,
+            self.name, self.color, self.moderated, self.id.0
+        )
+            .execute(executor).await
+            .map(|_| self)
+            .map_err(|_| HttpError::InvalidOperation("The category already has a tag with this name".to_string()))
+    }
+
+    /// Delete the tag.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn delete<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<()> {
+        sqlx::query!(r#"DELETE FROM tags WHERE id = $1"#,
+            self.id.0
+        )
+            .execute(executor).await
+            .map(|_| ())
+            .map_err(HttpError::Database)
+    }
+}
@@ -1,5 +1,7 @@
 use {
     bitflags::bitflags,
+    base64::prelude::{Engine as _, BASE64_URL_SAFE_NO_PAD},
+    chrono::{DateTime, Utc},
     serde::{Serialize, Deserialize},
     sqlx::{
         Decode, Postgres, PgExecutor,
@@ -8,7 +10,7 @@ use {
     crate::{
         bitflags_convector,
         models::{
-            message::Message, user::User
+            message::Message, poll::Poll, user::{User, Permissions}, attachment::Attachment
         },
         utils::snowflake::Snowflake,
         routes::{HttpError, Result as HttpResult}
@@ -29,6 +31,184 @@ bitflags! {
 
 bitflags_convector!(ThreadFlags, i32);
 
+/// The type of the thread
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ThreadType {
+    /// The thread is visible to everyone
+    #[default]
+    Public,
+    /// The thread is visible only to its members and thread managers
+    Private,
+    /// The thread is visible to everyone, but only thread managers can send messages
+    Announcement
+}
+
+impl From<i32> for ThreadType {
+    fn from(x: i32) -> Self {
+        match x {
+            1 => ThreadType::Private,
+            2 => ThreadType::Announcement,
+            _ => ThreadType::Public
+        }
+    }
+}
+
+impl From<ThreadType> for i32 {
+    fn from(x: ThreadType) -> Self {
+        match x {
+            ThreadType::Public => 0,
+            ThreadType::Private => 1,
+            ThreadType::Announcement => 2
+        }
+    }
+}
+
+/// Conditions a user has to meet before sending messages in the thread
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ThreadRequirements {
+    /// The minimum age of the user's account in seconds
+    pub min_account_age: Option<i64>,
+    /// The minimum number of messages the user has sent
+    pub min_message_count: Option<i32>,
+    /// The permissions the user must have
+    pub required_permissions: Option<Permissions>,
+    /// The question the user has to answer, answers are reviewed by the thread author or thread managers
+    pub question: Option<String>
+}
+
+impl ThreadRequirements {
+    /// Checks whether the thread has any requirements
+    pub fn is_empty(&self) -> bool {
+        self.min_account_age.is_none()
+            && self.min_message_count.is_none()
+            && self.required_permissions.is_none()
+            && self.question.is_none()
+    }
+}
+
+/// The maximum number of tags a thread can have
+pub const MAX_THREAD_TAGS: u64 = 5;
+
+/// The maximum account age in seconds a thread can require, 10 years
+pub const MAX_ACCOUNT_AGE: i64 = 10 * 365 * 86400;
+
+/// The order threads are listed in a category. Pinned threads always go first.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ThreadSort {
+    /// Threads with the most recent message first
+    #[default]
+    Latest,
+    /// Threads with the most recent activity (new or edited messages) first
+    Activity,
+    /// Threads with the most messages first
+    Top,
+    /// Newest threads first
+    Created
+}
+
+impl ThreadSort {
+    /// Returns the sort name used by database queries
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThreadSort::Latest => "latest",
+            ThreadSort::Activity => "activity",
+            ThreadSort::Top => "top",
+            ThreadSort::Created => "created"
+        }
+    }
+
+    /// Parses the sort name used by database queries
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "latest" => Some(ThreadSort::Latest),
+            "activity" => Some(ThreadSort::Activity),
+            "top" => Some(ThreadSort::Top),
+            "created" => Some(ThreadSort::Created),
+            _ => None
+        }
+    }
+}
+
+/// The position of a thread in a category listing, used to fetch the neighbouring pages.
+///
+/// It keeps the values the thread was ordered by when it was listed, so pages don't shift
+/// when the thread receives new messages or leaves the listing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThreadCursor {
+    /// The order the thread was listed in
+    pub sort: ThreadSort,
+    /// Whether the thread was pinned
+    pub pinned: bool,
+    /// The value the thread was ordered by
+    pub sort_key: i64,
+    /// The ID of the thread
+    pub id: Snowflake
+}
+
+impl ThreadCursor {
+    /// Encodes the cursor to an opaque string
+    pub fn encode(&self) -> String {
+        BASE64_URL_SAFE_NO_PAD.encode(format!("{}:{}:{}:{}", self.sort.as_str(), i32::from(self.pinned), self.sort_key, self.id.0))
+    }
+
+    /// Decodes the cursor from an opaque string, `None` if it's malformed
+    pub fn decode(value: &str) -> Option<Self> {
+        let value = String::from_utf8(BASE64_URL_SAFE_NO_PAD.decode(value).ok()?).ok()?;
+        let mut parts = value.split(':');
+
+        let cursor = Self {
+            sort: ThreadSort::parse(parts.next()?)?,
+            pinned: parts.next()? == "1",
+            sort_key: parts.next()?.parse().ok()?,
+            id: Snowflake(parts.next()?.parse().ok()?)
+        };
+
+        parts.next().is_none().then_some(cursor)
+    }
+}
+
+impl Serialize for ThreadCursor {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.encode())
+    }
+}
+
+impl<'de> Deserialize<'de> for ThreadCursor {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value: String = Deserialize::deserialize(deserializer)?;
+
+        ThreadCursor::decode(&value).ok_or(serde::de::Error::custom("invalid thread cursor"))
+    }
+}
+
+/// How threads are filtered by tags
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMode {
+    /// Threads with any of the tags
+    #[default]
+    Any,
+    /// Threads with all of the tags
+    All
+}
+
+/// Filters and order of threads listed in a category
+#[derive(Debug, Clone, Default)]
+pub struct ThreadFilter {
+    /// The order of the threads
+    pub sort: ThreadSort,
+    /// The IDs of the tags threads are filtered by
+    pub tags: Vec<Snowflake>,
+    /// How threads are filtered by tags
+    pub tag_mode: TagMode,
+    /// Whether only threads with (or without) an accepted answer are listed
+    pub solved: Option<bool>,
+    /// Whether deleted threads are listed instead of the others
+    pub deleted: bool
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Thread {
     /// The ID of the thread
@@ -39,10 +219,34 @@ pub struct Thread {
     pub author: User,
     /// The title of the thread
     pub title: String,
+    /// The type of the thread
+    #[serde(rename = "type")]
+    pub kind: ThreadType,
     /// The thread's flags
     pub flags: ThreadFlags,
+    /// Conditions a user has to meet before sending messages in the thread
+    pub requirements: ThreadRequirements,
+    /// The IDs of the tags applied to the thread
+    pub tags: Vec<Snowflake>,
     /// The message the thread is referenced to
-    pub original_message: Message
+    pub original_message: Message,
+    /// The reply accepted as the answer to the thread's question, shown below the original message
+    pub accepted_answer: Option<Message>,
+    /// The poll attached to the thread
+    pub poll: Option<Poll>,
+    /// The ID of the last message sent in the thread
+    pub last_message_id: Option<Snowflake>,
+    /// When a message was last sent or edited in the thread
+    pub last_activity_at: DateTime<Utc>,
+    /// The number of messages in the thread
+    pub message_count: i32,
+    /// When this thread was deleted, it's purged after the retention period
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// The ID of the user who deleted the thread
+    pub deleted_by: Option<Snowflake>,
+    /// The position of the thread in the category listing it was fetched by
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<ThreadCursor>
 }
 
 impl Thread {
@@ -52,9 +256,20 @@ impl Thread {
             id,
             category_id,
             title: title.to_string(),
+            kind: ThreadType::Public,
+            requirements: ThreadRequirements::default(),
+            tags: vec![],
             author: message.author.clone(),
+            last_message_id: Some(message.id),
+            last_activity_at: Utc::now(),
+            message_count: 1,
+            accepted_answer: None,
+            poll: None,
             original_message: message,
-            flags: flags.unwrap_or(ThreadFlags::empty())
+            flags: flags.unwrap_or(ThreadFlags::empty()),
+            deleted_at: None,
+            deleted_by: None,
+            cursor: None
         }
     }
 
@@ -63,6 +278,18 @@ impl Thread {
         self.flags.contains(flag)
     }
 
+    /// Set the type of the thread
+    pub fn with_type(mut self, kind: ThreadType) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Set the requirements to send messages in the thread
+    pub fn with_requirements(mut self, requirements: ThreadRequirements) -> Self {
+        self.requirements = requirements;
+        self
+    }
+
     /// Save a new thread in the database.
     ///
     /// ### Returns
@@ -73,15 +300,239 @@ impl Thread {
     ///
     /// * [`HttpError::UnknownCategory`] - If the category the thread will be created in is not found.
     pub async fn save<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Self> {
-        sqlx::query!(r#"INSERT INTO threads(id, author_id, category_id, original_message_id, title) VALUES ($1, $2, $3, $4, $5)"#,
-            self.id.0, self.author.id.0, self.category_id.0, self.original_message.id.0, self.title
+        sqlx::query!(r#"
+                INSERT INTO threads(id, author_id, category_id, original_message_id, title, type, min_account_age, min_message_count, required_permissions, join_question)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)"#,
+            self.id.0, self.author.id.0, self.category_id.0, self.original_message.id.0, self.title, i32::from(self.kind),
+            self.requirements.min_account_age, self.requirements.min_message_count,
+            self.requirements.required_permissions.map(|permissions| permissions.bits()), self.requirements.question
         )
             .execute(executor).await
             .map(|_| self)
             .map_err(|_| HttpError::UnknownCategory) // category_id references category table
     }
 
-    /// Delete the thread.
+    /// Replace requirements to send messages in the thread.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Thread`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn set_requirements<'a, E: PgExecutor<'a>>(mut self, executor: E, requirements: ThreadRequirements) -> HttpResult<Self> {
+        sqlx::query!(r#"UPDATE threads SET min_account_age = $1, min_message_count = $2, required_permissions = $3, join_question = $4 WHERE id = $5"#,
+            requirements.min_account_age, requirements.min_message_count,
+            requirements.required_permissions.map(|permissions| permissions.bits()), requirements.question, self.id.0
+        )
+            .execute(executor).await
+            .map_err(HttpError::Database)?;
+
+        self.requirements = requirements;
+        Ok(self)
+    }
+
+    /// Replace tags applied to the thread.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Thread`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::UnknownTag`] - If the tag is not found.
+    pub async fn set_tags<'a, E: PgExecutor<'a>>(mut self, executor: E, tags: Vec<Snowflake>) -> HttpResult<Self> {
+        sqlx::query!(r#"
+                WITH removed AS (DELETE FROM thread_tags WHERE thread_id = $1 AND tag_id <> ALL($2))
+                INSERT INTO thread_tags(thread_id, tag_id) SELECT $1, UNNEST($2::BIGINT[]) ON CONFLICT DO NOTHING"#,
+            self.id.0, &tags.iter().map(|tag| tag.0).collect::<Vec<_>>()
+        )
+            .execute(executor).await
+            .map_err(|_| HttpError::UnknownTag)?; // tag_id references tags table
+
+        self.tags = tags;
+        Ok(self)
+    }
+
+    /// Mark the message as the accepted answer of the thread, or unmark it if `None`.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Thread`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::UnknownMessage`] - If the message is not found.
+    pub async fn set_accepted_answer<'a, E: PgExecutor<'a>>(mut self, executor: E, message: Option<Message>) -> HttpResult<Self> {
+        sqlx::query!(r#"UPDATE threads SET accepted_answer_id = $1 WHERE id = $2"#,
+            message.as_ref().map(|message| message.id.0), self.id.0
+        )
+            .execute(executor).await
+            .map_err(|_| HttpError::UnknownMessage)?; // accepted_answer_id references messages table
+
+        self.accepted_answer = message;
+        Ok(self)
+    }
+
+    /// Move the thread to another category.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Thread`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::UnknownCategory`] - If the category is not found.
+    pub async fn set_category<'a, E: PgExecutor<'a>>(mut self, executor: E, category_id: Snowflake) -> HttpResult<Self> {
+        sqlx::query!(r#"UPDATE threads SET category_id = $1 WHERE id = $2"#,
+            category_id.0, self.id.0
+        )
+            .execute(executor).await
+            .map_err(|_| HttpError::UnknownCategory)?; // category_id references category table
+
+        self.category_id = category_id;
+        Ok(self)
+    }
+
+    /// Move the thread's messages to another thread.
+    ///
+    /// ### Arguments
+    ///
+    /// * `thread_id` - The ID of the thread the messages are moved to.
+    /// * `range` - The IDs of the first and the last message to move, all messages if `None`.
+    ///
+    /// ### Returns
+    ///
+    /// * The number of moved messages on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn move_messages<'a, E: PgExecutor<'a>>(&self, executor: E, thread_id: Snowflake, range: Option<(Snowflake, Snowflake)>) -> HttpResult<u64> {
+        let (first, last) = range.map_or((i64::MIN, i64::MAX), |(first, last)| (first.0, last.0));
+        sqlx::query!(r#"UPDATE messages SET thread_id = $1 WHERE thread_id = $2 AND id >= $3 AND id <= $4"#,
+            thread_id.0, self.id.0, first, last
+        )
+            .execute(executor).await
+            .map(|result| result.rows_affected())
+            .map_err(HttpError::Database)
+    }
+
+    /// Add a member to the thread.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::UnknownUser`] - If the user is not found.
+    pub async fn add_member<'a, E: PgExecutor<'a>>(&self, executor: E, user_id: Snowflake) -> HttpResult<()> {
+        sqlx::query!(r#"INSERT INTO thread_members(thread_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"#,
+            self.id.0, user_id.0
+        )
+            .execute(executor).await
+            .map(|_| ())
+            .map_err(|_| HttpError::UnknownUser) // user_id references users table
+    }
+
+    /// Copy members of another thread to the thread.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn copy_members<'a, E: PgExecutor<'a>>(&self, executor: E, thread_id: Snowflake) -> HttpResult<()> {
+        sqlx::query!(r#"INSERT INTO thread_members(thread_id, user_id) SELECT $1, user_id FROM thread_members WHERE thread_id = $2 ON CONFLICT DO NOTHING"#,
+            self.id.0, thread_id.0
+        )
+            .execute(executor).await
+            .map(|_| ())
+            .map_err(HttpError::Database)
+    }
+
+    /// Remove a member from the thread.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::UnknownUser`] - If the user is not a member of the thread.
+    pub async fn remove_member<'a, E: PgExecutor<'a>>(&self, executor: E, user_id: Snowflake) -> HttpResult<()> {
+        let result = sqlx::query!(r#"DELETE FROM thread_members WHERE thread_id = $1 AND user_id = $2"#,
+            self.id.0, user_id.0
+        )
+            .execute(executor).await
+            .map_err(HttpError::Database)?;
+
+        if result.rows_affected() == 0 {
+            return Err(HttpError::UnknownUser)
+        }
+
+        Ok(())
+    }
+
+    /// Mark the thread as deleted, keeping it until the retention period ends.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Thread`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::UnknownThread`] - If the thread is not found or already deleted.
+    pub async fn soft_delete<'a, E: PgExecutor<'a>>(mut self, executor: E, user_id: Snowflake) -> HttpResult<Self> {
+        let deleted_at = sqlx::query_scalar!(r#"
+                UPDATE threads SET deleted_at = CURRENT_TIMESTAMP, deleted_by = $1
+                WHERE id = $2 AND deleted_at IS NULL RETURNING deleted_at AS "deleted_at!""#,
+            user_id.0, self.id.0
+        )
+            .fetch_optional(executor).await
+            .map_err(HttpError::Database)?
+            .ok_or(HttpError::UnknownThread)?;
+
+        self.deleted_at = Some(deleted_at);
+        self.deleted_by = Some(user_id);
+        Ok(self)
+    }
+
+    /// Restore the deleted thread.
+    ///
+    /// ### Returns
+    ///
+    /// * [`Thread`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn restore<'a, E: PgExecutor<'a>>(mut self, executor: E) -> HttpResult<Self> {
+        sqlx::query!(r#"UPDATE threads SET deleted_at = NULL, deleted_by = NULL WHERE id = $1"#,
+            self.id.0
+        )
+            .execute(executor).await
+            .map_err(HttpError::Database)?;
+
+        self.deleted_at = None;
+        self.deleted_by = None;
+        Ok(self)
+    }
+
+    /// Permanently delete threads deleted before the given time with all their messages.
+    ///
+    /// ### Returns
+    ///
+    /// * Attachments of the purged messages, their files have to be removed from the storage.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn purge_deleted<'a, E: PgExecutor<'a>>(executor: E, before: DateTime<Utc>) -> HttpResult<Vec<Attachment>> {
+        // The outer query sees attachments as they were before the threads were deleted
+        sqlx::query_as!(Attachment, r#"
+                WITH purged AS (DELETE FROM threads WHERE deleted_at < $1 RETURNING id)
+                SELECT a.* FROM attachments a JOIN messages m ON a.message_id = m.id
+                WHERE m.thread_id IN (SELECT id FROM purged)"#,
+            before
+        )
+            .fetch_all(executor).await
+            .map_err(HttpError::Database)
+    }
+
+    /// Permanently delete the thread.
     ///
     /// ### Errors
     ///
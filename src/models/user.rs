@@ -8,6 +8,7 @@ use {
     },
     crate::{
         bitflags_convector,
+        models::attachment::Attachment,
         utils::snowflake::Snowflake,
         routes::{HttpError, Result as HttpResult}
     }
@@ -52,6 +53,8 @@ bitflags! {
         const MANAGE_USERS = 1 << 7;
         /// Allows for timing out and banning users
         const MODERATE_USERS = 1 << 8;
+        /// Allows for uploading, editing and deleting custom emoji
+        const MANAGE_EMOJIS = 1 << 9;
         /// Allows all permissions and grants access to all endpoints (This is dangerous permission to grant)
         const ADMINISTRATOR = i64::MAX;
     }
@@ -121,17 +124,26 @@ impl User {
             .map_err(HttpError::Database)
     }
 
-    /// Delete the user
+    /// Delete the user with their categories, threads and messages
+    ///
+    /// ### Returns
+    ///
+    /// * Attachments of the deleted messages, their files have to be removed from the storage
     ///
     /// ### Errors
     ///
     /// * [`HttpError::Database`] - If the database query fails
-    pub async fn delete<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<()> {
-        sqlx::query!(r#"DELETE FROM users WHERE id = $1"#,
+    pub async fn delete<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Vec<Attachment>> {
+        // The outer query sees attachments as they were before the user was deleted
+        sqlx::query_as!(Attachment, r#"
+                WITH deleted AS (DELETE FROM users WHERE id = $1 RETURNING id)
+                SELECT a.* FROM attachments a JOIN messages m ON a.message_id = m.id
+                JOIN threads t ON m.thread_id = t.id JOIN categories c ON t.category_id = c.id
+                JOIN messages o ON t.original_message_id = o.id
+                WHERE EXISTS (SELECT 1 FROM deleted) AND $1 IN (m.author_id, t.author_id, o.author_id, c.owner_id)"#,
             self.id.0
         )
-            .execute(executor).await
-            .map(|_| ())
+            .fetch_all(executor).await
             .map_err(HttpError::Database)
     }
 }
@@ -0,0 +1,137 @@
+use {
+    serde::{Serialize, Deserialize},
+    sqlx::PgExecutor,
+    crate::{
+        utils::snowflake::Snowflake,
+        routes::{HttpError, Result as HttpResult}
+    }
+};
+
+/// How the user follows a thread or a category
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchLevel {
+    /// The user is notified about new messages only when the category is watched
+    #[default]
+    Normal,
+    /// The user is notified about new messages
+    Watching,
+    /// The user is never notified about new messages
+    Muted
+}
+
+impl From<i32> for WatchLevel {
+    fn from(x: i32) -> Self {
+        match x {
+            1 => WatchLevel::Watching,
+            2 => WatchLevel::Muted,
+            _ => WatchLevel::Normal
+        }
+    }
+}
+
+impl From<WatchLevel> for i32 {
+    fn from(x: WatchLevel) -> Self {
+        match x {
+            WatchLevel::Normal => 0,
+            WatchLevel::Watching => 1,
+            WatchLevel::Muted => 2
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ThreadWatch {
+    /// The ID of the watched thread
+    pub thread_id: Snowflake,
+    /// The ID of the user who watches the thread
+    pub user_id: Snowflake,
+    /// The watch level of the thread, overrides the level of its category
+    pub level: WatchLevel
+}
+
+impl ThreadWatch {
+    /// Create a new [`ThreadWatch`] object
+    pub fn new(thread_id: Snowflake, user_id: Snowflake, level: WatchLevel) -> Self {
+        Self {
+            thread_id,
+            user_id,
+            level
+        }
+    }
+
+    /// Save the watch level in the database, replacing the previous level of the user.
+    ///
+    /// ### Returns
+    ///
+    /// * [`ThreadWatch`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn save<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Self> {
+        sqlx::query!(r#"
+                INSERT INTO thread_watches(thread_id, user_id, level) VALUES ($1, $2, $3)
+                ON CONFLICT (thread_id, user_id) DO UPDATE SET level = $3"#,
+            self.thread_id.0, self.user_id.0, i32::from(self.level)
+        )
+            .execute(executor).await
+            .map(|_| self)
+            .map_err(HttpError::Database)
+    }
+
+    /// Save the watch level in the database, unless the user already chose a level for the thread.
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn save_if_missing<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<()> {
+        sqlx::query!(r#"INSERT INTO thread_watches(thread_id, user_id, level) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING"#,
+            self.thread_id.0, self.user_id.0, i32::from(self.level)
+        )
+            .execute(executor).await
+            .map(|_| ())
+            .map_err(HttpError::Database)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CategoryWatch {
+    /// The ID of the watched category
+    pub category_id: Snowflake,
+    /// The ID of the user who watches the category
+    pub user_id: Snowflake,
+    /// The watch level of the category's threads
+    pub level: WatchLevel
+}
+
+impl CategoryWatch {
+    /// Create a new [`CategoryWatch`] object
+    pub fn new(category_id: Snowflake, user_id: Snowflake, level: WatchLevel) -> Self {
+        Self {
+            category_id,
+            user_id,
+            level
+        }
+    }
+
+    /// Save the watch level in the database, replacing the previous level of the user.
+    ///
+    /// ### Returns
+    ///
+    /// * [`CategoryWatch`] on success, otherwise [`HttpError`].
+    ///
+    /// ### Errors
+    ///
+    /// * [`HttpError::Database`] - If the database query fails.
+    pub async fn save<'a, E: PgExecutor<'a>>(self, executor: E) -> HttpResult<Self> {
+        sqlx::query!(r#"
+                INSERT INTO category_watches(category_id, user_id, level) VALUES ($1, $2, $3)
+                ON CONFLICT (category_id, user_id) DO UPDATE SET level = $3"#,
+            self.category_id.0, self.user_id.0, i32::from(self.level)
+        )
+            .execute(executor).await
+            .map(|_| self)
+            .map_err(HttpError::Database)
+    }
+}
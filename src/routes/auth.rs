@@ -48,7 +48,7 @@ async fn register(
 ) -> Result<HttpResponse> {
     payload
         .validate()
-        .map_err(|err| HttpError::Validation(err))?;
+        .map_err(HttpError::Validation)?;
 
     if app.database.fetch_user_by_username(&payload.username).await.is_some() {
         return Err(HttpError::TakenUsername)
@@ -92,7 +92,7 @@ async fn login(
 ) -> Result<HttpResponse> {
     payload
         .validate()
-        .map_err(|err| HttpError::Validation(err))?;
+        .map_err(HttpError::Validation)?;
 
     let user = app.database.fetch_user_by_username(&payload.username)
         .await.ok_or(HttpError::InvalidCredentials("Username or password is invalid".to_string()))?;
@@ -125,7 +125,7 @@ async fn logout(
 ) -> Result<HttpResponse> {
     payload
         .validate()
-        .map_err(|err| HttpError::Validation(err))?;
+        .map_err(HttpError::Validation)?;
 
     app.database.fetch_credentials_by_token(&payload.token).await?.1
         .delete(&app.pool).await?;
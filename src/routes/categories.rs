@@ -6,16 +6,24 @@ use {
     serde::Deserialize,
     crate::{
         App,
-        routes::{Result, HttpError},
+        routes::{Result, HttpError, emojis::check_message_emojis, threads::{check_message_mentions, dispatch_mentions}},
         models::{
             UserCredentials,
-            user::Permissions,
-            requests::{CreateCategoryPayload, CreateThreadPayload},
+            user::{User, Permissions},
+            requests::{CreateCategoryPayload, ModifyCategoryPayload, CreateThreadPayload, CreateTagPayload, ModifyTagPayload, WatchPayload},
             message::{Message, MessageFlags},
+            mention::Mentions,
             category::Category,
-            thread::Thread
+            thread::{Thread, ThreadCursor, ThreadSort, ThreadType, ThreadFilter, TagMode, MAX_THREAD_TAGS},
+            tag::{Tag, MAX_CATEGORY_TAGS},
+            poll::{Poll, PollOption},
+            watch::{ThreadWatch, CategoryWatch, WatchLevel},
+            read_state::ReadState
         },
-        utils::snowflake::Snowflake
+        utils::{
+            snowflake::Snowflake,
+            convectors::deserialize_snowflake_list
+        }
     }
 };
 
@@ -23,11 +31,17 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("categories")
             .route("{category_id}", web::get().to(get_category))
-            // .route("{category_id}", web::patch().to(modify_category))
+            .route("{category_id}", web::patch().to(modify_category))
             .route("", web::post().to(create_category))
             .route("{category_id}", web::delete().to(delete_category))
             .route("{category_id}/threads", web::post().to(create_thread))
             .route("{category_id}/threads", web::get().to(get_threads))
+            .route("{category_id}/tags", web::get().to(get_tags))
+            .route("{category_id}/tags", web::post().to(create_tag))
+            .route("{category_id}/tags/{tag_id}", web::patch().to(modify_tag))
+            .route("{category_id}/tags/{tag_id}", web::delete().to(delete_tag))
+            .route("{category_id}/watch", web::get().to(get_category_watch))
+            .route("{category_id}/watch", web::put().to(modify_category_watch))
     );
 }
 
@@ -67,21 +81,73 @@ async fn create_category(
 
     payload
         .validate()
-        .map_err(|err| HttpError::Validation(err))?;
+        .map_err(HttpError::Validation)?;
 
     let id = app.snowflake.lock().unwrap().build();
     Category::new(id, user, &payload.title, &payload.description, payload.is_locked)
+        .with_question_mode(payload.is_question_mode)
         .save(&app.pool)
         .await
         .map(|row| HttpResponse::Ok().json(row))
 }
 
+/// Modifies a category and return [`Category`] - `PATCH /categories/{category.id}`
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_CATEGORIES`]
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+/// * [`HttpError::UnknownCategory`] - If the category is not found
+/// * [`HttpError::Database`] - If the database query fails
+async fn modify_category(
+    category_id: web::Path<i64>,
+    payload: web::Json<ModifyCategoryPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    if !user.has_permission(Permissions::MANAGE_CATEGORIES) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    payload
+        .validate()
+        .map_err(HttpError::Validation)?;
+
+    let mut category = app.database.fetch_category(category_id.into_inner().into()).await
+        .ok_or(HttpError::UnknownCategory)?;
+
+    if let Some(title) = &payload.title {
+        category.title = title.clone();
+    }
+    if let Some(description) = &payload.description {
+        category.description = description.clone();
+    }
+    if let Some(locked) = payload.is_locked {
+        category.locked = locked;
+    }
+    if let Some(question_mode) = payload.is_question_mode {
+        category.question_mode = question_mode;
+    }
+
+    category.edit(&app.pool)
+        .await
+        .map(|row| HttpResponse::Ok().json(row))
+}
+
 /// Creates a new thread and return [`Thread`] - `POST /categories/{category.id}/threads`
 ///
+/// The author starts watching the thread and the thread is marked as read for them.
+///
 /// ### Errors
 ///
-/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::CREATE_THREADS`]
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::CREATE_THREADS`],
+///   [`Permissions::MANAGE_THREADS`] for announcement threads, or can't use a custom emoji from the content
 /// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+/// * [`HttpError::UnknownTag`] - If the tag is not found in the category
+/// * [`HttpError::InvalidOperation`] - If the content mentions too many users
+/// * [`HttpError::RateLimited`] - If the user mentioned too many users recently
 /// * [`HttpError::Database`] - If the database query fails
 async fn create_thread(
     payload: web::Json<CreateThreadPayload>,
@@ -95,23 +161,63 @@ async fn create_thread(
         return Err(HttpError::MissingAccess)
     }
 
+    if payload.kind == ThreadType::Announcement && !user.has_permission(Permissions::MANAGE_THREADS) {
+        return Err(HttpError::MissingAccess)
+    }
+
     payload
         .validate()
-        .map_err(|err| HttpError::Validation(err))?;
+        .map_err(HttpError::Validation)?;
 
-    let id = app.snowflake.lock().unwrap().build();
+    let tags = check_thread_tags(&app, path.to_owned().into(), &user, &payload.tags, &[]).await?;
+    let emojis = check_message_emojis(&app, &payload.content, &user).await?;
+    let mentions = check_message_mentions(&app, &payload.content, &user, &Mentions::default()).await?;
+
+    let (id, option_ids) = {
+        let mut snowflake = app.snowflake.lock().unwrap();
+        let id = snowflake.build();
+        let option_ids = payload.poll.as_ref()
+            .map_or(vec![], |poll| poll.options.iter().map(|_| snowflake.build()).collect::<Vec<_>>());
+        (id, option_ids)
+    };
     let mut tx = app.pool.begin().await?;
 
-    let message = Message::new(id, user, id, &payload.content, Some(MessageFlags::UNDELETEABLE))
+    let mut message = Message::new(id, user.clone(), id, &payload.content, Some(MessageFlags::UNDELETEABLE))
         .save(&mut *tx).await?;
+    message.emojis = emojis;
+    let mentioned_ids = mentions.save(&mut *tx, message.id).await?;
+    message.mentions = mentions;
 
-    let thread = Thread::new(id, path.to_owned().into(), message, &payload.title, None)
+    let mut thread = Thread::new(id, path.to_owned().into(), message, &payload.title, None)
+        .with_type(payload.kind)
+        .with_requirements(payload.requirements.clone().map(Into::into).unwrap_or_default())
+        .save(&mut *tx).await?
+        .set_tags(&mut *tx, tags).await?;
+
+    if thread.kind == ThreadType::Private {
+        thread.add_member(&mut *tx, user.id).await?;
+    }
+
+    ThreadWatch::new(thread.id, user.id, WatchLevel::Watching)
+        .save(&mut *tx).await?;
+    ReadState::new(thread.id, user.id, thread.original_message.id)
         .save(&mut *tx).await?;
 
-    tx.commit()
-        .await
-        .map(|_| HttpResponse::Ok().json(thread))
-        .map_err(HttpError::Database)
+    if let Some(poll) = &payload.poll {
+        let options = option_ids.into_iter()
+            .zip(&poll.options)
+            .map(|(id, text)| PollOption::new(id, text))
+            .collect();
+
+        thread.poll = Some(Poll::new(thread.id, &poll.question, options, poll.multiple_choice, poll.anonymous, poll.closes_at)
+            .save(&mut *tx).await?);
+    }
+
+    tx.commit().await?;
+
+    dispatch_mentions(&app, &thread, &thread.original_message, &mentioned_ids).await;
+
+    Ok(HttpResponse::Ok().json(thread))
 }
 
 /// Deletes a category - `DELETE /categories/{category_id}`
@@ -132,35 +238,281 @@ async fn delete_category(
         return Err(HttpError::MissingAccess);
     }
 
-    category.delete(&app.pool).await?;
+    for attachment in category.delete(&app.pool).await? {
+        _ = app.storage.delete(&attachment.key()).await;
+    }
 
     Ok(HttpResponse::NoContent().finish())
 }
 
+/// Checks tags applied to a thread and returns them without duplicates.
+///
+/// Moderated tags can be applied or removed only by users with [`Permissions::MANAGE_THREADS`],
+/// other users keep moderated tags from `current` tags.
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownTag`] - If the tag is not found in the category
+/// * [`HttpError::MissingAccess`] - If the user applies moderated tag without [`Permissions::MANAGE_THREADS`]
+/// * [`HttpError::InvalidOperation`] - If the thread would have more than [`MAX_THREAD_TAGS`] tags with the kept moderated tags
+pub(super) async fn check_thread_tags(app: &App, category_id: Snowflake, user: &User, tags: &[Snowflake], current: &[Snowflake]) -> Result<Vec<Snowflake>> {
+    let available = app.database.fetch_tags(category_id).await?;
+    let is_moderator = user.has_permission(Permissions::MANAGE_THREADS);
+
+    let mut result = vec![];
+    for id in tags {
+        let tag = available.iter().find(|tag| tag.id == *id)
+            .ok_or(HttpError::UnknownTag)?;
+
+        if tag.moderated && !is_moderator && !current.contains(id) {
+            return Err(HttpError::MissingAccess)
+        }
+
+        result.push(tag.id);
+    }
+
+    if !is_moderator {
+        result.extend(available.iter()
+            .filter(|tag| tag.moderated && current.contains(&tag.id))
+            .map(|tag| tag.id));
+    }
+
+    result.sort();
+    result.dedup();
+
+    if result.len() as u64 > MAX_THREAD_TAGS {
+        return Err(HttpError::InvalidOperation(format!("Thread can't have more than {MAX_THREAD_TAGS} tags")))
+    }
+
+    Ok(result)
+}
+
 #[derive(Deserialize)]
 pub struct SearchThreadsQuery {
+    #[serde(default)]
+    pub sort: ThreadSort,
+    #[serde(default, deserialize_with = "deserialize_snowflake_list")]
+    pub tags: Vec<Snowflake>,
+    #[serde(default)]
+    pub tag_mode: TagMode,
+    pub solved: Option<bool>,
+    #[serde(default)]
+    pub deleted: bool,
     pub limit: Option<u16>,
-    pub before: Option<Snowflake>,
-    pub after: Option<Snowflake>
+    pub before: Option<ThreadCursor>,
+    pub after: Option<ThreadCursor>
 }
 
 /// Returns [`Vec<Thread>`] of the thread - `GET /categories/{category_id}/threads`
 ///
+/// Pinned threads are always listed first. Private threads are listed only to their members.
+///
 /// ### Query
 ///
+/// * `sort` - The order of the threads: `latest`, `activity`, `top` or `created` (default `latest`)
+/// * `tags` - Comma separated IDs of the tags to filter threads by
+/// * `tag_mode` - Whether threads must have `any` or `all` of the tags (default `any`)
+/// * `solved` - List only threads with (`true`) or without (`false`) an accepted answer
+/// * `deleted` - List deleted threads instead, requires [`Permissions::MANAGE_THREADS`]
 /// * `limit` - Max number of threads to return (1-100, default 50)
-/// * `after` - Get threads after this thread ID
-/// * `before` - Get threads before this thread ID
+/// * `after` - Get threads listed before this thread cursor
+/// * `before` - Get threads listed after this thread cursor
 ///
 /// ### Errors
 ///
 /// * [`HttpError::UnknownCategory`] - If the category is not found
+/// * [`HttpError::MissingAccess`] - If deleted threads are requested without [`Permissions::MANAGE_THREADS`]
+/// * [`HttpError::InvalidOperation`] - If a cursor was returned for another sort
 async fn get_threads(
     path: web::Path<i64>,
     query: web::Query<SearchThreadsQuery>,
     app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
 ) -> Result<HttpResponse> {
-    let threads = app.database.fetch_threads(path.to_owned().into(), query.limit, query.before, query.after).await?;
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    if query.deleted && !user.has_permission(Permissions::MANAGE_THREADS) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    let category = app.database.fetch_category(path.into_inner().into()).await
+        .ok_or(HttpError::UnknownCategory)?;
+
+    if query.before.iter().chain(&query.after).any(|cursor| cursor.sort != query.sort) {
+        return Err(HttpError::InvalidOperation("Cursor doesn't match the sort of the threads".to_string()))
+    }
+
+    let filter = ThreadFilter {
+        sort: query.sort,
+        tags: query.tags.clone(),
+        tag_mode: query.tag_mode,
+        solved: query.solved,
+        deleted: query.deleted
+    };
+    let threads = app.database.fetch_threads(category.id, &user, &filter, query.limit, query.before, query.after).await?;
 
     Ok(HttpResponse::Ok().json(threads))
 }
+
+/// Returns [`Vec<Tag>`] of the category - `GET /categories/{category_id}/tags`
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownCategory`] - If the category is not found
+async fn get_tags(
+    category_id: web::Path<i64>,
+    app: web::Data<App>,
+) -> Result<HttpResponse> {
+    let category = app.database.fetch_category(category_id.into_inner().into()).await
+        .ok_or(HttpError::UnknownCategory)?;
+
+    let tags = app.database.fetch_tags(category.id).await?;
+
+    Ok(HttpResponse::Ok().json(tags))
+}
+
+/// Creates a new tag in the category and return [`Tag`] - `POST /categories/{category_id}/tags`
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_CATEGORIES`]
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+/// * [`HttpError::UnknownCategory`] - If the category is not found
+/// * [`HttpError::InvalidOperation`] - If the category has too many tags or a tag with the same name
+async fn create_tag(
+    category_id: web::Path<i64>,
+    payload: web::Json<CreateTagPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    if !user.has_permission(Permissions::MANAGE_CATEGORIES) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    payload
+        .validate()
+        .map_err(HttpError::Validation)?;
+
+    let category = app.database.fetch_category(category_id.into_inner().into()).await
+        .ok_or(HttpError::UnknownCategory)?;
+
+    if app.database.fetch_tags(category.id).await?.len() >= MAX_CATEGORY_TAGS {
+        return Err(HttpError::InvalidOperation(format!("Category can't have more than {} tags", MAX_CATEGORY_TAGS)))
+    }
+
+    let id = app.snowflake.lock().unwrap().build();
+    Tag::new(id, category.id, &payload.name, payload.color, payload.moderated)
+        .save(&app.pool)
+        .await
+        .map(|row| HttpResponse::Ok().json(row))
+}
+
+/// Modifies a tag of the category and return [`Tag`] - `PATCH /categories/{category_id}/tags/{tag_id}`
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_CATEGORIES`]
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+/// * [`HttpError::UnknownTag`] - If the tag is not found
+/// * [`HttpError::InvalidOperation`] - If the category has a tag with the same name
+async fn modify_tag(
+    path: web::Path<(i64, i64)>,
+    payload: web::Json<ModifyTagPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    if !user.has_permission(Permissions::MANAGE_CATEGORIES) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    payload
+        .validate()
+        .map_err(HttpError::Validation)?;
+
+    let mut tag = app.database.fetch_tag(path.0.into(), path.1.into()).await
+        .ok_or(HttpError::UnknownTag)?;
+
+    if let Some(name) = &payload.name {
+        tag.name = name.clone();
+    }
+    if let Some(color) = payload.color {
+        tag.color = color;
+    }
+    if let Some(moderated) = payload.moderated {
+        tag.moderated = moderated;
+    }
+
+    tag.edit(&app.pool)
+        .await
+        .map(|row| HttpResponse::Ok().json(row))
+}
+
+/// Deletes a tag of the category - `DELETE /categories/{category_id}/tags/{tag_id}`
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_CATEGORIES`]
+/// * [`HttpError::UnknownTag`] - If the tag is not found
+async fn delete_tag(
+    path: web::Path<(i64, i64)>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    if !user.has_permission(Permissions::MANAGE_CATEGORIES) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    let tag = app.database.fetch_tag(path.0.into(), path.1.into()).await
+        .ok_or(HttpError::UnknownTag)?;
+
+    tag.delete(&app.pool).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Returns [`CategoryWatch`] of the user - `GET /categories/{category_id}/watch`
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownCategory`] - If the category is not found
+async fn get_category_watch(
+    category_id: web::Path<i64>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let category = app.database.fetch_category(category_id.into_inner().into()).await
+        .ok_or(HttpError::UnknownCategory)?;
+
+    Ok(HttpResponse::Ok().json(app.database.fetch_category_watch(category.id, user.id).await))
+}
+
+/// Changes the watch level of a category and return [`CategoryWatch`] - `PUT /categories/{category_id}/watch`
+///
+/// The level applies to threads of the category the user didn't choose a watch level for.
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownCategory`] - If the category is not found
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+async fn modify_category_watch(
+    category_id: web::Path<i64>,
+    payload: web::Json<WatchPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let category = app.database.fetch_category(category_id.into_inner().into()).await
+        .ok_or(HttpError::UnknownCategory)?;
+
+    CategoryWatch::new(category.id, user.id, payload.level)
+        .save(&app.pool)
+        .await
+        .map(|row| HttpResponse::Ok().json(row))
+}
@@ -0,0 +1,261 @@
+use {
+    actix_web::{
+        web, mime, HttpResponse,
+        http::header::{self, ContentType, CacheControl, CacheDirective}
+    },
+    base64::prelude::{Engine as _, BASE64_STANDARD},
+    imagesize::ImageType,
+    validator::Validate,
+    crate::{
+        App,
+        routes::{Result, HttpError},
+        models::{
+            UserCredentials,
+            user::{User, Permissions},
+            requests::{CreateEmojiPayload, ModifyEmojiPayload},
+            emoji::{Emoji, Emojis, CustomEmoji, MAX_EMOJI_SIZE, EMOJI_CONTENT_TYPES}
+        },
+        storage::content::Content
+    }
+};
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("emojis")
+            .route("", web::get().to(get_emojis))
+            .route("", web::post().to(create_emoji))
+            .route("{emoji_id}", web::get().to(get_emoji))
+            .route("{emoji_id}", web::patch().to(modify_emoji))
+            .route("{emoji_id}", web::delete().to(delete_emoji))
+            .route("{emoji_id}/image", web::get().to(get_emoji_image))
+    );
+}
+
+/// Checks whether the user can use custom emoji from the message content and returns them.
+///
+/// Unknown emoji are left as plain text.
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user doesn't have permissions required by any of emoji
+pub(super) async fn check_message_emojis(app: &App, content: &str, user: &User) -> Result<Emojis> {
+    let ids = Emoji::parse_content(content).into_iter()
+        .filter_map(|emoji| emoji.id.map(i64::from))
+        .collect::<Vec<_>>();
+
+    if ids.is_empty() {
+        return Ok(Emojis::default())
+    }
+
+    let mut emojis = app.database.fetch_emojis(Some(&ids)).await?;
+    if emojis.iter().any(|emoji| !emoji.can_use(user)) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    // Same order as in messages fetched from the database
+    emojis.sort_by_key(|emoji| emoji.id.0);
+
+    Ok(Emojis(emojis.iter().map(Into::into).collect()))
+}
+
+/// Returns [`Vec<CustomEmoji>`] - `GET /emojis`
+async fn get_emojis(
+    app: web::Data<App>
+) -> Result<HttpResponse> {
+    let emojis = app.database.fetch_emojis(None).await?;
+
+    Ok(HttpResponse::Ok().json(emojis))
+}
+
+/// Returns [`CustomEmoji`] by given ID - `GET /emojis/{emoji_id}`
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownEmoji`] - If the emoji is not found
+async fn get_emoji(
+    emoji_id: web::Path<i64>,
+    app: web::Data<App>
+) -> Result<HttpResponse> {
+    let emoji = app.database.fetch_emoji(emoji_id.into_inner().into()).await
+        .ok_or(HttpError::UnknownEmoji)?;
+
+    Ok(HttpResponse::Ok().json(emoji))
+}
+
+/// Returns the image of the emoji - `GET /emojis/{emoji_id}/image`
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownEmoji`] - If the emoji is not found
+/// * [`HttpError::Storage`] - If the image couldn't be read
+async fn get_emoji_image(
+    emoji_id: web::Path<i64>,
+    app: web::Data<App>
+) -> Result<HttpResponse> {
+    let emoji = app.database.fetch_emoji(emoji_id.into_inner().into()).await
+        .ok_or(HttpError::UnknownEmoji)?;
+
+    let image = app.storage.get(&emoji.key(), None).await
+        .map_err(HttpError::Storage)?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType(emoji.content_type.parse().unwrap_or(mime::IMAGE_PNG)))
+        // Images can't be replaced, a new emoji has to be uploaded instead
+        .insert_header(CacheControl(vec![CacheDirective::Public, CacheDirective::MaxAge(31536000)]))
+        .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        .body(image))
+}
+
+/// Creates a new custom emoji and return [`CustomEmoji`] - `POST /emojis`
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_EMOJIS`]
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+/// * [`HttpError::InvalidOperation`] - If the image is malformed, too large, of unsupported or not declared type,
+///   or there is an emoji with the same name
+/// * [`HttpError::Storage`] - If the image couldn't be stored
+async fn create_emoji(
+    payload: web::Json<CreateEmojiPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    if !user.has_permission(Permissions::MANAGE_EMOJIS) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    payload
+        .validate()
+        .map_err(HttpError::Validation)?;
+
+    let (content_type, image) = decode_image(&payload.image)?;
+
+    let id = app.snowflake.lock().unwrap().build();
+    let emoji = CustomEmoji::new(id, &payload.name, &content_type, user.id)
+        .with_required_permissions(payload.required_permissions);
+
+    // The image is stored first and removed if the emoji can't be saved
+    let image = Content::from_bytes(&image).await
+        .map_err(HttpError::Storage)?;
+    app.storage.put(&emoji.key(), &emoji.content_type, &image).await
+        .map_err(HttpError::Storage)?;
+
+    let key = emoji.key();
+    match emoji.save(&app.pool).await {
+        Ok(emoji) => Ok(HttpResponse::Ok().json(emoji)),
+        Err(err) => {
+            _ = app.storage.delete(&key).await;
+            Err(err)
+        }
+    }
+}
+
+/// Decodes an image from data URI, e.g. `data:image/png;base64,...`
+///
+/// ### Errors
+///
+/// * [`HttpError::InvalidOperation`] - If the image is malformed, too large, of unsupported type or doesn't match the declared type
+fn decode_image(value: &str) -> Result<(String, Vec<u8>)> {
+    let (content_type, data) = value.strip_prefix("data:")
+        .and_then(|value| value.split_once(";base64,"))
+        .ok_or(HttpError::InvalidOperation("Image must be base64 encoded data URI".to_string()))?;
+
+    if !EMOJI_CONTENT_TYPES.contains(&content_type) {
+        return Err(HttpError::InvalidOperation(format!("Image must be one of {}", EMOJI_CONTENT_TYPES.join(", "))))
+    }
+
+    let image = BASE64_STANDARD.decode(data)
+        .map_err(|_| HttpError::InvalidOperation("Image must be base64 encoded data URI".to_string()))?;
+
+    if image.len() > MAX_EMOJI_SIZE {
+        return Err(HttpError::InvalidOperation(format!("Image can't be larger than {} bytes", MAX_EMOJI_SIZE)))
+    }
+
+    if imagesize::blob_size(&image).is_err() {
+        return Err(HttpError::InvalidOperation("Image is malformed".to_string()))
+    }
+
+    let image_type = match imagesize::image_type(&image) {
+        Ok(ImageType::Png) => "image/png",
+        Ok(ImageType::Jpeg) => "image/jpeg",
+        Ok(ImageType::Gif) => "image/gif",
+        Ok(ImageType::Webp) => "image/webp",
+        _ => return Err(HttpError::InvalidOperation(format!("Image must be one of {}", EMOJI_CONTENT_TYPES.join(", "))))
+    };
+
+    if image_type != content_type {
+        return Err(HttpError::InvalidOperation(format!("Image is {image_type}, not {content_type}")))
+    }
+
+    Ok((content_type.to_string(), image))
+}
+
+/// Modifies a custom emoji and return [`CustomEmoji`] - `PATCH /emojis/{emoji_id}`
+///
+/// Setting `required_permissions` to `0` lifts the restriction.
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_EMOJIS`]
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+/// * [`HttpError::UnknownEmoji`] - If the emoji is not found
+/// * [`HttpError::InvalidOperation`] - If there is an emoji with the same name
+async fn modify_emoji(
+    emoji_id: web::Path<i64>,
+    payload: web::Json<ModifyEmojiPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    if !user.has_permission(Permissions::MANAGE_EMOJIS) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    payload
+        .validate()
+        .map_err(HttpError::Validation)?;
+
+    let mut emoji = app.database.fetch_emoji(emoji_id.into_inner().into()).await
+        .ok_or(HttpError::UnknownEmoji)?;
+
+    if let Some(name) = &payload.name {
+        emoji.name = name.clone();
+    }
+    if let Some(permissions) = payload.required_permissions {
+        emoji = emoji.with_required_permissions(Some(permissions));
+    }
+
+    emoji.edit(&app.pool)
+        .await
+        .map(|row| HttpResponse::Ok().json(row))
+}
+
+/// Deletes a custom emoji with reactions using it - `DELETE /emojis/{emoji_id}`
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_EMOJIS`]
+/// * [`HttpError::UnknownEmoji`] - If the emoji is not found
+async fn delete_emoji(
+    emoji_id: web::Path<i64>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    if !user.has_permission(Permissions::MANAGE_EMOJIS) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    let emoji = app.database.fetch_emoji(emoji_id.into_inner().into()).await
+        .ok_or(HttpError::UnknownEmoji)?;
+
+    let key = emoji.key();
+    emoji.delete(&app.pool).await?;
+    _ = app.storage.delete(&key).await;
+
+    Ok(HttpResponse::NoContent().finish())
+}
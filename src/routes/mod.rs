@@ -15,6 +15,9 @@ mod categories;
 mod auth;
 mod threads;
 mod gateway;
+mod emojis;
+mod reports;
+mod search;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg
@@ -25,6 +28,9 @@ pub fn config(cfg: &mut web::ServiceConfig) {
                 .configure(users::config)
                 .configure(categories::config)
                 .configure(threads::config)
+                .configure(emojis::config)
+                .configure(reports::config)
+                .configure(search::config)
         )
         .service(
             web::scope("gateway")
@@ -44,6 +50,20 @@ pub enum HttpError {
     UnknownThread,
     #[error("Unknown Message")]
     UnknownMessage,
+    #[error("Unknown Tag")]
+    UnknownTag,
+    #[error("Unknown Poll")]
+    UnknownPoll,
+    #[error("Unknown Emoji")]
+    UnknownEmoji,
+    #[error("Unknown Attachment")]
+    UnknownAttachment,
+    #[error("Unknown Report")]
+    UnknownReport,
+    #[error("Unknown Notification")]
+    UnknownNotification,
+    #[error("Unknown Bookmark")]
+    UnknownBookmark,
     #[error("{0}")]
     Payload(#[from] actix_web::error::JsonPayloadError),
     #[error("Validation error: {0}")]
@@ -56,6 +76,10 @@ pub enum HttpError {
     Header(String),
     #[error("Error while interacting with the database: {0}")]
     Database(#[from] sqlx::Error),
+    #[error("{0}")]
+    Multipart(#[from] actix_multipart::MultipartError),
+    #[error("Error while interacting with the storage: {0}")]
+    Storage(std::io::Error),
     #[error("Missing access")]
     MissingAccess,
     #[error("The username is already taken")]
@@ -67,7 +91,13 @@ pub enum HttpError {
     #[error("{0}")]
     InvalidCredentials(String),
     #[error("Resource can't be deleted due to its policy")]
-    Undeletable
+    Undeletable,
+    #[error("{0}")]
+    InvalidOperation(String),
+    #[error("{0}")]
+    RequirementNotMet(String),
+    #[error("{0}")]
+    RateLimited(String)
 }
 
 impl actix_web::ResponseError for HttpError {
@@ -78,21 +108,34 @@ impl actix_web::ResponseError for HttpError {
             | HttpError::Query(..)
             | HttpError::Path(..)
             | HttpError::Header(..)
+            | HttpError::Multipart(..)
             | HttpError::TakenUsername
             | HttpError::WeekPassword
             | HttpError::InvalidCredentials(..)
-            | HttpError::Undeletable => StatusCode::BAD_REQUEST,
+            | HttpError::Undeletable
+            | HttpError::InvalidOperation(..) => StatusCode::BAD_REQUEST,
 
             HttpError::Unauthorized => StatusCode::UNAUTHORIZED,
 
-            HttpError::MissingAccess => StatusCode::FORBIDDEN,
+            HttpError::RateLimited(..) => StatusCode::TOO_MANY_REQUESTS,
+
+            HttpError::MissingAccess
+            | HttpError::RequirementNotMet(..) => StatusCode::FORBIDDEN,
 
             HttpError::UnknownUser
             | HttpError::UnknownCategory
             | HttpError::UnknownThread
-            | HttpError::UnknownMessage => StatusCode::NOT_FOUND,
+            | HttpError::UnknownMessage
+            | HttpError::UnknownTag
+            | HttpError::UnknownPoll
+            | HttpError::UnknownEmoji
+            | HttpError::UnknownAttachment
+            | HttpError::UnknownReport
+            | HttpError::UnknownNotification
+            | HttpError::UnknownBookmark => StatusCode::NOT_FOUND,
 
-            HttpError::Database(..) => StatusCode::INTERNAL_SERVER_ERROR
+            HttpError::Database(..)
+            | HttpError::Storage(..) => StatusCode::INTERNAL_SERVER_ERROR
         }
     }
 
@@ -104,6 +147,13 @@ impl actix_web::ResponseError for HttpError {
                 HttpError::UnknownCategory => 10001,
                 HttpError::UnknownThread => 10002,
                 HttpError::UnknownMessage => 10003,
+                HttpError::UnknownTag => 10004,
+                HttpError::UnknownPoll => 10005,
+                HttpError::UnknownEmoji => 10006,
+                HttpError::UnknownAttachment => 10007,
+                HttpError::UnknownReport => 10008,
+                HttpError::UnknownNotification => 10009,
+                HttpError::UnknownBookmark => 10010,
 
                 // The 2xxxx class of error code indicates that data was malformed or invalid
                 HttpError::Payload(..) => 20000,
@@ -115,13 +165,20 @@ impl actix_web::ResponseError for HttpError {
                 HttpError::Database(..) => 20007,
                 HttpError::Undeletable => 20009,
                 HttpError::TakenUsername => 20010,
+                HttpError::InvalidOperation(..) => 20011,
+                HttpError::Multipart(..) => 20013,
 
                 // The 3xxxx class of error code indicates that authorization process failed
                 HttpError::Unauthorized => 30000,
                 HttpError::WeekPassword => 30001,
 
                 // The 4xxxx class of error code indicates that recourse requires special permission
-                HttpError::MissingAccess => 40000
+                HttpError::MissingAccess => 40000,
+                HttpError::RequirementNotMet(..) => 40001,
+                HttpError::RateLimited(..) => 40002,
+
+                // The 5xxxx class of error code indicates that the server failed to process a valid request
+                HttpError::Storage(..) => 50000
             },
             description: self.to_string(),
         })
@@ -0,0 +1,147 @@
+use {
+    actix_web::{web, HttpResponse},
+    serde::Deserialize,
+    validator::Validate,
+    crate::{
+        App, DispatchTarget,
+        routes::{Result, HttpError},
+        models::{
+            UserCredentials,
+            user::{User, Permissions},
+            requests::ModifyReportPayload,
+            report::ReportStatus,
+            gateway::GatewayEvent::ReportUpdate
+        },
+        utils::snowflake::Snowflake
+    }
+};
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("reports")
+            .route("", web::get().to(get_reports))
+            .route("{report_id}", web::get().to(get_report))
+            .route("{report_id}", web::patch().to(modify_report))
+    );
+}
+
+/// Checks whether the user can manage the moderation queue.
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_MESSAGES`]
+fn check_moderator(user: &User) -> Result<()> {
+    match user.has_permission(Permissions::MANAGE_MESSAGES) {
+        true => Ok(()),
+        false => Err(HttpError::MissingAccess)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SearchReportsQuery {
+    pub status: Option<ReportStatus>,
+    pub assignee_id: Option<Snowflake>,
+    pub limit: Option<u16>,
+    pub after: Option<Snowflake>
+}
+
+/// Returns [`Vec<Report>`](crate::models::report::Report) from the moderation queue, oldest first - `GET /reports`
+///
+/// ### Query
+///
+/// * `status` - Get only reports with this status
+/// * `assignee_id` - Get only reports assigned to this moderator
+/// * `limit` - Max number of reports to return (1-100, default 50)
+/// * `after` - Get reports after this report ID
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_MESSAGES`]
+async fn get_reports(
+    query: web::Query<SearchReportsQuery>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    check_moderator(&user)?;
+
+    let reports = app.database.fetch_reports(None, query.status, query.assignee_id, query.limit, query.after).await?;
+
+    Ok(HttpResponse::Ok().json(reports))
+}
+
+/// Returns [`Report`](crate::models::report::Report) by given ID - `GET /reports/{report_id}`
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_MESSAGES`]
+/// * [`HttpError::UnknownReport`] - If the report is not found
+async fn get_report(
+    report_id: web::Path<i64>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    check_moderator(&user)?;
+
+    let report = app.database.fetch_report(report_id.into_inner().into()).await
+        .ok_or(HttpError::UnknownReport)?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Modifies a report and return [`Report`](crate::models::report::Report) - `PATCH /reports/{report_id}`
+///
+/// Moving the report out of `open` status records who resolved it and when. Setting `assignee_id`
+/// to `null` unassigns the report.
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_MESSAGES`]
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+/// * [`HttpError::UnknownReport`] - If the report is not found
+/// * [`HttpError::UnknownUser`] - If the assignee is not found
+/// * [`HttpError::InvalidOperation`] - If the assignee does not have [`Permissions::MANAGE_MESSAGES`]
+async fn modify_report(
+    report_id: web::Path<i64>,
+    payload: web::Json<ModifyReportPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    check_moderator(&user)?;
+
+    payload
+        .validate()
+        .map_err(HttpError::Validation)?;
+
+    let mut report = app.database.fetch_report(report_id.into_inner().into()).await
+        .ok_or(HttpError::UnknownReport)?;
+
+    let payload = payload.into_inner();
+    if let Some(assignee_id) = payload.assignee_id {
+        if let Some(assignee_id) = assignee_id {
+            let assignee = app.database.fetch_user(assignee_id).await
+                .ok_or(HttpError::UnknownUser)?;
+
+            if !assignee.has_permission(Permissions::MANAGE_MESSAGES) {
+                return Err(HttpError::InvalidOperation("Reports can be assigned only to moderators".to_string()))
+            }
+        }
+        report.assignee_id = assignee_id;
+    }
+
+    if let Some(resolution_note) = payload.resolution_note {
+        report.resolution_note = Some(resolution_note);
+    }
+
+    if let Some(status) = payload.status {
+        report = report.with_status(status, user.id);
+    }
+
+    let report = report.edit(&app.pool).await?;
+
+    _ = app.dispatch(DispatchTarget::Moderators, ReportUpdate(report.clone()));
+
+    Ok(HttpResponse::Ok().json(report))
+}
@@ -0,0 +1,85 @@
+use {
+    actix_web::{web, HttpResponse},
+    chrono::{DateTime, Utc},
+    serde::Deserialize,
+    validator::Validate,
+    crate::{
+        App,
+        routes::{Result, HttpError},
+        models::{
+            UserCredentials,
+            user::Permissions,
+            search::SearchFilter
+        },
+        utils::snowflake::Snowflake
+    }
+};
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("search", web::get().to(search));
+}
+
+#[derive(Deserialize, Validate)]
+pub struct SearchQuery {
+    #[validate(length(min = 1, max = 256))]
+    pub query: String,
+    pub author_id: Option<Snowflake>,
+    pub category_id: Option<Snowflake>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub has_attachments: Option<bool>,
+    #[serde(default)]
+    pub deleted: bool,
+    #[validate(range(min = 1, max = 100, message = "Limit must be between 1 and 100"))]
+    pub limit: Option<u16>,
+    pub before: Option<Snowflake>
+}
+
+/// Returns [`Vec<SearchResult>`](crate::models::search::SearchResult) of messages and thread titles matching the query, newest first - `GET /search`
+///
+/// Private threads are searched only if the user can see them.
+///
+/// ### Query
+///
+/// * `query` - The words to search for, supports `"quoted phrases"`, `or` and `-` to exclude words
+/// * `author_id` - Get only messages of this user
+/// * `category_id` - Get only messages from threads of this category
+/// * `since` - Get only messages sent after this time
+/// * `until` - Get only messages sent before this time
+/// * `has_attachments` - Get only messages with (`true`) or without (`false`) attachments
+/// * `deleted` - Search deleted messages and threads too, requires [`Permissions::MANAGE_MESSAGES`] and [`Permissions::MANAGE_THREADS`]
+/// * `limit` - Max number of results to return (1-100, default 25)
+/// * `before` - Get results before this message ID
+///
+/// ### Errors
+///
+/// * [`HttpError::Validation`] - If the query is empty or too long, or the limit is out of range
+/// * [`HttpError::MissingAccess`] - If deleted items are requested without [`Permissions::MANAGE_MESSAGES`]
+///   and [`Permissions::MANAGE_THREADS`]
+async fn search(
+    query: web::Query<SearchQuery>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    if query.deleted && !user.has_permission(Permissions::MANAGE_MESSAGES | Permissions::MANAGE_THREADS) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    query.validate().map_err(HttpError::Validation)?;
+
+    let query = query.into_inner();
+    let filter = SearchFilter {
+        query: query.query,
+        author_id: query.author_id,
+        category_id: query.category_id,
+        since: query.since,
+        until: query.until,
+        has_attachments: query.has_attachments,
+        deleted: query.deleted
+    };
+    let results = app.database.search_messages(&user, &filter, query.limit, query.before).await?;
+
+    Ok(HttpResponse::Ok().json(results))
+}
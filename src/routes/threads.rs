@@ -1,40 +1,263 @@
 use {
     actix_web::{
-        web, HttpResponse
+        web, guard, mime, HttpResponse,
+        guard::GuardContext,
+        web::BytesMut,
+        error::JsonPayloadError,
+        http::{
+            StatusCode,
+            header::{
+                self, ContentType, ContentDisposition, DispositionType, DispositionParam,
+                ContentEncoding, EntityTag, Range
+            }
+        }
     },
+    actix_multipart::Multipart,
+    futures::TryStreamExt,
     validator::Validate,
     serde::Deserialize,
+    chrono::{Duration, Utc},
     crate::{
-        App,
-        DispatchTarget,
-        routes::{Result, HttpError},
+        App, DispatchTarget,
+        routes::{Result, HttpError, categories::check_thread_tags, emojis::check_message_emojis},
         models::{
             UserCredentials,
-            user::Permissions,
-            requests::{CreateMessagePayload, ModifyMessagePayload},
-            message::{Message, MessageFlags},
-            gateway::GatewayEvent::*
+            user::{User, UserFlags, Permissions},
+            requests::{
+                CreateMessagePayload, ModifyMessagePayload, BulkDeleteMessagesPayload,
+                MoveThreadPayload, MergeThreadPayload, SplitThreadPayload,
+                ThreadRequirementsPayload, JoinThreadPayload, ReviewJoinRequestPayload,
+                ModifyThreadTagsPayload, AcceptAnswerPayload, VotePollPayload, WatchPayload,
+                AckThreadPayload, CreateReportPayload
+            },
+            read_state::ReadState,
+            report::Report,
+            notification::{Notification, NotificationType},
+            attachment::{Attachment, AttachmentLimits, MAX_MESSAGE_ATTACHMENTS},
+            mention::{Mentions, MAX_MESSAGE_MENTIONS, MENTION_RATE_LIMIT, MENTION_RATE_LIMIT_WINDOW},
+            emoji::{Emoji, CustomEmoji},
+            watch::{ThreadWatch, WatchLevel},
+            join_request::{JoinRequest, JoinRequestStatus},
+            message::{Message, MessageFlags, ContentFormat},
+            thread::{Thread, ThreadFlags, ThreadType},
+            gateway::{DeletedMessages, GatewayEvent::*}
         },
-        utils::snowflake::Snowflake
+        utils::{snowflake::Snowflake, markdown},
+        storage::content::{Content, ContentWriter}
     }
 };
 
+/// The maximum size of `payload_json` field of multipart requests
+const MAX_PAYLOAD_JSON_SIZE: usize = 64 * 1024;
+
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("threads")
             .route("{thread_id}", web::get().to(get_thread))
             .route("{thread_id}", web::delete().to(delete_thread))
+            .route("{thread_id}/restore", web::post().to(restore_thread))
+            .route("{thread_id}/move", web::post().to(move_thread))
+            .route("{thread_id}/merge", web::post().to(merge_thread))
+            .route("{thread_id}/split", web::post().to(split_thread))
+            .route("{thread_id}/members", web::get().to(get_thread_members))
+            .route("{thread_id}/members/{user_id}", web::put().to(add_thread_member))
+            .route("{thread_id}/members/{user_id}", web::delete().to(remove_thread_member))
+            .route("{thread_id}/requirements", web::put().to(modify_thread_requirements))
+            .route("{thread_id}/tags", web::put().to(modify_thread_tags))
+            .route("{thread_id}/answer", web::put().to(accept_answer))
+            .route("{thread_id}/answer", web::delete().to(remove_accepted_answer))
+            .route("{thread_id}/poll/votes", web::put().to(vote_poll))
+            .route("{thread_id}/poll/votes", web::delete().to(remove_poll_votes))
+            .route("{thread_id}/watch", web::get().to(get_thread_watch))
+            .route("{thread_id}/watch", web::put().to(modify_thread_watch))
+            .route("{thread_id}/ack", web::post().to(ack_thread))
+            .route("{thread_id}/join", web::post().to(join_thread))
+            .route("{thread_id}/join-requests", web::get().to(get_join_requests))
+            .route("{thread_id}/join-requests/{user_id}", web::patch().to(review_join_request))
             .service(
                 web::scope("{thread_id}/messages")
+                    .route("", web::post().guard(guard::fn_guard(is_multipart)).to(create_message_with_attachments))
                     .route("", web::post().to(create_message))
                     .route("", web::get().to(get_messages))
+                    .route("bulk-delete", web::post().to(bulk_delete_messages))
                     .route("{message_id}", web::get().to(get_message))
                     .route("{message_id}", web::patch().to(modify_message))
                     .route("{message_id}", web::delete().to(delete_message))
+                    .route("{message_id}/revisions", web::get().to(get_message_revisions))
+                    .route("{message_id}/restore", web::post().to(restore_message))
+                    .route("{message_id}/report", web::post().to(report_message))
+                    .route("{message_id}/attachments/{attachment_id}", web::get().to(get_attachment))
+                    .route("{message_id}/reactions/{emoji}", web::get().to(get_reactions))
+                    .route("{message_id}/reactions/{emoji}/@me", web::put().to(add_reaction))
+                    .route("{message_id}/reactions/{emoji}/@me", web::delete().to(remove_reaction))
             )
     );
 }
 
+fn is_multipart(ctx: &GuardContext) -> bool {
+    ctx.header::<ContentType>()
+        .is_some_and(|content_type| content_type.type_() == mime::MULTIPART)
+}
+
+/// Fetches a thread and checks whether the user can see it.
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownThread`] - If the thread is not found, or deleted and the user does not have
+///   [`Permissions::MANAGE_THREADS`]
+/// * [`HttpError::MissingAccess`] - If the thread is private and the user is not its member
+pub(super) async fn fetch_visible_thread(app: &App, thread_id: Snowflake, user: &User) -> Result<Thread> {
+    let thread = app.database.fetch_thread(thread_id)
+        .await?;
+
+    if thread.deleted_at.is_some() && !user.has_permission(Permissions::MANAGE_THREADS) {
+        return Err(HttpError::UnknownThread)
+    }
+
+    if !app.database.has_thread_access(&thread, user).await {
+        return Err(HttpError::MissingAccess)
+    }
+
+    Ok(thread)
+}
+
+/// Fetches a message of the thread which isn't deleted.
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownMessage`] - If the message is not found or deleted
+pub(super) async fn fetch_undeleted_message(app: &App, thread_id: Snowflake, message_id: Snowflake) -> Result<Message> {
+    app.database.fetch_message(thread_id, message_id).await
+        .filter(|message| !message.is_deleted())
+        .ok_or(HttpError::UnknownMessage)
+}
+
+/// Fetches a message of the thread, deleted messages are available only to users with [`Permissions::MANAGE_MESSAGES`].
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownMessage`] - If the message is not found, or deleted and the user can't see it
+async fn fetch_readable_message(app: &App, thread_id: Snowflake, message_id: Snowflake, user: &User) -> Result<Message> {
+    app.database.fetch_message(thread_id, message_id).await
+        .filter(|message| !message.is_deleted() || user.has_permission(Permissions::MANAGE_MESSAGES))
+        .ok_or(HttpError::UnknownMessage)
+}
+
+/// Hides contents of the message if it's deleted and the user does not have [`Permissions::MANAGE_MESSAGES`].
+fn visible_message(message: Message, user: &User) -> Message {
+    if message.is_deleted() && !user.has_permission(Permissions::MANAGE_MESSAGES) {
+        return message.into_tombstone()
+    }
+
+    message
+}
+
+/// Resolves users mentioned in the message content and checks whether the user can mention them.
+///
+/// Users already mentioned in `previous` content are not counted towards the rate limit.
+///
+/// ### Errors
+///
+/// * [`HttpError::InvalidOperation`] - If the content mentions more than [`MAX_MESSAGE_MENTIONS`] users
+/// * [`HttpError::RateLimited`] - If the user does not have [`Permissions::MANAGE_MESSAGES`] and mentioned more than
+///   [`MENTION_RATE_LIMIT`] users within [`MENTION_RATE_LIMIT_WINDOW`]
+pub(super) async fn check_message_mentions(app: &App, content: &str, user: &User, previous: &Mentions) -> Result<Mentions> {
+    let (user_ids, usernames) = markdown::mentions(content);
+
+    if user_ids.is_empty() && usernames.is_empty() {
+        return Ok(Mentions::default())
+    }
+
+    let mentions = Mentions(app.database.fetch_mentioned_users(&user_ids, &usernames).await?);
+    if mentions.0.len() > MAX_MESSAGE_MENTIONS {
+        return Err(HttpError::InvalidOperation(format!("Message can't mention more than {} users", MAX_MESSAGE_MENTIONS)))
+    }
+
+    let new_count = mentions.0.iter().filter(|mentioned| !previous.contains(mentioned.id)).count() as i64;
+    if new_count > 0 && !user.has_permission(Permissions::MANAGE_MESSAGES) {
+        let recent_count = app.database.fetch_recent_mention_count(user.id, Utc::now() - MENTION_RATE_LIMIT_WINDOW).await?;
+
+        if recent_count + new_count > MENTION_RATE_LIMIT {
+            return Err(HttpError::RateLimited("You are mentioning too many users, try again later".to_string()))
+        }
+    }
+
+    Ok(mentions)
+}
+
+/// Sends the message in a `MENTION_CREATE` event and a notification to newly mentioned users who can see the thread,
+/// except its author.
+///
+/// ### Returns
+///
+/// * IDs of notified users.
+pub(super) async fn dispatch_mentions(app: &App, thread: &Thread, message: &Message, user_ids: &[Snowflake]) -> Vec<Snowflake> {
+    let mut notified = Vec::new();
+
+    for mentioned in message.mentions.0.iter().filter(|mentioned| user_ids.contains(&mentioned.id) && mentioned.id != message.author.id) {
+        if app.database.has_thread_access(thread, mentioned).await {
+            _ = app.dispatch(DispatchTarget::User(mentioned.id), MentionCreate(message.clone()));
+            notified.push(mentioned.id);
+
+            let id = app.snowflake.lock().unwrap().build();
+            _ = app.notify(Notification::new(id, mentioned.id, NotificationType::Mention, message.author.id).with_message(thread.id, message.id)).await;
+        }
+    }
+
+    notified
+}
+
+/// Checks whether the user meets requirements of the thread, except the question.
+///
+/// Thread author, members and users with [`Permissions::MANAGE_THREADS`] are not checked.
+///
+/// ### Returns
+///
+/// * `true` if the user still has to answer the thread's question, otherwise `false`.
+///
+/// ### Errors
+///
+/// * [`HttpError::RequirementNotMet`] - If the user doesn't meet any of requirements
+async fn check_thread_requirements(app: &App, thread: &Thread, user: &User) -> Result<bool> {
+    let requirements = &thread.requirements;
+
+    if requirements.is_empty()
+        || user.id == thread.author.id
+        || user.has_permission(Permissions::MANAGE_THREADS)
+        || app.database.is_thread_member(thread.id, user.id).await {
+        return Ok(false)
+    }
+
+    if let Some(age) = requirements.min_account_age {
+        let old_enough = Duration::try_seconds(age)
+            .and_then(|age| user.id.created_at().checked_add_signed(age))
+            .is_some_and(|date| date <= Utc::now());
+        if !old_enough {
+            return Err(HttpError::RequirementNotMet(format!(
+                "Your account must be at least {} days old to send messages in this thread", age / 86400 + i64::from(age % 86400 != 0)
+            )))
+        }
+    }
+
+    if let Some(count) = requirements.min_message_count {
+        if app.database.fetch_user_message_count(user.id).await? < i64::from(count) {
+            return Err(HttpError::RequirementNotMet(format!(
+                "You must send at least {} messages before sending messages in this thread", count
+            )))
+        }
+    }
+
+    if let Some(permissions) = requirements.required_permissions {
+        if !user.has_permission(permissions) {
+            return Err(HttpError::RequirementNotMet(
+                "You don't have permissions required to send messages in this thread".to_string()
+            ))
+        }
+    }
+
+    Ok(requirements.question.is_some())
+}
+
 /// Returns [`Thread`] by given ID - `GET /threads/{thread_id}`
 ///
 /// ### Errors
@@ -42,11 +265,14 @@ pub fn config(cfg: &mut web::ServiceConfig) {
 /// * [`HttpError::UnknownThread`] - If the thread is not found
 /// * [`HttpError::UnknownUser`] - If the owner of the thread is not found
 /// * [`HttpError::UnknownMessage`] - If the original message of the thread is not found
+/// * [`HttpError::MissingAccess`] - If the thread is private and the user is not its member
 async fn get_thread(
     thread_id: web::Path<i64>,
     app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
 ) -> Result<HttpResponse> {
-    let thread = app.database.fetch_thread(thread_id.to_owned().into())
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, thread_id.to_owned().into(), &user)
         .await?;
 
     Ok(HttpResponse::Ok().json(thread))
@@ -54,178 +280,1703 @@ async fn get_thread(
 
 /// Deletes a thread - `DELETE /threads/{thread_id}`
 ///
+/// The thread is hidden from users without [`Permissions::MANAGE_THREADS`] and purged after the retention period.
+///
 /// ### Path
 ///
 /// * `thread_id` - The ID of the thread to delete
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user is not the thread author and does not have [`Permissions::MANAGE_THREADS`]
+/// * [`HttpError::UnknownThread`] - If the thread is not found or already deleted
 async fn delete_thread(
     thread_id: web::Path<i64>,
     app: web::Data<App>,
     credentials: Option<web::ReqData<UserCredentials>>
 ) -> Result<HttpResponse> {
     let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
-    let thread = app.database.fetch_thread(thread_id.to_owned().into())
+    let thread = fetch_visible_thread(&app, thread_id.to_owned().into(), &user)
         .await?;
 
     if user.id != thread.author.id && !user.has_permission(Permissions::MANAGE_THREADS) {
         return Err(HttpError::MissingAccess);
     }
 
-    _ = app.dispatch(DispatchTarget::Global, ThreadDelete {thread_id: thread_id.to_owned().into()});
+    let target = app.database.fetch_dispatch_target(&thread).await?;
+    thread.soft_delete(&app.pool, user.id).await?;
 
-    thread.delete(&app.pool).await?;
+    _ = app.dispatch(target, ThreadDelete {thread_id: thread_id.to_owned().into()});
 
     Ok(HttpResponse::NoContent().finish())
 }
 
-#[derive(Deserialize)]
-pub struct SearchMessagesQuery {
-    pub limit: Option<u16>,
-    pub before: Option<Snowflake>,
-    pub after: Option<Snowflake>
+/// Restores a deleted thread and return [`Thread`] - `POST /threads/{thread_id}/restore`
+///
+/// ### Path
+///
+/// * `thread_id` - The ID of the thread to restore
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_THREADS`]
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+/// * [`HttpError::InvalidOperation`] - If the thread is not deleted, or it was merged into another thread
+async fn restore_thread(
+    thread_id: web::Path<i64>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    if !user.has_permission(Permissions::MANAGE_THREADS) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    let thread = app.database.fetch_thread(thread_id.into_inner().into())
+        .await?;
+
+    if thread.deleted_at.is_none() {
+        return Err(HttpError::InvalidOperation("The thread is not deleted".to_string()))
+    }
+
+    if thread.original_message.thread_id != thread.id {
+        return Err(HttpError::InvalidOperation("Merged threads can't be restored".to_string()))
+    }
+
+    let thread = thread.restore(&app.pool).await?;
+
+    _ = app.dispatch(app.database.fetch_dispatch_target(&thread).await?, ThreadCreate(thread.clone()));
+
+    Ok(HttpResponse::Ok().json(thread))
 }
 
-/// Returns [`Vec<Message>`] of the thread - `GET /threads/{thread_id}/messages`
+/// Moves a thread to another category and return [`Thread`] - `POST /threads/{thread_id}/move`
 ///
-/// ### Query
+/// Tags of the thread are removed, since they belong to the previous category.
 ///
-/// * `limit` - Max number of messages to return (1-100, default 50)
-/// * `after` - Get messages after this message ID
-/// * `before` - Get messages before this message ID
+/// ### Path
+///
+/// * `thread_id` - The ID of the thread to move
 ///
 /// ### Errors
 ///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_THREADS`], or
+///   [`Permissions::MANAGE_CATEGORIES`] when either of categories is locked
 /// * [`HttpError::UnknownThread`] - If the thread is not found
-async fn get_messages(
-    path: web::Path<i64>,
-    query: web::Query<SearchMessagesQuery>,
+/// * [`HttpError::UnknownCategory`] - If the category is not found
+/// * [`HttpError::InvalidOperation`] - If the thread is already in the category
+async fn move_thread(
+    thread_id: web::Path<i64>,
+    payload: web::Json<MoveThreadPayload>,
     app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
 ) -> Result<HttpResponse> {
-    let messages = app.database.fetch_messages(path.to_owned().into(), query.limit, query.before, query.after).await?;
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
 
-    Ok(HttpResponse::Ok().json(messages))
+    if !user.has_permission(Permissions::MANAGE_THREADS) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    let thread = app.database.fetch_thread(thread_id.to_owned().into())
+        .await?;
+    let source = app.database.fetch_category(thread.category_id).await
+        .ok_or(HttpError::UnknownCategory)?;
+    let destination = app.database.fetch_category(payload.category_id).await
+        .ok_or(HttpError::UnknownCategory)?;
+
+    if source.id == destination.id {
+        return Err(HttpError::InvalidOperation("The thread is already in this category".to_string()))
+    }
+
+    if (source.locked || destination.locked) && !user.has_permission(Permissions::MANAGE_CATEGORIES) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    let id = app.snowflake.lock().unwrap().build();
+    let mut tx = app.pool.begin().await?;
+
+    let thread = thread.set_category(&mut *tx, destination.id).await?
+        .set_tags(&mut *tx, vec![]).await?;
+    let message = Message::new(id, user.clone(), thread.id,
+        &format!("{} moved the thread from {} to {}", user.username, source.title, destination.title),
+        Some(MessageFlags::SYSTEM)
+    )
+        .save(&mut *tx).await?;
+
+    tx.commit().await?;
+
+    let thread = app.database.fetch_thread(thread.id).await?;
+    let target = app.database.fetch_dispatch_target(&thread).await?;
+
+    _ = app.dispatch(target.clone(), ThreadUpdate(thread.clone()));
+    _ = app.dispatch(target, MessageCreate(message));
+
+    if thread.author.id != user.id {
+        let id = app.snowflake.lock().unwrap().build();
+        _ = app.notify(Notification::new(id, thread.author.id, NotificationType::ThreadMove, user.id).with_thread(thread.id)).await;
+    }
+
+    Ok(HttpResponse::Ok().json(thread))
 }
 
-/// Returns [`Message`] by given ID - `GET /threads/{thread_id}/messages/{message_id}`
+/// Merges messages of a thread into another thread and return the resulting [`Thread`] - `POST /threads/{thread_id}/merge`
+///
+/// The merged thread is soft deleted and its messages keep their chronological order in the destination thread.
+/// Members of a merged private thread are added to the destination thread.
 ///
 /// ### Path
 ///
-/// * `thread_id` - The ID of the thread
-/// * `message_id` - The ID of the message to fetch
+/// * `thread_id` - The ID of the thread to merge
 ///
 /// ### Errors
 ///
-/// * [`HttpError::UnknownMessage`] - If the message is not found
-async fn get_message(
-    path: web::Path<(i64, i64)>,
-    app: web::Data<App>
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_THREADS`]
+/// * [`HttpError::UnknownThread`] - If either of threads is not found or deleted
+/// * [`HttpError::InvalidOperation`] - If the thread is merged into itself, or only one of threads is private
+async fn merge_thread(
+    thread_id: web::Path<i64>,
+    payload: web::Json<MergeThreadPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
 ) -> Result<HttpResponse> {
-    let message = app.database.fetch_message(path.to_owned().0.into(), path.to_owned().1.into())
-        .await.ok_or(HttpError::UnknownMessage)?;
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
 
-    Ok(HttpResponse::Ok().json(message))
+    if !user.has_permission(Permissions::MANAGE_THREADS) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    let source = app.database.fetch_thread(thread_id.to_owned().into())
+        .await?;
+    let destination = app.database.fetch_thread(payload.thread_id)
+        .await?;
+
+    if source.deleted_at.is_some() || destination.deleted_at.is_some() {
+        return Err(HttpError::UnknownThread)
+    }
+
+    if source.id == destination.id {
+        return Err(HttpError::InvalidOperation("The thread can't be merged into itself".to_string()))
+    }
+
+    if (source.kind == ThreadType::Private) != (destination.kind == ThreadType::Private) {
+        return Err(HttpError::InvalidOperation("Private threads can only be merged with private threads".to_string()))
+    }
+
+    let source_target = app.database.fetch_dispatch_target(&source).await?;
+    let id = app.snowflake.lock().unwrap().build();
+    let mut tx = app.pool.begin().await?;
+
+    let moved = source.move_messages(&mut *tx, destination.id, None).await?;
+    let original_message = source.original_message.clone();
+    let flags = original_message.flags.difference(MessageFlags::UNDELETEABLE);
+    original_message.set_flags(&mut *tx, flags).await?;
+
+    if destination.kind == ThreadType::Private {
+        destination.copy_members(&mut *tx, source.id).await?;
+    }
+
+    let message = Message::new(id, user.clone(), destination.id,
+        &format!("{} merged {} messages from {} into this thread", user.username, moved, source.title),
+        Some(MessageFlags::SYSTEM)
+    )
+        .save(&mut *tx).await?;
+
+    let source_id = source.id;
+    source.soft_delete(&mut *tx, user.id).await?;
+
+    tx.commit().await?;
+
+    let thread = app.database.fetch_thread(destination.id).await?;
+    let target = app.database.fetch_dispatch_target(&thread).await?;
+
+    _ = app.dispatch(source_target, ThreadDelete {thread_id: source_id});
+    _ = app.dispatch(target.clone(), ThreadUpdate(thread.clone()));
+    _ = app.dispatch(target, MessageCreate(message));
+
+    Ok(HttpResponse::Ok().json(thread))
 }
 
-/// Creates a new message and return [`Message`] - `POST /threads/{thread_id}/messages`
+/// Moves a range of messages into a new thread and return the new [`Thread`] - `POST /threads/{thread_id}/split`
+///
+/// The first message of the range becomes the original message of the new thread, which keeps the type and members of
+/// the split thread.
 ///
 /// ### Path
 ///
-/// * `thread_id` - The ID of the thread
+/// * `thread_id` - The ID of the thread to split
 ///
 /// ### Errors
 ///
-/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::SEND_MESSAGES`]
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_THREADS`]
 /// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
-/// * [`HttpError::UnknownMessage`] - If the reference message is not found
-async fn create_message(
+/// * [`HttpError::UnknownThread`] - If the thread is not found or deleted
+/// * [`HttpError::UnknownMessage`] - If the first or the last message is not found in the thread or deleted
+/// * [`HttpError::InvalidOperation`] - If the range is empty or contains the original message of the thread
+async fn split_thread(
     thread_id: web::Path<i64>,
-    payload: web::Json<CreateMessagePayload>,
+    payload: web::Json<SplitThreadPayload>,
     app: web::Data<App>,
     credentials: Option<web::ReqData<UserCredentials>>
 ) -> Result<HttpResponse> {
     let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
 
-    if !user.clone().has_permission(Permissions::SEND_MESSAGES) {
+    if !user.has_permission(Permissions::MANAGE_THREADS) {
         return Err(HttpError::MissingAccess)
     }
 
     payload
         .validate()
-        .map_err(|err| HttpError::Validation(err))?;
+        .map_err(HttpError::Validation)?;
 
-    let id = app.snowflake.lock().unwrap().build();
+    let source = app.database.fetch_thread(thread_id.to_owned().into())
+        .await?;
 
-    let message = Message::new(id, user, thread_id.to_owned().into(), &payload.content, None)
-        .save(&app.pool).await?;
+    if source.deleted_at.is_some() {
+        return Err(HttpError::UnknownThread)
+    }
 
-    _ = app.dispatch(DispatchTarget::Global, MessageCreate(message.clone()));
+    let first_message = app.database.fetch_message(source.id, payload.first_message_id)
+        .await.filter(|message| message.deleted_at.is_none())
+        .ok_or(HttpError::UnknownMessage)?;
+    app.database.fetch_message(source.id, payload.last_message_id)
+        .await.filter(|message| message.deleted_at.is_none())
+        .ok_or(HttpError::UnknownMessage)?;
 
-    Ok(HttpResponse::Ok().json(message))
+    if payload.first_message_id > payload.last_message_id {
+        return Err(HttpError::InvalidOperation("The first message must be sent before the last message".to_string()))
+    }
+
+    if payload.first_message_id <= source.original_message.id {
+        return Err(HttpError::InvalidOperation("The original message of the thread can't be split".to_string()))
+    }
+
+    let (id, message_id, note_id) = {
+        let mut snowflake = app.snowflake.lock().unwrap();
+        (snowflake.build(), snowflake.build(), snowflake.build())
+    };
+    let mut tx = app.pool.begin().await?;
+
+    let moved = source.move_messages(&mut *tx, id, Some((payload.first_message_id, payload.last_message_id))).await?;
+    let flags = first_message.flags.union(MessageFlags::UNDELETEABLE);
+    let first_message = first_message.set_flags(&mut *tx, flags).await?;
+
+    let thread = Thread::new(id, source.category_id, first_message, &payload.title, None)
+        .with_type(source.kind)
+        .save(&mut *tx).await?;
+    thread.copy_members(&mut *tx, source.id).await?;
+
+    let message = Message::new(message_id, user.clone(), source.id,
+        &format!("{} split {} messages into {}", user.username, moved, thread.title),
+        Some(MessageFlags::SYSTEM)
+    )
+        .save(&mut *tx).await?;
+    let note = Message::new(note_id, user.clone(), thread.id,
+        &format!("{} split {} messages from {}", user.username, moved, source.title),
+        Some(MessageFlags::SYSTEM)
+    )
+        .save(&mut *tx).await?;
+
+    tx.commit().await?;
+
+    let source = app.database.fetch_thread(source.id).await?;
+    let thread = app.database.fetch_thread(thread.id).await?;
+    let target = app.database.fetch_dispatch_target(&source).await?;
+
+    _ = app.dispatch(target.clone(), ThreadCreate(thread.clone()));
+    _ = app.dispatch(target.clone(), ThreadUpdate(source));
+    _ = app.dispatch(target.clone(), MessageCreate(message));
+    _ = app.dispatch(target, MessageCreate(note));
+
+    Ok(HttpResponse::Ok().json(thread))
 }
 
-/// Updates a message - `PATCH /threads/{thread_id}/messages/{message_id}`
+/// Returns [`Vec<User>`] of the thread members - `GET /threads/{thread_id}/members`
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+/// * [`HttpError::MissingAccess`] - If the thread is private and the user is not its member
+async fn get_thread_members(
+    thread_id: web::Path<i64>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, thread_id.to_owned().into(), &user).await?;
+
+    let members = app.database.fetch_thread_members(thread.id).await?;
+
+    Ok(HttpResponse::Ok().json(members))
+}
+
+/// Adds a member to a private thread - `PUT /threads/{thread_id}/members/{user_id}`
 ///
 /// ### Path
 ///
 /// * `thread_id` - The ID of the thread
-/// * `message_id` - The ID of the message to delete
+/// * `user_id` - The ID of the user to add
 ///
 /// ### Errors
 ///
-/// * [`HttpError::MissingAccess`] - If the user is not the message author
-/// * [`HttpError::UnknownMessage`] - If the message is not found
-async fn modify_message(
+/// * [`HttpError::MissingAccess`] - If the user is not the thread author and does not have [`Permissions::MANAGE_THREADS`]
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+/// * [`HttpError::UnknownUser`] - If the user to add is not found
+/// * [`HttpError::InvalidOperation`] - If the thread is not private
+async fn add_thread_member(
     path: web::Path<(i64, i64)>,
-    payload: web::Json<ModifyMessagePayload>,
     app: web::Data<App>,
     credentials: Option<web::ReqData<UserCredentials>>
 ) -> Result<HttpResponse> {
     let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
-    let message = app.database.fetch_message(path.to_owned().0.into(), path.to_owned().1.into())
-        .await.ok_or(HttpError::UnknownMessage)?;
+    let thread = fetch_visible_thread(&app, path.0.into(), &user).await?;
 
-    if user.id != message.author.id {
-        return Err(HttpError::MissingAccess);
+    if user.id != thread.author.id && !user.has_permission(Permissions::MANAGE_THREADS) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    if thread.kind != ThreadType::Private {
+        return Err(HttpError::InvalidOperation("Only private threads have members".to_string()))
     }
 
-    message.clone().edit(&app.pool, &payload.content).await?;
+    let member = app.database.fetch_user(path.1.into())
+        .await.ok_or(HttpError::UnknownUser)?;
+
+    thread.add_member(&app.pool, member.id).await?;
 
-    _ = app.dispatch(DispatchTarget::Global, MessageUpdate(message.clone()));
+    _ = app.dispatch(app.database.fetch_dispatch_target(&thread).await?, ThreadMemberAdd {thread_id: thread.id, user: member});
 
-    Ok(HttpResponse::Ok().json(message.clone()))
+    Ok(HttpResponse::NoContent().finish())
 }
 
-/// Deletes a message - `DELETE /threads/{thread_id}/messages/{message_id}`
+/// Removes a member from a private thread - `DELETE /threads/{thread_id}/members/{user_id}`
+///
+/// Members can remove themselves, other members can be removed by the thread author and thread managers.
 ///
 /// ### Path
 ///
 /// * `thread_id` - The ID of the thread
-/// * `message_id` - The ID of the message to delete
+/// * `user_id` - The ID of the user to remove
 ///
 /// ### Errors
 ///
-/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_MESSAGES`]
-/// * [`HttpError::UnknownMessage`] - If the message is not found
-/// * [`HttpError::Undeletable`] - If the message has [`MessageFlags::UNDELETEABLE`]
-async fn delete_message(
+/// * [`HttpError::MissingAccess`] - If the user is not allowed to remove the member
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+/// * [`HttpError::UnknownUser`] - If the user is not a member of the thread
+async fn remove_thread_member(
     path: web::Path<(i64, i64)>,
     app: web::Data<App>,
     credentials: Option<web::ReqData<UserCredentials>>
 ) -> Result<HttpResponse> {
     let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
-    let message = app.database.fetch_message(path.to_owned().0.into(), path.to_owned().1.into())
-        .await.ok_or(HttpError::UnknownMessage)?;
-
-    if user.id != message.author.id && !user.has_permission(Permissions::MANAGE_MESSAGES) {
-        return Err(HttpError::MissingAccess);
-    }
+    let thread = fetch_visible_thread(&app, path.0.into(), &user).await?;
+    let member_id: Snowflake = path.1.into();
 
-    if message.clone().is(MessageFlags::UNDELETEABLE) {
-        return Err(HttpError::Undeletable)
+    if user.id != member_id && user.id != thread.author.id && !user.has_permission(Permissions::MANAGE_THREADS) {
+        return Err(HttpError::MissingAccess)
     }
 
-    _ = app.dispatch(DispatchTarget::Global, MessageDelete {thread_id: message.thread_id, message_id: message.id});
+    let target = app.database.fetch_dispatch_target(&thread).await?;
+    thread.remove_member(&app.pool, member_id).await?;
 
-    message.delete(&app.pool).await?;
+    _ = app.dispatch(target, ThreadMemberRemove {thread_id: thread.id, user_id: member_id});
 
     Ok(HttpResponse::NoContent().finish())
 }
+
+/// Replaces requirements to send messages in a thread and return [`Thread`] - `PUT /threads/{thread_id}/requirements`
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user is not the thread author and does not have [`Permissions::MANAGE_THREADS`]
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+async fn modify_thread_requirements(
+    thread_id: web::Path<i64>,
+    payload: web::Json<ThreadRequirementsPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, thread_id.to_owned().into(), &user).await?;
+
+    if user.id != thread.author.id && !user.has_permission(Permissions::MANAGE_THREADS) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    payload
+        .validate()
+        .map_err(HttpError::Validation)?;
+
+    let thread = thread.set_requirements(&app.pool, payload.into_inner().into()).await?;
+
+    _ = app.dispatch(app.database.fetch_dispatch_target(&thread).await?, ThreadUpdate(thread.clone()));
+
+    Ok(HttpResponse::Ok().json(thread))
+}
+
+/// Replaces tags applied to a thread and return [`Thread`] - `PUT /threads/{thread_id}/tags`
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user is not the thread author and does not have [`Permissions::MANAGE_THREADS`],
+///   or changes moderated tags without [`Permissions::MANAGE_THREADS`]
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+/// * [`HttpError::UnknownTag`] - If the tag is not found in the thread's category
+/// * [`HttpError::InvalidOperation`] - If the thread would have too many tags with the kept moderated tags
+async fn modify_thread_tags(
+    thread_id: web::Path<i64>,
+    payload: web::Json<ModifyThreadTagsPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, thread_id.to_owned().into(), &user).await?;
+
+    if user.id != thread.author.id && !user.has_permission(Permissions::MANAGE_THREADS) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    payload
+        .validate()
+        .map_err(HttpError::Validation)?;
+
+    let tags = check_thread_tags(&app, thread.category_id, &user, &payload.tags, &thread.tags).await?;
+    let thread = thread.set_tags(&app.pool, tags).await?;
+
+    _ = app.dispatch(app.database.fetch_dispatch_target(&thread).await?, ThreadUpdate(thread.clone()));
+
+    Ok(HttpResponse::Ok().json(thread))
+}
+
+/// Marks a reply as the accepted answer of a question thread and return [`Thread`] - `PUT /threads/{thread_id}/answer`
+///
+/// Replaces the previously accepted answer, if any.
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user is not the thread author and does not have [`Permissions::MANAGE_THREADS`]
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+/// * [`HttpError::UnknownMessage`] - If the message is not found in the thread
+/// * [`HttpError::InvalidOperation`] - If the thread's category is not in question mode, or the message is
+///   the original or a system message
+async fn accept_answer(
+    thread_id: web::Path<i64>,
+    payload: web::Json<AcceptAnswerPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, thread_id.to_owned().into(), &user).await?;
+
+    if user.id != thread.author.id && !user.has_permission(Permissions::MANAGE_THREADS) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    let category = app.database.fetch_category(thread.category_id).await
+        .ok_or(HttpError::UnknownCategory)?;
+
+    if !category.question_mode {
+        return Err(HttpError::InvalidOperation("The thread's category is not in question mode".to_string()))
+    }
+
+    let message = fetch_undeleted_message(&app, thread.id, payload.message_id).await?;
+
+    if message.id == thread.original_message.id || message.clone().is(MessageFlags::SYSTEM) {
+        return Err(HttpError::InvalidOperation("Only replies can be accepted as the answer".to_string()))
+    }
+
+    let thread = thread.set_accepted_answer(&app.pool, Some(message)).await?;
+
+    _ = app.dispatch(app.database.fetch_dispatch_target(&thread).await?, ThreadUpdate(thread.clone()));
+
+    Ok(HttpResponse::Ok().json(thread))
+}
+
+/// Unmarks the accepted answer of a thread and return [`Thread`] - `DELETE /threads/{thread_id}/answer`
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user is not the thread author and does not have [`Permissions::MANAGE_THREADS`]
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+/// * [`HttpError::InvalidOperation`] - If the thread has no accepted answer
+async fn remove_accepted_answer(
+    thread_id: web::Path<i64>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, thread_id.to_owned().into(), &user).await?;
+
+    if user.id != thread.author.id && !user.has_permission(Permissions::MANAGE_THREADS) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    if thread.accepted_answer.is_none() {
+        return Err(HttpError::InvalidOperation("The thread has no accepted answer".to_string()))
+    }
+
+    let thread = thread.set_accepted_answer(&app.pool, None).await?;
+
+    _ = app.dispatch(app.database.fetch_dispatch_target(&thread).await?, ThreadUpdate(thread.clone()));
+
+    Ok(HttpResponse::Ok().json(thread))
+}
+
+/// Votes in the poll of a thread and return updated [`Poll`] - `PUT /threads/{thread_id}/poll/votes`
+///
+/// Replaces previous votes of the user, so each user has a single vote.
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+/// * [`HttpError::MissingAccess`] - If the thread is private and the user is not its member
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+/// * [`HttpError::UnknownPoll`] - If the thread has no poll
+/// * [`HttpError::InvalidOperation`] - If the poll is closed, the option is not found, or several options
+///   are chosen in a single choice poll
+async fn vote_poll(
+    thread_id: web::Path<i64>,
+    payload: web::Json<VotePollPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, thread_id.to_owned().into(), &user).await?;
+
+    payload
+        .validate()
+        .map_err(HttpError::Validation)?;
+
+    let poll = thread.poll.clone().ok_or(HttpError::UnknownPoll)?;
+
+    if !poll.is_open() {
+        return Err(HttpError::InvalidOperation("The poll is closed".to_string()))
+    }
+
+    let mut option_ids = payload.option_ids.clone();
+    option_ids.sort();
+    option_ids.dedup();
+
+    if option_ids.len() > 1 && !poll.multiple_choice {
+        return Err(HttpError::InvalidOperation("Only one option can be chosen in this poll".to_string()))
+    }
+
+    if option_ids.iter().any(|id| !poll.options.iter().any(|option| option.id == *id)) {
+        return Err(HttpError::InvalidOperation("Unknown poll option".to_string()))
+    }
+
+    poll.vote(&app.pool, user.id, &option_ids).await?;
+
+    let poll = app.database.fetch_poll(thread.id).await?
+        .ok_or(HttpError::UnknownPoll)?;
+
+    _ = app.dispatch(app.database.fetch_dispatch_target(&thread).await?, PollUpdate(poll.clone()));
+
+    Ok(HttpResponse::Ok().json(poll))
+}
+
+/// Removes votes of the user from the poll of a thread and return updated [`Poll`] - `DELETE /threads/{thread_id}/poll/votes`
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+/// * [`HttpError::MissingAccess`] - If the thread is private and the user is not its member
+/// * [`HttpError::UnknownPoll`] - If the thread has no poll
+/// * [`HttpError::InvalidOperation`] - If the poll is closed
+async fn remove_poll_votes(
+    thread_id: web::Path<i64>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, thread_id.to_owned().into(), &user).await?;
+    let poll = thread.poll.clone().ok_or(HttpError::UnknownPoll)?;
+
+    if !poll.is_open() {
+        return Err(HttpError::InvalidOperation("The poll is closed".to_string()))
+    }
+
+    poll.vote(&app.pool, user.id, &[]).await?;
+
+    let poll = app.database.fetch_poll(thread.id).await?
+        .ok_or(HttpError::UnknownPoll)?;
+
+    _ = app.dispatch(app.database.fetch_dispatch_target(&thread).await?, PollUpdate(poll.clone()));
+
+    Ok(HttpResponse::Ok().json(poll))
+}
+
+/// Returns [`ThreadWatch`] of the user - `GET /threads/{thread_id}/watch`
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+/// * [`HttpError::MissingAccess`] - If the thread is private and the user is not its member
+async fn get_thread_watch(
+    thread_id: web::Path<i64>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, thread_id.to_owned().into(), &user).await?;
+
+    Ok(HttpResponse::Ok().json(app.database.fetch_thread_watch(thread.id, user.id).await))
+}
+
+/// Changes the watch level of a thread and return [`ThreadWatch`] - `PUT /threads/{thread_id}/watch`
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+/// * [`HttpError::MissingAccess`] - If the thread is private and the user is not its member
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+async fn modify_thread_watch(
+    thread_id: web::Path<i64>,
+    payload: web::Json<WatchPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, thread_id.to_owned().into(), &user).await?;
+
+    ThreadWatch::new(thread.id, user.id, payload.level)
+        .save(&app.pool)
+        .await
+        .map(|row| HttpResponse::Ok().json(row))
+}
+
+/// Marks messages of a thread as read and return [`ReadState`] - `POST /threads/{thread_id}/ack`
+///
+/// Messages up to the given message are read, all messages if it's not given. The read state is sent to
+/// other connections of the user in a `READ_STATE_UPDATE` event.
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+/// * [`HttpError::MissingAccess`] - If the thread is private and the user is not its member
+/// * [`HttpError::UnknownMessage`] - If the message is not found in the thread
+async fn ack_thread(
+    thread_id: web::Path<i64>,
+    payload: web::Json<AckThreadPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, thread_id.to_owned().into(), &user).await?;
+
+    let message_id = match payload.message_id {
+        Some(message_id) => app.database.fetch_message(thread.id, message_id)
+            .await.ok_or(HttpError::UnknownMessage)?.id,
+        None => thread.last_message_id.unwrap_or(thread.original_message.id)
+    };
+
+    let read_state = ReadState::new(thread.id, user.id, message_id)
+        .save(&app.pool).await?;
+
+    _ = app.dispatch(DispatchTarget::User(user.id), ReadStateUpdate(read_state.clone()));
+
+    Ok(HttpResponse::Ok().json(read_state))
+}
+
+/// Joins a thread with requirements - `POST /threads/{thread_id}/join`
+///
+/// The user becomes a member of the thread right away, unless the thread has a question. In that case the answer is saved
+/// as [`JoinRequest`] and returned, the user becomes a member once the request is approved.
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+/// * [`HttpError::InvalidOperation`] - If the user is already a member of the thread or their answer was rejected
+/// * [`HttpError::RequirementNotMet`] - If the user doesn't meet any of requirements or didn't answer the question
+async fn join_thread(
+    thread_id: web::Path<i64>,
+    payload: web::Json<JoinThreadPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, thread_id.to_owned().into(), &user).await?;
+
+    payload
+        .validate()
+        .map_err(HttpError::Validation)?;
+
+    if app.database.is_thread_member(thread.id, user.id).await {
+        return Err(HttpError::InvalidOperation("You are already a member of this thread".to_string()))
+    }
+
+    if check_thread_requirements(&app, &thread, &user).await? {
+        let answer = payload.answer.as_ref()
+            .ok_or(HttpError::RequirementNotMet("You must answer the question to join this thread".to_string()))?;
+
+        let request = JoinRequest::new(thread.id, user, answer)
+            .save(&app.pool).await?;
+
+        return Ok(HttpResponse::Ok().json(request))
+    }
+
+    thread.add_member(&app.pool, user.id).await?;
+
+    _ = app.dispatch(app.database.fetch_dispatch_target(&thread).await?, ThreadMemberAdd {thread_id: thread.id, user});
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Returns pending [`Vec<JoinRequest>`] of a thread - `GET /threads/{thread_id}/join-requests`
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user is not the thread author and does not have [`Permissions::MANAGE_THREADS`]
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+async fn get_join_requests(
+    thread_id: web::Path<i64>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, thread_id.to_owned().into(), &user).await?;
+
+    if user.id != thread.author.id && !user.has_permission(Permissions::MANAGE_THREADS) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    let requests = app.database.fetch_join_requests(thread.id, None).await?;
+
+    Ok(HttpResponse::Ok().json(requests))
+}
+
+/// Approves or rejects a join request and return [`JoinRequest`] - `PATCH /threads/{thread_id}/join-requests/{user_id}`
+///
+/// ### Path
+///
+/// * `thread_id` - The ID of the thread
+/// * `user_id` - The ID of the user who requested to join
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user is not the thread author and does not have [`Permissions::MANAGE_THREADS`]
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+/// * [`HttpError::UnknownUser`] - If the join request is not found
+/// * [`HttpError::InvalidOperation`] - If the join request is already reviewed or the status is `pending`
+async fn review_join_request(
+    path: web::Path<(i64, i64)>,
+    payload: web::Json<ReviewJoinRequestPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, path.0.into(), &user).await?;
+
+    if user.id != thread.author.id && !user.has_permission(Permissions::MANAGE_THREADS) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    let request = app.database.fetch_join_request(thread.id, path.1.into())
+        .await.ok_or(HttpError::UnknownUser)?;
+
+    if request.status != JoinRequestStatus::Pending || payload.status == JoinRequestStatus::Pending {
+        return Err(HttpError::InvalidOperation("Only pending join requests can be approved or rejected".to_string()))
+    }
+
+    let mut tx = app.pool.begin().await?;
+
+    let request = request.review(&mut *tx, payload.status, user.id).await?;
+    if request.status == JoinRequestStatus::Approved {
+        thread.add_member(&mut *tx, request.user.id).await?;
+    }
+
+    tx.commit().await?;
+
+    if request.status == JoinRequestStatus::Approved {
+        _ = app.dispatch(app.database.fetch_dispatch_target(&thread).await?, ThreadMemberAdd {thread_id: thread.id, user: request.user.clone()});
+    }
+
+    Ok(HttpResponse::Ok().json(request))
+}
+
+#[derive(Deserialize, Validate)]
+pub struct SearchMessagesQuery {
+    #[validate(range(min = 1, max = 100, message = "Limit must be between 1 and 100"))]
+    pub limit: Option<u16>,
+    pub before: Option<Snowflake>,
+    pub after: Option<Snowflake>,
+    pub around: Option<Snowflake>,
+    #[serde(default)]
+    pub format: ContentFormat
+}
+
+/// Returns [`Vec<Message>`] of the thread - `GET /threads/{thread_id}/messages`
+///
+/// ### Query
+///
+/// * `limit` - Max number of messages to return (1-100, default 50)
+/// * `after` - Get messages after this message ID
+/// * `before` - Get messages before this message ID
+/// * `around` - Get messages around this message ID, including the message itself. Can't be combined with `before` or `after`
+/// * `format` - Also return the content rendered to `html` or `text`
+///
+/// ### Errors
+///
+/// * [`HttpError::Validation`] - If the limit is out of range
+/// * [`HttpError::InvalidOperation`] - If `around` is combined with `before` or `after`
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+/// * [`HttpError::MissingAccess`] - If the thread is private and the user is not its member
+/// * [`HttpError::UnknownMessage`] - If the `around` message is not in the thread
+async fn get_messages(
+    path: web::Path<i64>,
+    query: web::Query<SearchMessagesQuery>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    fetch_visible_thread(&app, path.to_owned().into(), &user).await?;
+
+    query.validate().map_err(HttpError::Validation)?;
+
+    if query.around.is_some() && (query.before.is_some() || query.after.is_some()) {
+        return Err(HttpError::InvalidOperation("`around` can't be combined with `before` or `after`".into()))
+    }
+
+    let messages = app.database.fetch_messages(path.to_owned().into(), query.limit, query.before, query.after, query.around).await?
+        .into_iter()
+        .map(|message| visible_message(message, &user).render(query.format))
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(messages))
+}
+
+#[derive(Deserialize)]
+pub struct MessageFormatQuery {
+    #[serde(default)]
+    pub format: ContentFormat
+}
+
+/// Returns [`Message`] by given ID - `GET /threads/{thread_id}/messages/{message_id}`
+///
+/// ### Path
+///
+/// * `thread_id` - The ID of the thread
+/// * `message_id` - The ID of the message to fetch
+///
+/// ### Query
+///
+/// * `format` - Also return the content rendered to `html` or `text`
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownMessage`] - If the message is not found
+/// * [`HttpError::MissingAccess`] - If the thread is private and the user is not its member
+async fn get_message(
+    path: web::Path<(i64, i64)>,
+    query: web::Query<MessageFormatQuery>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    fetch_visible_thread(&app, path.to_owned().0.into(), &user).await?;
+
+    let message = app.database.fetch_message(path.to_owned().0.into(), path.to_owned().1.into())
+        .await.ok_or(HttpError::UnknownMessage)?;
+
+    Ok(HttpResponse::Ok().json(visible_message(message, &user).render(query.format)))
+}
+
+/// Creates a new message and return [`Message`] - `POST /threads/{thread_id}/messages`
+///
+/// The author starts watching the thread, unless they already chose a watch level for it, and the thread
+/// is marked as read for them. Users who watch the thread receive the message in a `WATCHED_MESSAGE_CREATE` event,
+/// the author of the referenced message receives it in a `REPLY_CREATE` event instead.
+///
+/// ### Path
+///
+/// * `thread_id` - The ID of the thread
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::SEND_MESSAGES`], can't see the thread,
+///   does not have [`Permissions::MANAGE_THREADS`] in announcement thread or can't use a custom emoji from the content
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+/// * [`HttpError::UnknownMessage`] - If the reference message is not found in the thread
+/// * [`HttpError::RequirementNotMet`] - If the user doesn't meet requirements of the thread
+/// * [`HttpError::InvalidOperation`] - If the content mentions too many users
+/// * [`HttpError::RateLimited`] - If the user mentioned too many users recently
+async fn create_message(
+    thread_id: web::Path<i64>,
+    payload: web::Json<CreateMessagePayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_writable_thread(&app, thread_id.to_owned().into(), &user).await?;
+
+    send_message(&app, thread, user, payload.into_inner(), Vec::new()).await
+}
+
+/// Creates a new message with attached files and return [`Message`] - `POST /threads/{thread_id}/messages`
+///
+/// Used when the request is `multipart/form-data`. The message payload is sent as JSON in `payload_json`
+/// field, every field with a filename is attached to the message.
+///
+/// ### Path
+///
+/// * `thread_id` - The ID of the thread
+///
+/// ### Errors
+///
+/// * [`HttpError::Multipart`] - If the request body is malformed
+/// * [`HttpError::Payload`] - If `payload_json` field is malformed
+/// * [`HttpError::InvalidOperation`] - If `payload_json` field is missing, there are too many files, a file is
+///   too large, its type is not allowed for the user or it claims to be an image but isn't
+/// * [`HttpError::Storage`] - If a file couldn't be stored
+/// * Same errors as [`create_message`]
+async fn create_message_with_attachments(
+    thread_id: web::Path<i64>,
+    multipart: Multipart,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    // Access is checked before the files are read, so they aren't written to disk for nothing
+    let thread = fetch_writable_thread(&app, thread_id.to_owned().into(), &user).await?;
+
+    let (payload, uploads) = read_message_multipart(multipart, AttachmentLimits::of(&user)).await?;
+
+    send_message(&app, thread, user, payload, uploads).await
+}
+
+/// File from a multipart request waiting to be attached to a message
+struct Upload {
+    filename: String,
+    content_type: String,
+    content: Content
+}
+
+/// Reads the message payload from `payload_json` field and files from fields with a filename.
+async fn read_message_multipart(mut multipart: Multipart, limits: AttachmentLimits) -> Result<(CreateMessagePayload, Vec<Upload>)> {
+    let mut payload = None;
+    let mut uploads = Vec::new();
+
+    while let Some(mut field) = multipart.try_next().await? {
+        let Some(filename) = field.content_disposition().and_then(|disposition| disposition.get_filename()) else {
+            if field.name() == Some("payload_json") {
+                let mut content = BytesMut::new();
+                while let Some(chunk) = field.try_next().await? {
+                    if content.len() + chunk.len() > MAX_PAYLOAD_JSON_SIZE {
+                        return Err(HttpError::Payload(JsonPayloadError::Overflow { limit: MAX_PAYLOAD_JSON_SIZE }))
+                    }
+                    content.extend_from_slice(&chunk);
+                }
+                payload = Some(serde_json::from_slice(&content)
+                    .map_err(|err| HttpError::Payload(JsonPayloadError::Deserialize(err)))?);
+            }
+            continue
+        };
+
+        // Clients may send a full path, only the last component is kept
+        let filename = filename.rsplit(['/', '\\']).next().unwrap_or_default().chars().take(255).collect::<String>();
+        let filename = if filename.is_empty() { "file".to_string() } else { filename };
+
+        if uploads.len() >= MAX_MESSAGE_ATTACHMENTS {
+            return Err(HttpError::InvalidOperation(format!("Message can't have more than {} attachments", MAX_MESSAGE_ATTACHMENTS)))
+        }
+
+        let content_type = field.content_type()
+            .map_or("application/octet-stream".to_string(), |mime| mime.essence_str().to_string());
+        if !limits.allows(&content_type) {
+            return Err(HttpError::InvalidOperation(format!("You can't upload files of type `{}`", content_type)))
+        }
+
+        // Files are written to temporary files as they arrive instead of being kept in memory
+        let mut content = ContentWriter::new().map_err(HttpError::Storage)?;
+        while let Some(chunk) = field.try_next().await? {
+            if content.size() + chunk.len() as u64 > limits.max_size {
+                return Err(HttpError::InvalidOperation(format!("File `{}` is larger than {} bytes", filename, limits.max_size)))
+            }
+            content.write(&chunk).await.map_err(HttpError::Storage)?;
+        }
+
+        uploads.push(Upload { filename, content_type, content: content.finish().await.map_err(HttpError::Storage)? });
+    }
+
+    let payload = payload.ok_or(HttpError::InvalidOperation("Missing `payload_json` field".to_string()))?;
+
+    Ok((payload, uploads))
+}
+
+/// Fetches the thread the user can send messages to.
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::SEND_MESSAGES`], can't see the thread
+///   or does not have [`Permissions::MANAGE_THREADS`] in announcement thread
+/// * [`HttpError::UnknownThread`] - If the thread is not found or deleted
+/// * [`HttpError::RequirementNotMet`] - If the user doesn't meet requirements of the thread
+async fn fetch_writable_thread(app: &App, thread_id: Snowflake, user: &User) -> Result<Thread> {
+    if !user.clone().has_permission(Permissions::SEND_MESSAGES) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    let thread = fetch_visible_thread(app, thread_id, user).await?;
+
+    if thread.deleted_at.is_some() {
+        return Err(HttpError::UnknownThread)
+    }
+
+    if thread.kind == ThreadType::Announcement && !user.has_permission(Permissions::MANAGE_THREADS) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    if check_thread_requirements(app, &thread, user).await? {
+        let reason = match app.database.fetch_join_request(thread.id, user.id).await.map(|request| request.status) {
+            Some(JoinRequestStatus::Pending) => "Your answer to the thread's question is waiting for review",
+            Some(JoinRequestStatus::Rejected) => "Your answer to the thread's question was rejected",
+            _ => "You must answer the thread's question before sending messages in this thread"
+        };
+        return Err(HttpError::RequirementNotMet(reason.to_string()))
+    }
+
+    Ok(thread)
+}
+
+/// Sends the message with attached files to the thread the user can send messages to.
+async fn send_message(
+    app: &App,
+    thread: Thread,
+    user: User,
+    payload: CreateMessagePayload,
+    uploads: Vec<Upload>
+) -> Result<HttpResponse> {
+    payload
+        .validate()
+        .map_err(HttpError::Validation)?;
+
+    let emojis = check_message_emojis(app, &payload.content, &user).await?;
+    let mentions = check_message_mentions(app, &payload.content, &user, &Mentions::default()).await?;
+
+    let referenced_message = match payload.referenced_message_id {
+        Some(message_id) => Some(fetch_undeleted_message(app, thread.id, message_id).await?),
+        None => None
+    };
+
+    let id = app.snowflake.lock().unwrap().build();
+
+    let attachments = uploads.into_iter()
+        .map(|upload| {
+            let attachment = Attachment::new(app.snowflake.lock().unwrap().build(), id, &upload.filename, &upload.content_type, &upload.content);
+
+            if upload.content_type.starts_with("image/") && !attachment.is_image() {
+                return Err(HttpError::InvalidOperation(format!("File `{}` is not a valid image", upload.filename)))
+            }
+
+            Ok((attachment, upload.content))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Files are stored before the transaction so it isn't held open during uploads, and removed if it fails
+    let mut keys = Vec::new();
+    for (attachment, content) in &attachments {
+        if let Err(err) = app.storage.put(&attachment.key(), &attachment.content_type, content).await {
+            delete_files(app, &keys).await;
+            return Err(HttpError::Storage(err))
+        }
+        keys.push(attachment.key());
+    }
+
+    let saved: Result<_> = async {
+        let mut tx = app.pool.begin().await?;
+
+        let mut message = Message::new(id, user.clone(), thread.id, &payload.content, None);
+        if let Some(referenced_message) = &referenced_message {
+            message = message.with_reference(referenced_message);
+        }
+        let mut message = message.save(&mut *tx).await?;
+        message.emojis = emojis;
+        let mentioned_ids = mentions.save(&mut *tx, message.id).await?;
+        message.mentions = mentions;
+
+        for (attachment, _) in attachments {
+            message.attachments.0.push(attachment.save(&mut *tx).await?);
+        }
+
+        ThreadWatch::new(thread.id, user.id, WatchLevel::Watching)
+            .save_if_missing(&mut *tx).await?;
+        ReadState::new(thread.id, user.id, message.id)
+            .save(&mut *tx).await?;
+
+        tx.commit().await?;
+
+        Ok((message, mentioned_ids))
+    }.await;
+
+    let (message, mentioned_ids) = match saved {
+        Ok(saved) => saved,
+        Err(err) => {
+            delete_files(app, &keys).await;
+            return Err(err)
+        }
+    };
+
+    _ = app.dispatch(app.database.fetch_dispatch_target(&thread).await?, MessageCreate(message.clone()));
+
+    let notified = dispatch_mentions(app, &thread, &message, &mentioned_ids).await;
+
+    let replied_user = referenced_message
+        .map(|message| message.author)
+        .filter(|author| author.id != user.id && !notified.contains(&author.id));
+
+    if let Some(author) = &replied_user {
+        if app.database.has_thread_access(&thread, author).await {
+            _ = app.dispatch(DispatchTarget::User(author.id), ReplyCreate(message.clone()));
+
+            let id = app.snowflake.lock().unwrap().build();
+            _ = app.notify(Notification::new(id, author.id, NotificationType::Reply, user.id).with_message(thread.id, message.id)).await;
+        }
+    }
+
+    for watcher_id in app.database.fetch_thread_watchers(&thread, user.id).await? {
+        if replied_user.as_ref().is_some_and(|author| author.id == watcher_id) || notified.contains(&watcher_id) {
+            continue
+        }
+        _ = app.dispatch(DispatchTarget::User(watcher_id), WatchedMessageCreate(message.clone()));
+    }
+
+    Ok(HttpResponse::Ok().json(message))
+}
+
+/// Removes stored files, ignoring failures since the files are unreachable anyway.
+async fn delete_files(app: &App, keys: &[String]) {
+    for key in keys {
+        _ = app.storage.delete(key).await;
+    }
+}
+
+/// Updates a message - `PATCH /threads/{thread_id}/messages/{message_id}`
+///
+/// ### Path
+///
+/// * `thread_id` - The ID of the thread
+/// * `message_id` - The ID of the message to delete
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user is not the message author, is banned, can't use a custom emoji
+///   from the content or the thread is locked and the user does not have [`Permissions::MANAGE_THREADS`]
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+/// * [`HttpError::UnknownMessage`] - If the message is not found
+/// * [`HttpError::InvalidOperation`] - If the content mentions too many users
+/// * [`HttpError::RateLimited`] - If the user mentioned too many users recently
+async fn modify_message(
+    path: web::Path<(i64, i64)>,
+    payload: web::Json<ModifyMessagePayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    if user.has_flag(UserFlags::BANNED) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    payload
+        .validate()
+        .map_err(HttpError::Validation)?;
+
+    let thread = fetch_visible_thread(&app, path.to_owned().0.into(), &user).await?;
+    let message = fetch_undeleted_message(&app, thread.id, path.to_owned().1.into()).await?;
+
+    if user.id != message.author.id {
+        return Err(HttpError::MissingAccess);
+    }
+
+    if thread.clone().is(ThreadFlags::LOCKED) && !user.has_permission(Permissions::MANAGE_THREADS) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    check_message_emojis(&app, &payload.content, &user).await?;
+    let mentions = check_message_mentions(&app, &payload.content, &user, &message.mentions).await?;
+
+    let mut tx = app.pool.begin().await?;
+    let mut message = message.edit(&mut *tx, &payload.content).await?;
+    let mentioned_ids = mentions.save(&mut *tx, message.id).await?;
+    message.mentions = mentions;
+    tx.commit().await?;
+
+    _ = app.dispatch(app.database.fetch_dispatch_target(&thread).await?, MessageUpdate(message.clone()));
+    dispatch_mentions(&app, &thread, &message, &mentioned_ids).await;
+
+    Ok(HttpResponse::Ok().json(message))
+}
+
+/// Returns [`Vec<MessageRevision>`](crate::models::message::MessageRevision) with previous contents of a message - `GET /threads/{thread_id}/messages/{message_id}/revisions`
+///
+/// ### Path
+///
+/// * `thread_id` - The ID of the thread
+/// * `message_id` - The ID of the message
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user is not the message author and does not have [`Permissions::MANAGE_MESSAGES`]
+/// * [`HttpError::UnknownMessage`] - If the message is not found
+async fn get_message_revisions(
+    path: web::Path<(i64, i64)>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, path.0.into(), &user).await?;
+    let message = fetch_readable_message(&app, thread.id, path.1.into(), &user).await?;
+
+    if user.id != message.author.id && !user.has_permission(Permissions::MANAGE_MESSAGES) {
+        return Err(HttpError::MissingAccess);
+    }
+
+    let revisions = app.database.fetch_message_revisions(message.id).await?;
+
+    Ok(HttpResponse::Ok().json(revisions))
+}
+
+/// Reports a message to moderators - `POST /threads/{thread_id}/messages/{message_id}/report`
+///
+/// Returns the created [`Report`]. Each user can report a message only once.
+///
+/// ### Path
+///
+/// * `thread_id` - The ID of the thread
+/// * `message_id` - The ID of the message to report
+///
+/// ### Errors
+///
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+/// * [`HttpError::UnknownMessage`] - If the message is not found or deleted
+/// * [`HttpError::InvalidOperation`] - If the message is sent by the user or was already reported by them
+async fn report_message(
+    path: web::Path<(i64, i64)>,
+    payload: web::Json<CreateReportPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    payload
+        .validate()
+        .map_err(HttpError::Validation)?;
+
+    let thread = fetch_visible_thread(&app, path.0.into(), &user).await?;
+    let message = fetch_undeleted_message(&app, thread.id, path.1.into()).await?;
+
+    if message.author.id == user.id {
+        return Err(HttpError::InvalidOperation("You can't report your own message".to_string()))
+    }
+
+    let payload = payload.into_inner();
+    let id = app.snowflake.lock().unwrap().build();
+    let report = Report::new(id, thread.id, message.id, user.id, payload.reason, payload.details)
+        .save(&app.pool).await?;
+
+    _ = app.dispatch(DispatchTarget::Moderators, ReportCreate(report.clone()));
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Deletes a message - `DELETE /threads/{thread_id}/messages/{message_id}`
+///
+/// The message is replaced with a tombstone for users without [`Permissions::MANAGE_MESSAGES`] and purged
+/// after the retention period.
+///
+/// ### Path
+///
+/// * `thread_id` - The ID of the thread
+/// * `message_id` - The ID of the message to delete
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user is not the message author and does not have [`Permissions::MANAGE_MESSAGES`]
+/// * [`HttpError::UnknownMessage`] - If the message is not found or already deleted
+/// * [`HttpError::Undeletable`] - If the message has [`MessageFlags::UNDELETEABLE`]
+async fn delete_message(
+    path: web::Path<(i64, i64)>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, path.to_owned().0.into(), &user).await?;
+    let message = fetch_undeleted_message(&app, thread.id, path.to_owned().1.into()).await?;
+
+    if user.id != message.author.id && !user.has_permission(Permissions::MANAGE_MESSAGES) {
+        return Err(HttpError::MissingAccess);
+    }
+
+    if message.clone().is(MessageFlags::UNDELETEABLE) {
+        return Err(HttpError::Undeletable)
+    }
+
+    let message = message.soft_delete(&app.pool, user.id).await?;
+
+    _ = app.dispatch(app.database.fetch_dispatch_target(&thread).await?, MessageDelete {thread_id: message.thread_id, message_id: message.id});
+
+    if message.author.id != user.id {
+        let id = app.snowflake.lock().unwrap().build();
+        _ = app.notify(Notification::new(id, message.author.id, NotificationType::ModerationAction, user.id).with_message(thread.id, message.id)).await;
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Deletes several messages of a thread at once - `POST /threads/{thread_id}/messages/bulk-delete`
+///
+/// Either all of the messages are deleted or none of them.
+///
+/// ### Path
+///
+/// * `thread_id` - The ID of the thread
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_MESSAGES`]
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+/// * [`HttpError::UnknownMessage`] - If any of the messages is not found or already deleted
+/// * [`HttpError::Undeletable`] - If any of the messages has [`MessageFlags::UNDELETEABLE`]
+async fn bulk_delete_messages(
+    thread_id: web::Path<i64>,
+    payload: web::Json<BulkDeleteMessagesPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    if !user.has_permission(Permissions::MANAGE_MESSAGES) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    payload
+        .validate()
+        .map_err(HttpError::Validation)?;
+
+    let thread = fetch_visible_thread(&app, thread_id.into_inner().into(), &user).await?;
+
+    let mut message_ids = payload.messages.iter().map(|id| id.0).collect::<Vec<_>>();
+    message_ids.sort();
+    message_ids.dedup();
+
+    let mut tx = app.pool.begin().await?;
+    let deleted = Message::soft_delete_bulk(&mut *tx, thread.id, &message_ids, user.id).await?;
+    tx.commit().await?;
+
+    let (message_ids, mut author_ids): (Vec<_>, Vec<_>) = deleted.into_iter().unzip();
+    _ = app.dispatch(app.database.fetch_dispatch_target(&thread).await?, MessageDeleteBulk {
+        messages: vec![DeletedMessages {thread_id: thread.id, message_ids}]
+    });
+
+    author_ids.sort();
+    author_ids.dedup();
+    for author_id in author_ids.into_iter().filter(|id| *id != user.id) {
+        let id = app.snowflake.lock().unwrap().build();
+        _ = app.notify(Notification::new(id, author_id, NotificationType::ModerationAction, user.id).with_thread(thread.id)).await;
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Restores a deleted message and return [`Message`] - `POST /threads/{thread_id}/messages/{message_id}/restore`
+///
+/// ### Path
+///
+/// * `thread_id` - The ID of the thread
+/// * `message_id` - The ID of the message to restore
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_MESSAGES`]
+/// * [`HttpError::UnknownMessage`] - If the message is not found
+/// * [`HttpError::InvalidOperation`] - If the message is not deleted or the thread is deleted
+async fn restore_message(
+    path: web::Path<(i64, i64)>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    if !user.has_permission(Permissions::MANAGE_MESSAGES) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    let thread = fetch_visible_thread(&app, path.0.into(), &user).await?;
+    let message = app.database.fetch_message(thread.id, path.1.into())
+        .await.ok_or(HttpError::UnknownMessage)?;
+
+    if !message.is_deleted() {
+        return Err(HttpError::InvalidOperation("The message is not deleted".to_string()))
+    }
+
+    if thread.deleted_at.is_some() {
+        return Err(HttpError::InvalidOperation("The thread of the message is deleted".to_string()))
+    }
+
+    let message = message.restore(&app.pool).await?;
+
+    _ = app.dispatch(app.database.fetch_dispatch_target(&thread).await?, MessageUpdate(message.clone()));
+
+    Ok(HttpResponse::Ok().json(message))
+}
+
+/// Downloads a file attached to a message - `GET /threads/{thread_id}/messages/{message_id}/attachments/{attachment_id}`
+///
+/// Supports a single byte range in `Range` header, answering with `206 Partial Content`.
+///
+/// ### Path
+///
+/// * `thread_id` - The ID of the thread
+/// * `message_id` - The ID of the message
+/// * `attachment_id` - The ID of the attachment to download
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownMessage`] - If the message is not found
+/// * [`HttpError::UnknownAttachment`] - If the attachment is not found
+/// * [`HttpError::MissingAccess`] - If the thread is private and the user is not its member
+/// * [`HttpError::Storage`] - If the file couldn't be read
+async fn get_attachment(
+    path: web::Path<(i64, i64, i64)>,
+    range: Option<web::Header<Range>>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, path.0.into(), &user).await?;
+    let message = fetch_readable_message(&app, thread.id, path.1.into(), &user).await?;
+    let attachment = message.attachments.0.into_iter()
+        .find(|attachment| attachment.id == path.2.into())
+        .ok_or(HttpError::UnknownAttachment)?;
+
+    let size = attachment.size as u64;
+    let Ok(range) = byte_range(range.map(web::Header::into_inner), size) else {
+        return Ok(HttpResponse::build(StatusCode::RANGE_NOT_SATISFIABLE)
+            .insert_header((header::CONTENT_RANGE, format!("bytes */{}", size)))
+            .finish())
+    };
+
+    let content = app.storage.get(&attachment.key(), range).await
+        .map_err(HttpError::Storage)?;
+
+    let mut response = match range {
+        Some((start, end)) => {
+            let mut response = HttpResponse::PartialContent();
+            response.insert_header((header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, size)));
+            response
+        },
+        None => HttpResponse::Ok()
+    };
+
+    Ok(response
+        .insert_header(ContentType(attachment.content_type.parse().unwrap_or(mime::APPLICATION_OCTET_STREAM)))
+        .insert_header(ContentDisposition {
+            disposition: if attachment.is_image() { DispositionType::Inline } else { DispositionType::Attachment },
+            parameters: vec![DispositionParam::Filename(attachment.filename.clone())]
+        })
+        .insert_header((header::ACCEPT_RANGES, "bytes"))
+        .insert_header(header::ETag(EntityTag::new_strong(attachment.hash.clone())))
+        .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        // Compressing the file would break byte ranges
+        .insert_header(ContentEncoding::Identity)
+        .body(content))
+}
+
+/// Selects the inclusive byte range of a file to send for the `Range` header, `None` to send the whole file.
+///
+/// Several ranges and unknown units are ignored, as well as ranges of an empty file, which has no bytes to select.
+///
+/// ### Errors
+///
+/// * If the range doesn't overlap the file
+fn byte_range(range: Option<Range>, size: u64) -> std::result::Result<Option<(u64, u64)>, ()> {
+    match range {
+        Some(Range::Bytes(ranges)) if ranges.len() == 1 && size > 0 => ranges[0].to_satisfiable_range(size)
+            .map(Some)
+            .ok_or(()),
+        _ => Ok(None)
+    }
+}
+
+/// Parses an emoji from the path, giving custom emoji their current name.
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownEmoji`] - If the emoji is malformed or the custom emoji is not found
+async fn parse_path_emoji(app: &App, value: &str) -> Result<(Emoji, Option<CustomEmoji>)> {
+    let emoji = Emoji::parse(value).ok_or(HttpError::UnknownEmoji)?;
+
+    let Some(emoji_id) = emoji.id else {
+        return Ok((emoji, None))
+    };
+
+    let custom_emoji = app.database.fetch_emoji(emoji_id).await
+        .ok_or(HttpError::UnknownEmoji)?;
+
+    Ok(((&custom_emoji).into(), Some(custom_emoji)))
+}
+
+#[derive(Deserialize)]
+pub struct SearchReactionsQuery {
+    pub limit: Option<u16>,
+    pub after: Option<Snowflake>
+}
+
+/// Returns [`Vec<User>`] who reacted to a message with the emoji - `GET /threads/{thread_id}/messages/{message_id}/reactions/{emoji}`
+///
+/// ### Path
+///
+/// * `emoji` - Unicode emoji or custom emoji in `name:id` format
+///
+/// ### Query
+///
+/// * `limit` - Max number of users to return (1-100, default 25)
+/// * `after` - Get users after this user ID
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownMessage`] - If the message is not found
+/// * [`HttpError::MissingAccess`] - If the thread is private and the user is not its member
+/// * [`HttpError::UnknownEmoji`] - If the emoji is malformed or the custom emoji is not found
+async fn get_reactions(
+    path: web::Path<(i64, i64, String)>,
+    query: web::Query<SearchReactionsQuery>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, path.0.into(), &user).await?;
+    let message = fetch_readable_message(&app, thread.id, path.1.into(), &user).await?;
+    let (emoji, _) = parse_path_emoji(&app, &path.2).await?;
+
+    let users = app.database.fetch_reactors(message.id, &emoji, query.limit, query.after).await?;
+
+    Ok(HttpResponse::Ok().json(users))
+}
+
+/// Reacts to a message with the emoji - `PUT /threads/{thread_id}/messages/{message_id}/reactions/{emoji}/@me`
+///
+/// ### Path
+///
+/// * `emoji` - Unicode emoji or custom emoji in `name:id` format
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::ADD_REACTIONS`], can't see the thread
+///   or use the custom emoji
+/// * [`HttpError::UnknownMessage`] - If the message is not found
+/// * [`HttpError::UnknownEmoji`] - If the emoji is malformed or the custom emoji is not found
+/// * [`HttpError::InvalidOperation`] - If the message has too many distinct reactions
+async fn add_reaction(
+    path: web::Path<(i64, i64, String)>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    if !user.has_permission(Permissions::ADD_REACTIONS) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    let thread = fetch_visible_thread(&app, path.0.into(), &user).await?;
+    let message = fetch_undeleted_message(&app, thread.id, path.1.into()).await?;
+    let (emoji, custom_emoji) = parse_path_emoji(&app, &path.2).await?;
+
+    if custom_emoji.is_some_and(|emoji| !emoji.can_use(&user)) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    if message.add_reaction(&app.pool, user.id, &emoji).await? {
+        _ = app.dispatch(app.database.fetch_dispatch_target(&thread).await?, MessageReactionAdd {
+            thread_id: thread.id,
+            message_id: message.id,
+            user_id: user.id,
+            emoji: emoji.clone()
+        });
+
+        if message.author.id != user.id {
+            let id = app.snowflake.lock().unwrap().build();
+            _ = app.notify(Notification::new(id, message.author.id, NotificationType::Reaction, user.id)
+                .with_message(thread.id, message.id)
+                .with_emoji(emoji)).await;
+        }
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Removes the user's reaction from a message - `DELETE /threads/{thread_id}/messages/{message_id}/reactions/{emoji}/@me`
+///
+/// ### Path
+///
+/// * `emoji` - Unicode emoji or custom emoji in `name:id` format
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownMessage`] - If the message is not found
+/// * [`HttpError::MissingAccess`] - If the thread is private and the user is not its member
+/// * [`HttpError::UnknownEmoji`] - If the emoji is malformed or the custom emoji is not found
+async fn remove_reaction(
+    path: web::Path<(i64, i64, String)>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let thread = fetch_visible_thread(&app, path.0.into(), &user).await?;
+    let message = fetch_readable_message(&app, thread.id, path.1.into(), &user).await?;
+    let (emoji, _) = parse_path_emoji(&app, &path.2).await?;
+
+    if message.remove_reaction(&app.pool, user.id, &emoji).await? {
+        _ = app.dispatch(app.database.fetch_dispatch_target(&thread).await?, MessageReactionRemove {
+            thread_id: thread.id,
+            message_id: message.id,
+            user_id: user.id,
+            emoji
+        });
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(value: &str, size: u64) -> std::result::Result<Option<(u64, u64)>, ()> {
+        byte_range(Some(value.parse().unwrap()), size)
+    }
+
+    #[test]
+    fn selects_single_byte_range() {
+        assert_eq!(byte_range(None, 100), Ok(None));
+        assert_eq!(range("bytes=0-9", 100), Ok(Some((0, 9))));
+        assert_eq!(range("bytes=90-", 100), Ok(Some((90, 99))));
+        assert_eq!(range("bytes=-10", 100), Ok(Some((90, 99))));
+        assert_eq!(range("bytes=50-500", 100), Ok(Some((50, 99))));
+        assert_eq!(range("bytes=-500", 100), Ok(Some((0, 99))));
+    }
+
+    #[test]
+    fn ignores_several_ranges_and_unknown_units() {
+        assert_eq!(range("bytes=0-9, 20-29", 100), Ok(None));
+        assert_eq!(range("lines=0-9", 100), Ok(None));
+    }
+
+    #[test]
+    fn rejects_ranges_outside_of_file() {
+        assert_eq!(range("bytes=100-", 100), Err(()));
+        assert_eq!(range("bytes=100-200", 100), Err(()));
+        assert_eq!(range("bytes=-0", 100), Err(()));
+    }
+
+    #[test]
+    fn sends_empty_file_whole() {
+        assert_eq!(range("bytes=0-9", 0), Ok(None));
+        assert_eq!(range("bytes=0-", 0), Ok(None));
+        assert_eq!(range("bytes=-10", 0), Ok(None));
+    }
+}
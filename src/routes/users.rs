@@ -1,10 +1,27 @@
 use {
+    std::collections::BTreeMap,
     actix_web::{
         web, HttpResponse
     },
+    chrono::{Duration, Utc},
+    serde::Deserialize,
+    validator::Validate,
     crate::{
-        App, routes::{Result, HttpError},
-        models::UserCredentials
+        App, DispatchTarget,
+        routes::{Result, HttpError, threads::{fetch_visible_thread, fetch_undeleted_message}},
+        models::{
+            UserCredentials,
+            user::Permissions,
+            message::Message,
+            notification::{Notification, NotificationSettings, NotificationType},
+            bookmark::Bookmark,
+            requests::{
+                PurgeUserMessagesPayload, AckNotificationsPayload, ModifyNotificationPayload, CreateBookmarkPayload,
+                ModifyBookmarkPayload
+            },
+            gateway::{DeletedMessages, GatewayEvent::{MessageDeleteBulk, BookmarkCreate, BookmarkUpdate, BookmarkDelete}}
+        },
+        utils::snowflake::Snowflake
     }
 };
 
@@ -12,7 +29,19 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("users")
             .route("@me", web::get().to(get_current_user))
+            .route("@me/unread", web::get().to(get_unread_summary))
+            .route("@me/notifications", web::get().to(get_notifications))
+            .route("@me/notifications/ack", web::post().to(ack_notifications))
+            .route("@me/notifications/settings", web::get().to(get_notification_settings))
+            .route("@me/notifications/settings", web::patch().to(modify_notification_settings))
+            .route("@me/notifications/{notification_id}", web::patch().to(modify_notification))
+            .route("@me/bookmarks", web::get().to(get_bookmarks))
+            .route("@me/bookmarks", web::post().to(create_bookmark))
+            .route("@me/bookmarks/folders", web::get().to(get_bookmark_folders))
+            .route("@me/bookmarks/{bookmark_id}", web::patch().to(modify_bookmark))
+            .route("@me/bookmarks/{bookmark_id}", web::delete().to(delete_bookmark))
             .route("{user_id}", web::get().to(get_user))
+            .route("{user_id}/messages/purge", web::post().to(purge_user_messages))
     );
 }
 
@@ -24,6 +53,270 @@ async fn get_current_user(
         .map(|row| HttpResponse::Ok().json(row.into_inner().1))
 }
 
+/// Returns [`UnreadSummary`] of the current user - `GET /users/@me/unread`
+///
+/// Only threads the user has read before are counted.
+///
+/// [`UnreadSummary`]: crate::models::read_state::UnreadSummary
+async fn get_unread_summary(
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let summary = app.database.fetch_unread_summary(&user).await?;
+
+    Ok(HttpResponse::Ok().json(summary))
+}
+
+#[derive(Deserialize, Validate)]
+pub struct SearchNotificationsQuery {
+    #[serde(default)]
+    pub unread: bool,
+    #[validate(range(min = 1, max = 100, message = "Limit must be between 1 and 100"))]
+    pub limit: Option<u16>,
+    pub before: Option<Snowflake>,
+    pub after: Option<Snowflake>
+}
+
+/// Returns [`Vec<Notification>`] of the current user, newest first - `GET /users/@me/notifications`
+///
+/// ### Query
+///
+/// * `unread` - Get only unread notifications
+/// * `limit` - Max number of notifications to return (1-100, default 50)
+/// * `before` - Get notifications before this notification ID
+/// * `after` - Get notifications after this notification ID
+///
+/// ### Errors
+///
+/// * [`HttpError::Validation`] - If the limit is out of range
+async fn get_notifications(
+    query: web::Query<SearchNotificationsQuery>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    query.validate().map_err(HttpError::Validation)?;
+
+    let notifications = app.database.fetch_notifications(user.id, None, query.unread, query.limit, query.before, query.after).await?;
+
+    Ok(HttpResponse::Ok().json(notifications))
+}
+
+/// Marks all notifications of the current user as read - `POST /users/@me/notifications/ack`
+///
+/// When `notification_id` is given, only notifications up to it are marked.
+async fn ack_notifications(
+    payload: web::Json<AckNotificationsPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    Notification::read_all(&app.pool, user.id, payload.notification_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Marks a notification of the current user as read or unread and return [`Notification`] - `PATCH /users/@me/notifications/{notification_id}`
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownNotification`] - If the notification is not found
+async fn modify_notification(
+    notification_id: web::Path<i64>,
+    payload: web::Json<ModifyNotificationPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    let notification = app.database.fetch_notification(user.id, notification_id.into_inner().into()).await
+        .ok_or(HttpError::UnknownNotification)?
+        .set_read(&app.pool, payload.read).await?;
+
+    Ok(HttpResponse::Ok().json(notification))
+}
+
+/// Returns [`NotificationSettings`] of the current user - `GET /users/@me/notifications/settings`
+async fn get_notification_settings(
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let settings = app.database.fetch_notification_settings(user.id).await?;
+
+    Ok(HttpResponse::Ok().json(settings))
+}
+
+/// Enables or disables notification types and return [`NotificationSettings`] - `PATCH /users/@me/notifications/settings`
+///
+/// Types missing from the payload keep their settings. Disabled notifications are neither saved nor dispatched.
+///
+/// ### Errors
+///
+/// * [`HttpError::Payload`] - If the payload contains unknown notification types
+async fn modify_notification_settings(
+    payload: web::Json<NotificationSettings>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    payload.save(&app.pool, user.id).await?;
+    let settings = app.database.fetch_notification_settings(user.id).await?;
+
+    Ok(HttpResponse::Ok().json(settings))
+}
+
+#[derive(Deserialize, Validate)]
+pub struct SearchBookmarksQuery {
+    pub folder: Option<String>,
+    #[validate(range(min = 1, max = 100, message = "Limit must be between 1 and 100"))]
+    pub limit: Option<u16>,
+    pub before: Option<Snowflake>,
+    pub after: Option<Snowflake>
+}
+
+/// Returns [`Vec<Bookmark>`] of the current user, newest first - `GET /users/@me/bookmarks`
+///
+/// Bookmarks on threads the user can't see anymore are listed without the thread, deleted messages are tombstones.
+///
+/// ### Query
+///
+/// * `folder` - Get only bookmarks in this folder
+/// * `limit` - Max number of bookmarks to return (1-100, default 50)
+/// * `before` - Get bookmarks before this bookmark ID
+/// * `after` - Get bookmarks after this bookmark ID
+///
+/// ### Errors
+///
+/// * [`HttpError::Validation`] - If the limit is out of range
+async fn get_bookmarks(
+    query: web::Query<SearchBookmarksQuery>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    query.validate().map_err(HttpError::Validation)?;
+
+    let bookmarks = app.database.fetch_bookmarks(&user, None, query.folder.as_deref(), query.limit, query.before, query.after).await?;
+
+    Ok(HttpResponse::Ok().json(bookmarks))
+}
+
+/// Bookmarks a thread or a message and returns [`Bookmark`] - `POST /users/@me/bookmarks`
+///
+/// ### Errors
+///
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+/// * [`HttpError::UnknownThread`] - If the thread is not found
+/// * [`HttpError::MissingAccess`] - If the thread is private and the user is not its member
+/// * [`HttpError::UnknownMessage`] - If the message is not found in the thread
+/// * [`HttpError::InvalidOperation`] - If the user has already bookmarked the thread or message
+async fn create_bookmark(
+    payload: web::Json<CreateBookmarkPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    payload
+        .validate()
+        .map_err(HttpError::Validation)?;
+
+    let payload = payload.into_inner();
+    let thread = fetch_visible_thread(&app, payload.thread_id, &user).await?;
+
+    let id = app.snowflake.lock().unwrap().build();
+    let mut bookmark = Bookmark::new(id, user.id, thread)
+        .with_folder(payload.folder)
+        .with_note(payload.note);
+
+    if let Some(message_id) = payload.message_id {
+        bookmark = bookmark.with_message(fetch_undeleted_message(&app, payload.thread_id, message_id).await?);
+    }
+
+    let bookmark = bookmark.save(&app.pool).await?;
+    _ = app.dispatch(DispatchTarget::User(user.id), BookmarkCreate(bookmark.clone()));
+
+    Ok(HttpResponse::Ok().json(bookmark))
+}
+
+/// Returns [`Vec<BookmarkFolder>`] of the current user - `GET /users/@me/bookmarks/folders`
+///
+/// [`Vec<BookmarkFolder>`]: crate::models::bookmark::BookmarkFolder
+async fn get_bookmark_folders(
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+    let folders = app.database.fetch_bookmark_folders(user.id).await?;
+
+    Ok(HttpResponse::Ok().json(folders))
+}
+
+/// Modifies the folder or note of a bookmark and returns [`Bookmark`] - `PATCH /users/@me/bookmarks/{bookmark_id}`
+///
+/// ### Errors
+///
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+/// * [`HttpError::UnknownBookmark`] - If the bookmark is not found
+async fn modify_bookmark(
+    bookmark_id: web::Path<i64>,
+    payload: web::Json<ModifyBookmarkPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    payload
+        .validate()
+        .map_err(HttpError::Validation)?;
+
+    let mut bookmark = app.database.fetch_bookmark(user.id, bookmark_id.into_inner().into()).await
+        .ok_or(HttpError::UnknownBookmark)?;
+
+    let payload = payload.into_inner();
+    if let Some(folder) = payload.folder {
+        bookmark = bookmark.with_folder(folder);
+    }
+    if let Some(note) = payload.note {
+        bookmark = bookmark.with_note(note);
+    }
+
+    let bookmark = bookmark.edit(&app.pool).await?;
+    let bookmark = app.database.fetch_bookmarks(&user, Some(bookmark.id), None, None, None, None).await?
+        .pop().ok_or(HttpError::UnknownBookmark)?;
+    _ = app.dispatch(DispatchTarget::User(user.id), BookmarkUpdate(bookmark.clone()));
+
+    Ok(HttpResponse::Ok().json(bookmark))
+}
+
+/// Deletes a bookmark of the current user - `DELETE /users/@me/bookmarks/{bookmark_id}`
+///
+/// ### Errors
+///
+/// * [`HttpError::UnknownBookmark`] - If the bookmark is not found
+async fn delete_bookmark(
+    bookmark_id: web::Path<i64>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    let bookmark = app.database.fetch_bookmark(user.id, bookmark_id.into_inner().into()).await
+        .ok_or(HttpError::UnknownBookmark)?;
+
+    let bookmark_id = bookmark.id;
+    bookmark.delete(&app.pool).await?;
+    _ = app.dispatch(DispatchTarget::User(user.id), BookmarkDelete {bookmark_id});
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
 /// Returns [`User`] by given ID - `GET /users/{user_id}`
 ///
 /// ### Errors
@@ -36,4 +329,69 @@ async fn get_user(
     app.database.fetch_user(user_id.into_inner().into())
         .await.ok_or(HttpError::UnknownUser)
         .map(|row| HttpResponse::Ok().json(row))
-}
\ No newline at end of file
+}
+
+/// Deletes messages the user sent in the last hours across all threads - `POST /users/{user_id}/messages/purge`
+///
+/// Messages with [`MessageFlags::UNDELETEABLE`] are skipped. A single `MESSAGE_DELETE_BULK` event is dispatched
+/// for messages of public threads, messages of each private thread are dispatched to its members in their own event.
+///
+/// ### Errors
+///
+/// * [`HttpError::MissingAccess`] - If the user does not have [`Permissions::MANAGE_MESSAGES`]
+/// * [`HttpError::Validation`] - If the payload is malformed or doesn't follow requirements
+/// * [`HttpError::UnknownUser`] - If the user is not found
+///
+/// [`MessageFlags::UNDELETEABLE`]: crate::models::message::MessageFlags::UNDELETEABLE
+async fn purge_user_messages(
+    user_id: web::Path<i64>,
+    payload: web::Json<PurgeUserMessagesPayload>,
+    app: web::Data<App>,
+    credentials: Option<web::ReqData<UserCredentials>>
+) -> Result<HttpResponse> {
+    let user = credentials.ok_or(HttpError::Unauthorized)?.into_inner().1;
+
+    if !user.has_permission(Permissions::MANAGE_MESSAGES) {
+        return Err(HttpError::MissingAccess)
+    }
+
+    payload
+        .validate()
+        .map_err(HttpError::Validation)?;
+
+    let author = app.database.fetch_user(user_id.into_inner().into())
+        .await.ok_or(HttpError::UnknownUser)?;
+
+    let mut tx = app.pool.begin().await?;
+    let deleted = Message::soft_delete_by_author(&mut *tx, author.id, Utc::now() - Duration::hours(payload.hours), user.id).await?;
+    tx.commit().await?;
+
+    let mut threads = BTreeMap::<_, Vec<_>>::new();
+    for (thread_id, message_id) in deleted {
+        threads.entry(thread_id).or_default().push(message_id);
+    }
+
+    let thread_ids = threads.keys().map(|id| id.0).collect::<Vec<_>>();
+    let mut messages = Vec::new();
+    for thread in app.database.fetch_threads_by_ids(&thread_ids).await? {
+        let Some(message_ids) = threads.remove(&thread.id) else {
+            continue
+        };
+        let deleted = DeletedMessages {thread_id: thread.id, message_ids};
+        match app.database.fetch_dispatch_target(&thread).await? {
+            DispatchTarget::Global => messages.push(deleted),
+            target => _ = app.dispatch(target, MessageDeleteBulk {messages: vec![deleted]})
+        }
+    }
+
+    if !messages.is_empty() {
+        _ = app.dispatch(DispatchTarget::Global, MessageDeleteBulk {messages});
+    }
+
+    if author.id != user.id && !thread_ids.is_empty() {
+        let id = app.snowflake.lock().unwrap().build();
+        _ = app.notify(Notification::new(id, author.id, NotificationType::ModerationAction, user.id)).await;
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
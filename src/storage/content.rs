@@ -0,0 +1,85 @@
+use {
+    std::{io, io::SeekFrom},
+    actix_web::web::{Bytes, BytesMut},
+    sha2::{Digest, Sha256},
+    tokio::{fs::File, io::{AsyncSeekExt, AsyncWriteExt}}
+};
+
+/// How many bytes from the beginning of the content are kept in memory, enough to read dimensions of images
+pub const HEAD_SIZE: usize = 256 * 1024;
+
+/// Content waiting to be stored, kept in an anonymous temporary file so large files aren't held in memory
+pub struct Content {
+    file: File,
+    /// The size of the content in bytes
+    pub size: u64,
+    /// The SHA-256 hash of the content in hex
+    pub hash: String,
+    /// The first [`HEAD_SIZE`] bytes of the content
+    pub head: Bytes
+}
+
+impl Content {
+    /// Write the content to a temporary file.
+    pub async fn from_bytes(content: &[u8]) -> io::Result<Self> {
+        let mut writer = ContentWriter::new()?;
+        writer.write(content).await?;
+        writer.finish().await
+    }
+
+    /// Returns a new handle to the file positioned at the beginning of the content.
+    pub async fn reader(&self) -> io::Result<File> {
+        let mut file = self.file.try_clone().await?;
+        file.seek(SeekFrom::Start(0)).await?;
+        Ok(file)
+    }
+}
+
+/// Writes the content received in chunks to a temporary file, hashing it on the way
+pub struct ContentWriter {
+    file: File,
+    size: u64,
+    hasher: Sha256,
+    head: BytesMut
+}
+
+impl ContentWriter {
+    /// Create a new [`ContentWriter`] object with an empty temporary file
+    pub fn new() -> io::Result<Self> {
+        Ok(Self {
+            file: File::from_std(tempfile::tempfile()?),
+            size: 0,
+            hasher: Sha256::new(),
+            head: BytesMut::new()
+        })
+    }
+
+    /// The number of bytes written so far
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Append the chunk to the content.
+    pub async fn write(&mut self, chunk: &[u8]) -> io::Result<()> {
+        self.file.write_all(chunk).await?;
+        self.hasher.update(chunk);
+        self.size += chunk.len() as u64;
+
+        let kept = chunk.len().min(HEAD_SIZE.saturating_sub(self.head.len()));
+        self.head.extend_from_slice(&chunk[..kept]);
+
+        Ok(())
+    }
+
+    /// Finish writing and return the [`Content`].
+    pub async fn finish(mut self) -> io::Result<Content> {
+        self.file.flush().await?;
+
+        Ok(Content {
+            file: self.file,
+            size: self.size,
+            hash: format!("{:x}", self.hasher.finalize()),
+            head: self.head.freeze()
+        })
+    }
+}
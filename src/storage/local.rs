@@ -0,0 +1,65 @@
+use {
+    std::{io, io::SeekFrom, path::PathBuf},
+    actix_web::web::Bytes,
+    futures::future::{BoxFuture, FutureExt},
+    tokio::{fs, io::{self as tokio_io, AsyncReadExt, AsyncSeekExt, AsyncWriteExt}},
+    crate::storage::{Storage, content::Content}
+};
+
+/// Storage keeping files in a directory of the local filesystem
+pub struct LocalStorage {
+    /// The directory files are stored in
+    pub root: PathBuf
+}
+
+impl LocalStorage {
+    /// Create a new [`LocalStorage`] object
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self {
+            root: root.into()
+        }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+}
+
+impl Storage for LocalStorage {
+    fn put<'a>(&'a self, key: &'a str, _content_type: &'a str, content: &'a Content) -> BoxFuture<'a, io::Result<()>> {
+        async move {
+            let path = self.path(key);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+
+            let mut file = fs::File::create(path).await?;
+            tokio_io::copy(&mut content.reader().await?, &mut file).await?;
+            file.flush().await
+        }.boxed()
+    }
+
+    fn get<'a>(&'a self, key: &'a str, range: Option<(u64, u64)>) -> BoxFuture<'a, io::Result<Bytes>> {
+        async move {
+            let Some((start, end)) = range else {
+                return fs::read(self.path(key)).await.map(Bytes::from)
+            };
+
+            let mut file = fs::File::open(self.path(key)).await?;
+            file.seek(SeekFrom::Start(start)).await?;
+
+            let mut content = Vec::new();
+            file.take(end - start + 1).read_to_end(&mut content).await?;
+            Ok(content.into())
+        }.boxed()
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, io::Result<()>> {
+        async move {
+            match fs::remove_file(self.path(key)).await {
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+                result => result
+            }
+        }.boxed()
+    }
+}
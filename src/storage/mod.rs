@@ -0,0 +1,51 @@
+use {
+    std::io,
+    actix_web::web::Bytes,
+    futures::future::BoxFuture,
+    self::{content::Content, local::LocalStorage, s3::S3Storage}
+};
+
+pub mod content;
+pub mod local;
+pub mod s3;
+
+/// Backend which keeps contents of uploaded files
+pub trait Storage: Send + Sync {
+    /// Store the content under the key, replacing the previous one. The content is streamed from its temporary file.
+    fn put<'a>(&'a self, key: &'a str, content_type: &'a str, content: &'a Content) -> BoxFuture<'a, io::Result<()>>;
+
+    /// Read the content stored under the key.
+    ///
+    /// ### Arguments
+    ///
+    /// * `range` - Inclusive byte range to read, the whole content if `None`
+    ///
+    /// ### Errors
+    ///
+    /// * [`io::ErrorKind::NotFound`] - If nothing is stored under the key
+    fn get<'a>(&'a self, key: &'a str, range: Option<(u64, u64)>) -> BoxFuture<'a, io::Result<Bytes>>;
+
+    /// Remove the content stored under the key.
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, io::Result<()>>;
+}
+
+/// Create the storage selected by `STORAGE` variable, `local` by default.
+///
+/// ### Panics
+///
+/// * If `STORAGE` is unknown or the selected storage is not configured
+pub fn from_env() -> Box<dyn Storage> {
+    match dotenvy::var("STORAGE").as_deref() {
+        Ok("local") | Err(_) => Box::new(LocalStorage::new(
+            dotenvy::var("STORAGE_PATH").unwrap_or("attachments".to_string())
+        )),
+        Ok("s3") => Box::new(S3Storage::new(
+            &dotenvy::var("S3_ENDPOINT").expect("`S3_ENDPOINT` not in .env"),
+            &dotenvy::var("S3_BUCKET").expect("`S3_BUCKET` not in .env"),
+            &dotenvy::var("S3_REGION").unwrap_or("us-east-1".to_string()),
+            &dotenvy::var("S3_ACCESS_KEY").expect("`S3_ACCESS_KEY` not in .env"),
+            &dotenvy::var("S3_SECRET_KEY").expect("`S3_SECRET_KEY` not in .env")
+        )),
+        Ok(other) => panic!("Unknown storage `{}`", other)
+    }
+}
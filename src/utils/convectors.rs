@@ -1,3 +1,5 @@
+use crate::utils::snowflake::Snowflake;
+
 /// Implements `Deserialize`, `Serializer`, `From`, `Into` to bit flag structure
 #[macro_export]
 macro_rules! bitflags_convector {
@@ -63,13 +65,28 @@ pub fn to_string_radix_unsigned(value: u64, radix: u32) -> String {
 
         // Will panic anyway
         result.push(char::from_digit(tmp, radix).unwrap());
-        if value <= 0 {
+        if value == 0 {
             break;
         }
     }
     result.iter().rev().collect()
 }
 
+/// Deserialize a comma separated list of snowflakes, e.g. `1,2,3`.
+pub fn deserialize_snowflake_list<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<Snowflake>, D::Error> {
+    let value: String = serde::Deserialize::deserialize(deserializer)?;
+
+    value.split(',')
+        .filter(|part| !part.is_empty())
+        .map(|part| part.trim().parse::<i64>().map(Snowflake).map_err(serde::de::Error::custom))
+        .collect()
+}
+
+/// Deserialize a present field as `Some`, so an explicit `null` can be told apart from a missing field.
+pub fn deserialize_some<'de, T: serde::Deserialize<'de>, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<T>, D::Error> {
+    serde::Deserialize::deserialize(deserializer).map(Some)
+}
+
 pub fn hex_to_int(hex: &str) -> i64 {
-    i64::from_str_radix(hex, 16).unwrap_or_else(|_| 0)
+    i64::from_str_radix(hex, 16).unwrap_or(0)
 }
\ No newline at end of file
@@ -0,0 +1,578 @@
+//! Markdown dialect of message content.
+//!
+//! Supported syntax:
+//!
+//! * `**bold**`, `*italic*` or `_italic_`, `||spoiler||` and `` `code` ``
+//! * `[label](https://example.com)` and bare `https://` links
+//! * ```` ```language ```` fenced code blocks and `>` quotes
+//! * custom emoji in `<:name:id>` format
+//! * user mentions in `<@id>` format, `@username` mentions are left as text
+//!
+//! Everything else, including raw HTML, is rendered as escaped text, so the rendered HTML contains only
+//! the tags produced here.
+
+use {
+    std::{collections::HashMap, sync::LazyLock},
+    regex::Regex,
+    crate::models::{
+        emoji::CUSTOM_EMOJI_PATTERN,
+        mention::Mentions
+    }
+};
+
+/// User mention by ID, e.g. `<@1234>`
+static USER_MENTION_PATTERN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^<@(\d{1,19})>").unwrap());
+
+/// User mention by name, e.g. `@username`, which is not a part of an email address
+static USERNAME_MENTION_PATTERN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:^|[^\w@.])@([\w.\-]{2,32})").unwrap());
+
+/// Schemes allowed in links
+const LINK_SCHEMES: [&str; 3] = ["http://", "https://", "mailto:"];
+
+/// The maximum length of code block language
+const MAX_LANGUAGE_LENGTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    /// Lines of text separated by blank lines from other blocks
+    Paragraph(Vec<Inline>),
+    /// Consecutive lines starting with `>`
+    Quote(Vec<Inline>),
+    /// Fenced code block, its content is not formatted
+    Code {
+        language: Option<String>,
+        code: String
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inline {
+    Text(String),
+    LineBreak,
+    Code(String),
+    Bold(Vec<Inline>),
+    Italic(Vec<Inline>),
+    Spoiler(Vec<Inline>),
+    Link {
+        label: Vec<Inline>,
+        url: String
+    },
+    Emoji {
+        name: String,
+        id: String
+    },
+    Mention(String)
+}
+
+/// Parses message content into blocks.
+pub fn parse(content: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph = Vec::<&str>::new();
+    let mut lines = content.lines().peekable();
+
+    let flush = |paragraph: &mut Vec<&str>, blocks: &mut Vec<Block>| {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(parse_inline(&paragraph.join("\n"), true)));
+            paragraph.clear();
+        }
+    };
+
+    while let Some(line) = lines.next() {
+        if let Some(info) = line.strip_prefix("```") {
+            flush(&mut paragraph, &mut blocks);
+
+            // ```code``` on a single line
+            if let Some(code) = info.trim_end().strip_suffix("```") {
+                blocks.push(Block::Code { language: None, code: code.to_string() });
+                continue
+            }
+
+            let code = lines.by_ref()
+                .take_while(|line| line.trim_end() != "```")
+                .collect::<Vec<_>>()
+                .join("\n");
+            blocks.push(Block::Code { language: parse_language(info.trim()), code });
+        } else if line.starts_with('>') {
+            flush(&mut paragraph, &mut blocks);
+
+            let mut quote = vec![strip_quote(line)];
+            while let Some(line) = lines.next_if(|line| line.starts_with('>')) {
+                quote.push(strip_quote(line));
+            }
+            blocks.push(Block::Quote(parse_inline(&quote.join("\n"), true)));
+        } else if line.trim().is_empty() {
+            flush(&mut paragraph, &mut blocks);
+        } else {
+            paragraph.push(line);
+        }
+    }
+    flush(&mut paragraph, &mut blocks);
+
+    blocks
+}
+
+/// Returns IDs and usernames of users mentioned in the content outside of code, without duplicates.
+pub fn mentions(content: &str) -> (Vec<i64>, Vec<String>) {
+    fn collect(nodes: &[Inline], ids: &mut Vec<i64>, usernames: &mut Vec<String>) {
+        for node in nodes {
+            match node {
+                Inline::Text(text) => {
+                    for captures in USERNAME_MENTION_PATTERN.captures_iter(text) {
+                        let username = captures[1].trim_end_matches(['.', '-']);
+                        if username.len() >= 2 && !usernames.iter().any(|name| name == username) {
+                            usernames.push(username.to_string());
+                        }
+                    }
+                },
+                Inline::Mention(id) => {
+                    if let Ok(id) = id.parse::<i64>() {
+                        if !ids.contains(&id) {
+                            ids.push(id);
+                        }
+                    }
+                },
+                Inline::Bold(nodes) | Inline::Italic(nodes) | Inline::Spoiler(nodes) => collect(nodes, ids, usernames),
+                Inline::Link { label, .. } => collect(label, ids, usernames),
+                Inline::LineBreak | Inline::Code(..) | Inline::Emoji { .. } => ()
+            }
+        }
+    }
+
+    let (mut ids, mut usernames) = (Vec::new(), Vec::new());
+    for block in parse(content) {
+        if let Block::Paragraph(nodes) | Block::Quote(nodes) = block {
+            collect(&nodes, &mut ids, &mut usernames);
+        }
+    }
+
+    (ids, usernames)
+}
+
+/// Renders message content to HTML. Mentions of users who are not in `mentions` are left as text.
+pub fn to_html(content: &str, mentions: &Mentions) -> String {
+    let mut html = String::new();
+
+    for block in parse(content) {
+        match block {
+            Block::Paragraph(nodes) => {
+                html.push_str("<p>");
+                inline_html(&nodes, mentions, &mut html);
+                html.push_str("</p>");
+            },
+            Block::Quote(nodes) => {
+                html.push_str("<blockquote>");
+                inline_html(&nodes, mentions, &mut html);
+                html.push_str("</blockquote>");
+            },
+            Block::Code { language, code } => {
+                match language {
+                    Some(language) => html.push_str(&format!("<pre><code class=\"language-{}\">", escape(&language))),
+                    None => html.push_str("<pre><code>")
+                }
+                html.push_str(&escape(&code));
+                html.push_str("</code></pre>");
+            }
+        }
+    }
+
+    html
+}
+
+/// Renders message content to plain text without any formatting.
+///
+/// Spoilers are replaced with `[spoiler]`, so the text is safe to show in previews. Mentions of users
+/// who are in `mentions` are replaced with their usernames.
+pub fn to_text(content: &str, mentions: &Mentions) -> String {
+    parse(content).into_iter()
+        .map(|block| match block {
+            Block::Paragraph(nodes) | Block::Quote(nodes) => {
+                let mut text = String::new();
+                inline_text(&nodes, mentions, &mut text);
+                text
+            },
+            Block::Code { code, .. } => code
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Escapes characters which have special meaning in HTML.
+pub fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+fn strip_quote(line: &str) -> &str {
+    let line = &line[1..];
+    line.strip_prefix(' ').unwrap_or(line)
+}
+
+fn parse_language(info: &str) -> Option<String> {
+    let valid = !info.is_empty() && info.len() <= MAX_LANGUAGE_LENGTH
+        && info.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '_' | '#'));
+
+    valid.then(|| info.to_lowercase())
+}
+
+fn is_valid_url(url: &str) -> bool {
+    LINK_SCHEMES.iter().any(|scheme| url.len() > scheme.len() && url.get(..scheme.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme)))
+        && !url.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+fn char_at(text: &str, index: usize) -> Option<char> {
+    text.get(index..).and_then(|rest| rest.chars().next())
+}
+
+fn char_before(text: &str, index: usize) -> Option<char> {
+    text[..index].chars().next_back()
+}
+
+/// Closing markers found after positions of the text, so unclosed spans don't make the text scanned again
+/// for every opening marker
+#[derive(Default)]
+struct Closings(HashMap<(&'static str, usize), Option<usize>>);
+
+/// Returns the position after the character at `index`, skipping escaped characters and code.
+fn next_index(text: &str, index: usize, marker: &str) -> Option<usize> {
+    let c = char_at(text, index)?;
+    let rest = &text[index..];
+
+    if c == '\\' {
+        return Some(index + 1 + char_at(text, index + 1).map_or(0, char::len_utf8))
+    }
+
+    if c == '`' {
+        if let Some(end) = rest[1..].find('`') {
+            return Some(index + end + 2)
+        }
+    }
+
+    // Bold inside of italic
+    if marker == "*" && rest.starts_with("**") {
+        return Some(index + 2)
+    }
+
+    Some(index + c.len_utf8())
+}
+
+/// Checks whether the marker at `index` can close a span.
+fn is_closing(text: &str, index: usize, marker: &str) -> bool {
+    let rest = &text[index..];
+
+    rest.starts_with(marker)
+        && !(marker == "*" && rest.starts_with("**"))
+        && !char_before(text, index).is_some_and(char::is_whitespace)
+        && (marker != "_" || !char_at(text, index + 1).is_some_and(char::is_alphanumeric))
+}
+
+/// Finds the closing marker of a span starting at `start`, skipping escaped characters and code.
+///
+/// Positions passed on the way are remembered in `closings` with the result, so the text is scanned once
+/// for each marker.
+fn find_closing(text: &str, start: usize, marker: &'static str, closings: &mut Closings) -> Option<usize> {
+    let mut passed = Vec::new();
+    let mut index = start;
+
+    let closing = loop {
+        if let Some(&closing) = closings.0.get(&(marker, index)) {
+            break closing
+        }
+        passed.push(index);
+
+        match next_index(text, index, marker) {
+            Some(next) if next < text.len() && is_closing(text, next, marker) => break Some(next),
+            Some(next) if next < text.len() => index = next,
+            _ => break None
+        }
+    };
+
+    for index in passed {
+        closings.0.insert((marker, index), closing);
+    }
+
+    closing
+}
+
+/// Parses a span wrapped in `marker` at `index`, returning its content and total length.
+fn parse_span<'a>(text: &'a str, index: usize, marker: &'static str, closings: &mut Closings) -> Option<(&'a str, usize)> {
+    let start = index + marker.len();
+    if char_at(text, start).is_none_or(char::is_whitespace) {
+        return None
+    }
+
+    let end = find_closing(text, start, marker, closings)?;
+    Some((&text[start..end], end + marker.len() - index))
+}
+
+/// Parses a `[label](url)` link at `index`, returning its label, URL and total length.
+fn parse_link<'a>(text: &'a str, index: usize, closings: &mut Closings) -> Option<(&'a str, &'a str, usize)> {
+    let label_end = find_closing(text, index + 1, "]", closings)?;
+    if char_at(text, label_end + 1) != Some('(') {
+        return None
+    }
+
+    let url_start = label_end + 2;
+    let mut depth = 0;
+    for (offset, c) in text[url_start..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                let url = &text[url_start..url_start + offset];
+                return is_valid_url(url)
+                    .then(|| (&text[index + 1..label_end], url, url_start + offset + 1 - index))
+            },
+            ')' => depth -= 1,
+            c if c.is_whitespace() => return None,
+            _ => ()
+        }
+    }
+
+    None
+}
+
+/// Parses a bare `http(s)://` link at `index`, returning its length.
+fn parse_autolink(text: &str, index: usize) -> Option<usize> {
+    let rest = &text[index..];
+    if !rest.starts_with("http://") && !rest.starts_with("https://") {
+        return None
+    }
+
+    let mut url = &rest[..rest.find(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"')).unwrap_or(rest.len())];
+
+    // Punctuation at the end most likely belongs to the sentence
+    loop {
+        let trimmed = url.trim_end_matches(['.', ',', ':', ';', '!', '?', '\'', '*', '_', '|']);
+        let trimmed = match trimmed.strip_suffix(')') {
+            Some(stripped) if trimmed.matches('(').count() < trimmed.matches(')').count() => stripped,
+            _ => trimmed
+        };
+
+        if trimmed.len() == url.len() {
+            break
+        }
+        url = trimmed;
+    }
+
+    is_valid_url(url).then_some(url.len())
+}
+
+/// Parses a node at `index`, returning it with its length.
+fn parse_node(text: &str, index: usize, links: bool, closings: &mut Closings) -> Option<(Inline, usize)> {
+    let rest = &text[index..];
+
+    if let Some(code) = rest.strip_prefix('`') {
+        let end = code.find('`')?;
+        return (end > 0).then(|| (Inline::Code(code[..end].to_string()), end + 2))
+    }
+
+    if rest.starts_with("**") {
+        return match parse_span(text, index, "**", closings) {
+            Some((inner, length)) => Some((Inline::Bold(parse_inline(inner, links)), length)),
+            None => Some((Inline::Text("**".to_string()), 2))
+        }
+    }
+
+    if rest.starts_with("||") {
+        return parse_span(text, index, "||", closings)
+            .map(|(inner, length)| (Inline::Spoiler(parse_inline(inner, links)), length))
+    }
+
+    if rest.starts_with('*') {
+        return parse_span(text, index, "*", closings)
+            .map(|(inner, length)| (Inline::Italic(parse_inline(inner, links)), length))
+    }
+
+    // Underscores inside of words, e.g. snake_case, are left as is
+    if rest.starts_with('_') && !char_before(text, index).is_some_and(char::is_alphanumeric) {
+        return parse_span(text, index, "_", closings)
+            .map(|(inner, length)| (Inline::Italic(parse_inline(inner, links)), length))
+    }
+
+    if rest.starts_with("<@") {
+        let captures = USER_MENTION_PATTERN.captures(rest)?;
+        return Some((Inline::Mention(captures[1].to_string()), captures[0].len()))
+    }
+
+    if rest.starts_with("<:") {
+        let captures = CUSTOM_EMOJI_PATTERN.captures(rest)?;
+        let matched = captures.get(0)?;
+        return (matched.start() == 0)
+            .then(|| (Inline::Emoji { name: captures[1].to_string(), id: captures[2].to_string() }, matched.end()))
+    }
+
+    if !links {
+        return None
+    }
+
+    if rest.starts_with('[') {
+        return parse_link(text, index, closings)
+            .map(|(label, url, length)| (Inline::Link { label: parse_inline(label, false), url: url.to_string() }, length))
+    }
+
+    parse_autolink(text, index)
+        .map(|length| (Inline::Link { label: vec![Inline::Text(rest[..length].to_string())], url: rest[..length].to_string() }, length))
+}
+
+/// Parses inline formatting of the text. Links are not parsed inside of link labels.
+fn parse_inline(text: &str, links: bool) -> Vec<Inline> {
+    let mut nodes = Vec::new();
+    let mut plain = String::new();
+    let mut index = 0;
+    let mut closings = Closings::default();
+
+    let flush = |plain: &mut String, nodes: &mut Vec<Inline>| {
+        if !plain.is_empty() {
+            nodes.push(Inline::Text(std::mem::take(plain)));
+        }
+    };
+
+    while let Some(c) = char_at(text, index) {
+        if let Some((node, length)) = parse_node(text, index, links, &mut closings) {
+            flush(&mut plain, &mut nodes);
+            nodes.push(node);
+            index += length;
+            continue
+        }
+
+        match c {
+            '\\' if char_at(text, index + 1).is_some_and(|c| c.is_ascii_punctuation()) => {
+                plain.push(text.as_bytes()[index + 1] as char);
+                index += 2;
+            },
+            '\n' => {
+                flush(&mut plain, &mut nodes);
+                nodes.push(Inline::LineBreak);
+                index += 1;
+            },
+            _ => {
+                plain.push(c);
+                index += c.len_utf8();
+            }
+        }
+    }
+    flush(&mut plain, &mut nodes);
+
+    nodes
+}
+
+fn mentioned_username<'a>(mentions: &'a Mentions, id: &str) -> Option<&'a str> {
+    mentions.0.iter()
+        .find(|user| user.id.0.to_string() == id)
+        .map(|user| user.username.as_str())
+}
+
+fn inline_html(nodes: &[Inline], mentions: &Mentions, html: &mut String) {
+    for node in nodes {
+        match node {
+            Inline::Text(text) => html.push_str(&escape(text)),
+            Inline::LineBreak => html.push_str("<br>"),
+            Inline::Code(code) => html.push_str(&format!("<code>{}</code>", escape(code))),
+            Inline::Bold(nodes) => wrap_html("<strong>", nodes, "</strong>", mentions, html),
+            Inline::Italic(nodes) => wrap_html("<em>", nodes, "</em>", mentions, html),
+            Inline::Spoiler(nodes) => wrap_html("<span class=\"spoiler\">", nodes, "</span>", mentions, html),
+            Inline::Link { label, url } => {
+                let open = format!("<a href=\"{}\" rel=\"nofollow noopener noreferrer\" target=\"_blank\">", escape(url));
+                wrap_html(&open, label, "</a>", mentions, html);
+            },
+            Inline::Mention(id) => match mentioned_username(mentions, id) {
+                Some(username) => html.push_str(&format!("<span class=\"mention\" data-user-id=\"{}\">@{}</span>", escape(id), escape(username))),
+                None => html.push_str(&escape(&format!("<@{id}>")))
+            },
+            Inline::Emoji { name, id } => {
+                html.push_str(&format!("<img class=\"emoji\" src=\"/api/v1/emojis/{}/image\" alt=\":{}:\">", escape(id), escape(name)));
+            }
+        }
+    }
+}
+
+fn wrap_html(open: &str, nodes: &[Inline], close: &str, mentions: &Mentions, html: &mut String) {
+    html.push_str(open);
+    inline_html(nodes, mentions, html);
+    html.push_str(close);
+}
+
+fn inline_text(nodes: &[Inline], mentions: &Mentions, text: &mut String) {
+    for node in nodes {
+        match node {
+            Inline::Text(value) | Inline::Code(value) => text.push_str(value),
+            Inline::LineBreak => text.push('\n'),
+            Inline::Bold(nodes) | Inline::Italic(nodes) => inline_text(nodes, mentions, text),
+            Inline::Spoiler(..) => text.push_str("[spoiler]"),
+            Inline::Link { label, url } => {
+                let mut label_text = String::new();
+                inline_text(label, mentions, &mut label_text);
+
+                text.push_str(&label_text);
+                if label_text != *url {
+                    text.push_str(&format!(" ({url})"));
+                }
+            },
+            Inline::Emoji { name, .. } => text.push_str(&format!(":{name}:")),
+            Inline::Mention(id) => match mentioned_username(mentions, id) {
+                Some(username) => text.push_str(&format!("@{username}")),
+                None => text.push_str(&format!("<@{id}>"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn html(content: &str) -> String {
+        to_html(content, &Mentions::default())
+    }
+
+    #[test]
+    fn escapes_raw_html() {
+        assert_eq!(html("<script>alert(1)</script>"), "<p>&lt;script&gt;alert(1)&lt;/script&gt;</p>");
+        assert_eq!(html("<img src=x onerror=\"alert('x')\">"), "<p>&lt;img src=x onerror=&quot;alert(&#39;x&#39;)&quot;&gt;</p>");
+        assert_eq!(html("**<b>&amp;</b>**"), "<p><strong>&lt;b&gt;&amp;amp;&lt;/b&gt;</strong></p>");
+        assert_eq!(html("```html\n<script>\n```"), "<pre><code class=\"language-html\">&lt;script&gt;</code></pre>");
+    }
+
+    #[test]
+    fn links_only_allowed_schemes() {
+        assert_eq!(html("[a](javascript:alert(1))"), "<p>[a](javascript:alert(1))</p>");
+        assert_eq!(html("[a](JaVaScRiPt:alert(1))"), "<p>[a](JaVaScRiPt:alert(1))</p>");
+        assert_eq!(html("[a](data:text/html,x)"), "<p>[a](data:text/html,x)</p>");
+        assert_eq!(
+            html("[a](HTTPS://example.com/\"x)"),
+            "<p><a href=\"HTTPS://example.com/&quot;x\" rel=\"nofollow noopener noreferrer\" target=\"_blank\">a</a></p>"
+        );
+    }
+
+    #[test]
+    fn checks_schemes_of_non_ascii_urls() {
+        assert_eq!(html("[a](mailtoé)"), "<p>[a](mailtoé)</p>");
+        assert_eq!(html("[a](httéééé)"), "<p>[a](httéééé)</p>");
+        assert!(html("[a](mailto:é@example.com)").contains("href=\"mailto:é@example.com\""));
+    }
+
+    #[test]
+    fn leaves_unclosed_markers_as_text() {
+        assert_eq!(html("**bold"), "<p>**bold</p>");
+        assert_eq!(html("*a *b _c ||d `e [f"), "<p>*a *b _c ||d `e [f</p>");
+        assert_eq!(html("*a **b*"), "<p><em>a **b</em></p>");
+        assert_eq!(html("*a* *b"), "<p><em>a</em> *b</p>");
+        assert_eq!(html("*a `*` b*"), "<p><em>a <code>*</code> b</em></p>");
+        assert_eq!(html("snake_case_name"), "<p>snake_case_name</p>");
+    }
+
+    #[test]
+    fn parses_many_unclosed_markers() {
+        let content = "*a _b ||c [d ".repeat(300);
+        assert!(to_text(&content, &Mentions::default()) == content);
+    }
+}
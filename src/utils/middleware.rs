@@ -16,10 +16,10 @@ pub async fn authorization_middleware(
 ) -> Result<ServiceResponse<impl MessageBody>, Error> {
     //TODO: Make this with wrap stuff in config
     if req.path().contains("/auth") {
-        return Ok(next.call(req).await?);
+        return next.call(req).await;
     };
 
-    let token = extract_header(&req.request(), AUTHORIZATION)?;
+    let token = extract_header(req.request(), AUTHORIZATION)?;
     let credentials = req.app_data::<web::Data<App>>().unwrap().database.fetch_credentials_by_token(token).await?;
 
     req.extensions_mut().insert(UserCredentials(credentials.0, credentials.1));
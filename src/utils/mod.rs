@@ -1,4 +1,5 @@
 pub mod snowflake;
 pub mod authorization;
 pub mod convectors;
-pub mod middleware;
\ No newline at end of file
+pub mod middleware;
+pub mod markdown;
@@ -1,4 +1,6 @@
 use {
+    chrono::{DateTime, Utc},
+    sqlx::{Decode, Postgres, postgres::PgValueRef},
     std::{
         process::id,
         time::SystemTime,
@@ -42,15 +44,36 @@ impl SnowflakeBuilder {
     }
 }
 
-impl Into<i64> for Snowflake {
-    fn into(self) -> i64 {
-        self.0
+impl Snowflake {
+    /// Returns when the snowflake was generated
+    pub fn created_at(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis((self.0 >> 22) + EPOCH as i64).unwrap_or_default()
+    }
+
+    /// Returns the lowest snowflake generated at the given time, useful to compare IDs with time
+    pub fn from_timestamp(time: DateTime<Utc>) -> Self {
+        Snowflake((time.timestamp_millis() - EPOCH as i64).max(0) << 22)
+    }
+}
+
+impl From<Snowflake> for i64 {
+    fn from(value: Snowflake) -> Self {
+        value.0
+    }
+}
+
+impl From<i64> for Snowflake {
+    fn from(value: i64) -> Self {
+        Snowflake(value)
     }
 }
 
-impl Into<Snowflake> for i64 {
-    fn into(self) -> Snowflake {
-        Snowflake(self)
+impl Decode<'_, Postgres> for Snowflake {
+    fn decode(
+        value: PgValueRef<'_>,
+    ) -> Result<Self, Box<dyn std::error::Error + 'static + Send + Sync>> {
+        let s: i64 = sqlx::Decode::<'_, Postgres>::decode(value)?;
+        Ok(Snowflake(s))
     }
 }
 
@@ -0,0 +1,165 @@
+mod common;
+
+use {
+    std::io,
+    actix_web::{http::StatusCode, test::TestRequest, web::Bytes},
+    futures::future::{BoxFuture, FutureExt},
+    serde_json::json,
+    sqlx::PgPool,
+    forum::storage::{Storage, content::Content},
+    common::*
+};
+
+const BOUNDARY: &str = "AttachmentBoundary";
+
+/// Build a multipart request sending the message with the files.
+fn multipart(path: &str, content: &str, files: &[(&str, &str, &[u8])]) -> TestRequest {
+    let mut body = format!(
+        "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"payload_json\"\r\nContent-Type: application/json\r\n\r\n{}\r\n",
+        json!({"content": content})
+    ).into_bytes();
+
+    for (i, (filename, content_type, content)) in files.iter().enumerate() {
+        body.extend(format!(
+            "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"files[{i}]\"; filename=\"{filename}\"\r\nContent-Type: {content_type}\r\n\r\n"
+        ).bytes());
+        body.extend_from_slice(content);
+        body.extend(b"\r\n");
+    }
+    body.extend(format!("--{BOUNDARY}--\r\n").bytes());
+
+    TestRequest::post()
+        .uri(&format!("/api/v1{path}"))
+        .insert_header(("Content-Type", format!("multipart/form-data; boundary={BOUNDARY}")))
+        .set_payload(body)
+}
+
+#[sqlx::test]
+async fn attachments_are_downloaded_by_ranges(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+
+    let files: [(&str, &str, &[u8]); 2] = [("notes.txt", "text/plain", b"Hello, attachments!"), ("empty.txt", "text/plain", b"")];
+    let (status, message) = call(&app, &token, multipart(&format!("/threads/{thread_id}/messages"), "files", &files)).await;
+    assert_eq!(status, StatusCode::OK, "{message}");
+
+    let attachments = message["attachments"].as_array().unwrap();
+    assert_eq!(attachments.len(), 2);
+    assert_eq!(attachments[0]["size"], 19);
+    assert_eq!(attachments[1]["size"], 0);
+
+    let download = |attachment: usize, range: Option<&'static str>| {
+        let path = format!("/api/v1/threads/{thread_id}/messages/{}/attachments/{}", message["id"].as_str().unwrap(), attachments[attachment]["id"].as_str().unwrap());
+        let request = TestRequest::get().uri(&path)
+            .insert_header(("User-Agent", "test"))
+            .insert_header(("Authorization", token.as_str()))
+            .peer_addr("127.0.0.1:8080".parse().unwrap());
+        match range {
+            Some(range) => request.insert_header(("Range", range)),
+            None => request
+        }.to_request()
+    };
+
+    let response = actix_web::test::call_service(&app, download(0, None)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(actix_web::test::read_body(response).await, "Hello, attachments!");
+
+    let response = actix_web::test::call_service(&app, download(0, Some("bytes=7-17"))).await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.headers().get("content-range").unwrap(), "bytes 7-17/19");
+    assert_eq!(actix_web::test::read_body(response).await, "attachments");
+
+    let response = actix_web::test::call_service(&app, download(0, Some("bytes=19-"))).await;
+    assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(response.headers().get("content-range").unwrap(), "bytes */19");
+
+    let response = actix_web::test::call_service(&app, download(1, Some("bytes=0-"))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(actix_web::test::read_body(response).await, "");
+}
+
+/// Storage failing to store anything
+struct BrokenStorage;
+
+impl Storage for BrokenStorage {
+    fn put<'a>(&'a self, _key: &'a str, _content_type: &'a str, _content: &'a Content) -> BoxFuture<'a, io::Result<()>> {
+        async { Err(io::Error::other("disk is full")) }.boxed()
+    }
+
+    fn get<'a>(&'a self, _key: &'a str, _range: Option<(u64, u64)>) -> BoxFuture<'a, io::Result<Bytes>> {
+        async { Err(io::ErrorKind::NotFound.into()) }.boxed()
+    }
+
+    fn delete<'a>(&'a self, _key: &'a str) -> BoxFuture<'a, io::Result<()>> {
+        async { Ok(()) }.boxed()
+    }
+}
+
+#[sqlx::test]
+async fn message_is_not_sent_when_files_are_not_stored(pool: PgPool) {
+    let data = app_data_with_storage(pool.clone(), Box::new(BrokenStorage));
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+
+    let files: [(&str, &str, &[u8]); 1] = [("notes.txt", "text/plain", b"Hello")];
+    let (status, body) = call(&app, &token, multipart(&format!("/threads/{thread_id}/messages"), "files", &files)).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR, "{body}");
+    assert_eq!(body["code"], 50000);
+
+    let (status, body) = call(&app, &token, get(&format!("/threads/{thread_id}/messages"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body.as_array().unwrap().len(), 1);
+}
+
+#[sqlx::test]
+async fn access_is_checked_before_files_are_read(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (user_token, _) = register(&app, "user").await;
+    let (category_id, private_id) = create_thread(&app, &token, json!({"type": "private"})).await;
+    let announcement_id = create_thread_in(&app, &token, &category_id, json!({"type": "announcement"})).await;
+
+    // The file type isn't allowed for the user, which would be reported if the files were read first
+    let files: [(&str, &str, &[u8]); 1] = [("setup.exe", "application/x-msdownload", b"MZ")];
+    for thread_id in [private_id, announcement_id] {
+        let (status, body) = call(&app, &user_token, multipart(&format!("/threads/{thread_id}/messages"), "files", &files)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+        assert_eq!(body["code"], 40000);
+    }
+}
+
+#[sqlx::test]
+async fn files_are_removed_with_their_category_or_author(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (user_token, user_id) = register(&app, "user").await;
+    let (category_id, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let (_, other_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+
+    let files: [(&str, &str, &[u8]); 1] = [("notes.txt", "text/plain", b"Hello")];
+    let mut keys = Vec::new();
+    for (token, thread_id) in [(&token, &thread_id), (&user_token, &other_id), (&token, &other_id)] {
+        let (status, message) = call(&app, token, multipart(&format!("/threads/{thread_id}/messages"), "files", &files)).await;
+        assert_eq!(status, StatusCode::OK, "{message}");
+        keys.push(format!("attachments/{}/{}", message["id"].as_str().unwrap(), message["attachments"][0]["id"].as_str().unwrap()));
+    }
+
+    let (status, body) = call(&app, &token, delete(&format!("/categories/{category_id}"))).await;
+    assert!(status.is_success(), "{body}");
+
+    let user = data.database.fetch_user(user_id.parse::<i64>().unwrap().into()).await.unwrap();
+    let attachments = user.delete(&pool).await.unwrap();
+    assert_eq!(attachments.iter().map(|attachment| attachment.key()).collect::<Vec<_>>(), [keys[1].clone()]);
+    for attachment in attachments {
+        data.storage.delete(&attachment.key()).await.unwrap();
+    }
+
+    assert_eq!(data.storage.get(&keys[0], None).await.unwrap_err().kind(), io::ErrorKind::NotFound);
+    assert_eq!(data.storage.get(&keys[1], None).await.unwrap_err().kind(), io::ErrorKind::NotFound);
+    assert_eq!(data.storage.get(&keys[2], None).await.unwrap(), "Hello");
+}
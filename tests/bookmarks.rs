@@ -0,0 +1,96 @@
+mod common;
+
+use {
+    actix_web::http::StatusCode,
+    serde_json::json,
+    sqlx::PgPool,
+    common::*
+};
+
+#[sqlx::test]
+async fn bookmark_limit_is_validated(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register(&app, "user").await;
+
+    for limit in [0, 101] {
+        let (status, body) = call(&app, &token, get(&format!("/users/@me/bookmarks?limit={limit}"))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+        assert_eq!(body["code"], 20004);
+    }
+
+    let (status, body) = call(&app, &token, get("/users/@me/bookmarks?limit=100")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+}
+
+#[sqlx::test]
+async fn deleted_message_is_bookmarked_as_tombstone(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (user_token, _) = register(&app, "user").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let message_id = send_message(&app, &token, &thread_id, "worth keeping").await;
+
+    let payload = json!({"thread_id": thread_id, "message_id": message_id, "note": "read later"});
+    let (status, bookmark) = call(&app, &user_token, post("/users/@me/bookmarks", payload)).await;
+    assert_eq!(status, StatusCode::OK, "{bookmark}");
+
+    let (status, body) = call(&app, &token, delete(&format!("/threads/{thread_id}/messages/{message_id}"))).await;
+    assert!(status.is_success(), "{body}");
+
+    let (status, body) = call(&app, &user_token, get("/users/@me/bookmarks")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body[0]["id"], bookmark["id"]);
+    assert_eq!(body[0]["message"]["id"], message_id);
+    assert_eq!(body[0]["message"]["content"], "");
+
+    let path = format!("/users/@me/bookmarks/{}", bookmark["id"].as_str().unwrap());
+    let (status, body) = call(&app, &user_token, patch(&path, json!({"folder": "Archive"}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["folder"], "Archive");
+    assert_eq!(body["note"], "read later");
+    assert_eq!(body["thread"]["id"], thread_id);
+    assert_eq!(body["message"]["content"], "");
+
+    let (status, body) = call(&app, &token, patch(&path, json!({"folder": "Stolen"}))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+
+    let (status, body) = call(&app, &user_token, delete(&path)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT, "{body}");
+    let (status, body) = call(&app, &user_token, delete(&path)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+}
+
+#[sqlx::test]
+async fn bookmarks_are_removed_with_purged_items(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (category_id, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let other_thread_id = create_thread_in(&app, &token, &category_id, json!({"type": "public"})).await;
+    let message_id = send_message(&app, &token, &other_thread_id, "deleted soon").await;
+
+    for payload in [json!({"thread_id": thread_id}), json!({"thread_id": other_thread_id, "message_id": message_id}), json!({"thread_id": other_thread_id})] {
+        let (status, body) = call(&app, &token, post("/users/@me/bookmarks", payload)).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+    }
+
+    let (status, body) = call(&app, &token, delete(&format!("/threads/{thread_id}"))).await;
+    assert!(status.is_success(), "{body}");
+    let (status, body) = call(&app, &token, delete(&format!("/threads/{other_thread_id}/messages/{message_id}"))).await;
+    assert!(status.is_success(), "{body}");
+
+    let (status, body) = call(&app, &token, get("/users/@me/bookmarks")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body.as_array().unwrap().len(), 3, "{body}");
+
+    data.purge_deleted(chrono::Duration::zero()).await.unwrap();
+
+    let (status, body) = call(&app, &token, get("/users/@me/bookmarks")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let bookmarks = body.as_array().unwrap();
+    assert_eq!(bookmarks.len(), 1, "{body}");
+    assert_eq!(bookmarks[0]["thread_id"], other_thread_id);
+    assert!(bookmarks[0]["message_id"].is_null(), "{body}");
+}
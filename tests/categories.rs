@@ -0,0 +1,47 @@
+mod common;
+
+use {
+    actix_web::http::StatusCode,
+    serde_json::json,
+    sqlx::PgPool,
+    common::*
+};
+
+#[sqlx::test]
+async fn modify_category_switches_question_mode(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (user_token, _) = register(&app, "user").await;
+    let (category_id, _) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let path = format!("/categories/{category_id}");
+
+    let (status, _) = call(&app, &user_token, patch(&path, json!({"is_question_mode": true}))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = call(&app, &token, patch(&path, json!({"title": "Questions", "is_question_mode": true}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["title"], "Questions");
+    assert_eq!(body["question_mode"], true);
+
+    let (status, body) = call(&app, &token, get(&path)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["question_mode"], true);
+    assert_eq!(body["locked"], false);
+}
+
+#[sqlx::test]
+async fn threads_of_unknown_category_are_not_listed(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (category_id, _) = create_thread(&app, &token, json!({"type": "public"})).await;
+
+    let (status, body) = call(&app, &token, get(&format!("/categories/{category_id}/threads"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body.as_array().unwrap().len(), 1);
+
+    let (status, body) = call(&app, &token, get("/categories/1/threads")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+    assert_eq!(body["code"], 10001);
+}
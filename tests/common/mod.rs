@@ -0,0 +1,187 @@
+//! Helpers shared by the integration tests.
+//!
+//! Tests run with `#[sqlx::test]`, which creates a fresh database from `migrations` for every test
+//! using `DATABASE_URL`.
+
+#![allow(dead_code)]
+
+use {
+    std::collections::HashMap,
+    actix_http::Request,
+    actix_web::{
+        body::MessageBody,
+        dev::{Service, ServiceResponse},
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App
+    },
+    serde_json::{json, Value},
+    sqlx::PgPool,
+    tokio::sync::broadcast,
+    forum::{
+        routes,
+        App as AppData,
+        DispatchTarget,
+        models::{database::Database, gateway::GatewayEvent},
+        storage::{Storage, local::LocalStorage},
+        utils::snowflake::{EPOCH, SnowflakeBuilder}
+    }
+};
+
+/// Create the application state on the test database, files are stored in a temporary directory.
+pub fn app_data(pool: PgPool) -> web::Data<AppData> {
+    let root = std::env::temp_dir().join(format!("forum-test-{}", forum::models::new_hex_id(16)));
+
+    app_data_with_storage(pool, Box::new(LocalStorage::new(root)))
+}
+
+/// Create the application state on the test database with the storage.
+pub fn app_data_with_storage(pool: PgPool, storage: Box<dyn Storage>) -> web::Data<AppData> {
+    web::Data::new(AppData {
+        snowflake: SnowflakeBuilder {
+            epoch: EPOCH,
+            worker_id: 1,
+            increment: 0,
+        }.into(),
+        channel: broadcast::Sender::new(1024),
+        online: HashMap::new(),
+        database: Database::new(pool.clone()),
+        pool,
+        storage
+    })
+}
+
+/// Create the service with all routes, configured like the server.
+pub async fn service(data: &web::Data<AppData>) -> impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = actix_web::Error> {
+    test::init_service(
+        App::new()
+            .app_data(web::Data::clone(data))
+            .app_data(
+                web::JsonConfig::default()
+                    .error_handler(|err, _| routes::HttpError::Payload(err).into()),
+            )
+            .app_data(
+                web::PathConfig::default()
+                    .error_handler(|err, _| routes::HttpError::Path(err).into()),
+            )
+            .app_data(
+                web::QueryConfig::default()
+                    .error_handler(|err, _| routes::HttpError::Query(err).into()),
+            )
+            .configure(routes::config)
+    ).await
+}
+
+/// Send the request to the API and return the status with the JSON body, `null` if the body is empty.
+pub async fn call<S, B>(app: &S, token: &str, request: TestRequest) -> (StatusCode, Value)
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody
+{
+    let request = request
+        .insert_header(("User-Agent", "test"))
+        .insert_header(("Authorization", token))
+        .peer_addr("127.0.0.1:8080".parse().unwrap())
+        .to_request();
+    let response = test::call_service(app, request).await;
+    let status = response.status();
+    let body = test::read_body(response).await;
+
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+pub fn get(path: &str) -> TestRequest {
+    TestRequest::get().uri(&format!("/api/v1{path}"))
+}
+
+pub fn post(path: &str, payload: Value) -> TestRequest {
+    TestRequest::post().uri(&format!("/api/v1{path}")).set_json(payload)
+}
+
+pub fn patch(path: &str, payload: Value) -> TestRequest {
+    TestRequest::patch().uri(&format!("/api/v1{path}")).set_json(payload)
+}
+
+pub fn put(path: &str) -> TestRequest {
+    TestRequest::put().uri(&format!("/api/v1{path}"))
+}
+
+pub fn delete(path: &str) -> TestRequest {
+    TestRequest::delete().uri(&format!("/api/v1{path}"))
+}
+
+/// Register a user and return their token and ID.
+pub async fn register<S, B>(app: &S, username: &str) -> (String, String)
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody
+{
+    let payload = json!({"username": username, "password": "passwordpassword1", "display_name": username});
+    let (status, body) = call(app, "", post("/auth/register", payload)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    (body["token"].as_str().unwrap().to_string(), body["user"]["id"].as_str().unwrap().to_string())
+}
+
+/// Register a user with all permissions and return their token and ID.
+pub async fn register_admin<S, B>(app: &S, pool: &PgPool, username: &str) -> (String, String)
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody
+{
+    let (token, id) = register(app, username).await;
+    sqlx::query("UPDATE users SET permissions = $1, flags = 2 WHERE id = $2")
+        .bind(i64::MAX)
+        .bind(id.parse::<i64>().unwrap())
+        .execute(pool).await
+        .unwrap();
+
+    (token, id)
+}
+
+/// Create a category and a thread in it as the given user, return their IDs.
+pub async fn create_thread<S, B>(app: &S, token: &str, payload: Value) -> (String, String)
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody
+{
+    let category = json!({"title": "General", "description": "General discussion here", "is_locked": false});
+    let (status, body) = call(app, token, post("/categories", category)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let category_id = body["id"].as_str().unwrap().to_string();
+
+    let thread_id = create_thread_in(app, token, &category_id, payload).await;
+    (category_id, thread_id)
+}
+
+/// Create a thread in the category as the given user and return its ID.
+pub async fn create_thread_in<S, B>(app: &S, token: &str, category_id: &str, mut payload: Value) -> String
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody
+{
+    let defaults = json!({"title": "Hello there", "content": "first post", "is_nsfw": false, "tags": []});
+    for (key, value) in defaults.as_object().unwrap() {
+        payload.as_object_mut().unwrap().entry(key).or_insert(value.clone());
+    }
+
+    let (status, body) = call(app, token, post(&format!("/categories/{category_id}/threads"), payload)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    body["id"].as_str().unwrap().to_string()
+}
+
+/// Send a message to the thread and return its ID.
+pub async fn send_message<S, B>(app: &S, token: &str, thread_id: &str, content: &str) -> String
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody
+{
+    let (status, body) = call(app, token, post(&format!("/threads/{thread_id}/messages"), json!({"content": content}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    body["id"].as_str().unwrap().to_string()
+}
+
+/// Take the events dispatched since the receiver subscribed to the channel.
+pub fn dispatched(events: &mut broadcast::Receiver<(DispatchTarget, GatewayEvent)>) -> Vec<(DispatchTarget, GatewayEvent)> {
+    std::iter::from_fn(|| events.try_recv().ok()).collect()
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use {
+    actix_web::http::StatusCode,
+    serde_json::json,
+    sqlx::PgPool,
+    common::*
+};
+
+/// 1x1 transparent PNG image.
+const PNG: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+
+#[sqlx::test]
+async fn emojis_are_managed_by_moderators(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (user_token, _) = register(&app, "user").await;
+    let payload = json!({"name": "party", "image": format!("data:image/png;base64,{PNG}")});
+
+    let (status, body) = call(&app, &user_token, post("/emojis", payload.clone())).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+    assert_eq!(body["code"], 40000);
+
+    let (status, body) = call(&app, &token, post("/emojis", json!({"name": "party", "image": format!("data:image/gif;base64,{PNG}")}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["code"], 20011);
+
+    let (status, emoji) = call(&app, &token, post("/emojis", payload.clone())).await;
+    assert_eq!(status, StatusCode::OK, "{emoji}");
+    assert_eq!(emoji["name"], "party");
+    assert_eq!(emoji["content_type"], "image/png");
+    let path = format!("/emojis/{}", emoji["id"].as_str().unwrap());
+
+    let (status, body) = call(&app, &token, post("/emojis", payload)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["code"], 20011);
+
+    let (status, body) = call(&app, &user_token, delete(&path)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+
+    let (status, body) = call(&app, &token, delete(&path)).await;
+    assert!(status.is_success(), "{body}");
+
+    let (status, body) = call(&app, &token, get(&path)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+    assert_eq!(body["code"], 10006);
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use {
+    std::collections::HashSet,
+    actix_web::http::StatusCode,
+    serde_json::json,
+    sqlx::PgPool,
+    forum::{DispatchTarget, models::gateway::GatewayEvent},
+    common::*
+};
+
+/// Returns IDs of users the events mention with the message.
+fn mentioned(events: Vec<(DispatchTarget, GatewayEvent)>, message_id: &str) -> HashSet<String> {
+    events.into_iter()
+        .filter_map(|(target, event)| match (target, event) {
+            (DispatchTarget::User(user_id), GatewayEvent::MentionCreate(message)) if message.id.0.to_string() == message_id => Some(user_id.0.to_string()),
+            _ => None
+        })
+        .collect()
+}
+
+#[sqlx::test]
+async fn mentions_are_resolved_by_id_and_username(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (sender_token, sender_id) = register(&app, "sender").await;
+    let (alice_token, alice_id) = register(&app, "alice").await;
+    let (_, bob_id) = register(&app, "bob").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+
+    let mut events = data.channel.subscribe();
+    let content = format!("hi <@{alice_id}> and @bob, not `@admin` nor myself <@{sender_id}>");
+    let (status, message) = call(&app, &sender_token, post(&format!("/threads/{thread_id}/messages"), json!({"content": content}))).await;
+    assert_eq!(status, StatusCode::OK, "{message}");
+
+    let ids = message["mentions"].as_array().unwrap().iter()
+        .map(|user| user["id"].as_str().unwrap().to_string())
+        .collect::<HashSet<_>>();
+    assert_eq!(ids, HashSet::from([alice_id.clone(), bob_id.clone(), sender_id]));
+
+    let message_id = message["id"].as_str().unwrap();
+    assert_eq!(mentioned(dispatched(&mut events), message_id), HashSet::from([alice_id, bob_id]));
+
+    let (status, body) = call(&app, &alice_token, get("/users/@me/notifications")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body[0]["type"], "mention");
+    assert_eq!(body[0]["message_id"], message_id);
+}
+
+#[sqlx::test]
+async fn mass_mentions_are_rejected(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (sender_token, _) = register(&app, "sender").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let path = format!("/threads/{thread_id}/messages");
+
+    let mut ids = vec![];
+    for i in 0..21 {
+        ids.push(register(&app, &format!("user{i}")).await.1);
+    }
+    let content = |ids: &[String]| ids.iter().map(|id| format!("<@{id}>")).collect::<Vec<_>>().join(" ");
+
+    let (status, body) = call(&app, &sender_token, post(&path, json!({"content": content(&ids)}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["code"], 20011);
+
+    for _ in 0..2 {
+        let (status, body) = call(&app, &sender_token, post(&path, json!({"content": content(&ids[..20])}))).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+    }
+
+    let (status, body) = call(&app, &sender_token, post(&path, json!({"content": content(&ids[..20])}))).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS, "{body}");
+    assert_eq!(body["code"], 40002);
+
+    let (status, body) = call(&app, &sender_token, post(&path, json!({"content": "no mentions"}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let (status, body) = call(&app, &token, post(&path, json!({"content": content(&ids[..20])}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+}
+
+#[sqlx::test]
+async fn edited_message_mentions_only_new_users(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (sender_token, _) = register(&app, "sender").await;
+    let (_, alice_id) = register(&app, "alice").await;
+    let (_, bob_id) = register(&app, "bob").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let message_id = send_message(&app, &sender_token, &thread_id, "hi @alice").await;
+
+    let mut events = data.channel.subscribe();
+    let (status, message) = call(&app, &sender_token, patch(&format!("/threads/{thread_id}/messages/{message_id}"), json!({"content": "hi @alice and @bob"}))).await;
+    assert_eq!(status, StatusCode::OK, "{message}");
+    assert_eq!(message["mentions"].as_array().unwrap().len(), 2);
+    assert_eq!(mentioned(dispatched(&mut events), &message_id), HashSet::from([bob_id]));
+
+    let (status, message) = call(&app, &sender_token, patch(&format!("/threads/{thread_id}/messages/{message_id}"), json!({"content": "hi @alice"}))).await;
+    assert_eq!(status, StatusCode::OK, "{message}");
+    let ids = message["mentions"].as_array().unwrap().iter()
+        .map(|user| user["id"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, [alice_id.as_str()]);
+}
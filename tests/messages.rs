@@ -0,0 +1,259 @@
+mod common;
+
+use {
+    actix_web::http::StatusCode,
+    serde_json::json,
+    sqlx::PgPool,
+    forum::{DispatchTarget, models::gateway::GatewayEvent},
+    common::*
+};
+
+#[sqlx::test]
+async fn edited_message_is_returned_and_dispatched(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (other_token, _) = register(&app, "other").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let message_id = send_message(&app, &token, &thread_id, "original").await;
+    let path = format!("/threads/{thread_id}/messages/{message_id}");
+
+    let (status, body) = call(&app, &other_token, patch(&path, json!({"content": "stolen"}))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+
+    let (status, body) = call(&app, &token, patch(&path, json!({"content": ""}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["code"], 20004);
+
+    let mut events = data.channel.subscribe();
+    let (status, message) = call(&app, &token, patch(&path, json!({"content": "edited"}))).await;
+    assert_eq!(status, StatusCode::OK, "{message}");
+    assert_eq!(message["content"], "edited");
+    assert!(message["updated_at"].is_string(), "{message}");
+
+    let event = std::iter::from_fn(|| events.try_recv().ok())
+        .find_map(|(_, event)| match event {
+            GatewayEvent::MessageUpdate(message) => Some(serde_json::to_value(message).unwrap()),
+            _ => None
+        })
+        .expect("MessageUpdate is not dispatched");
+    assert_eq!(event["id"], message_id);
+    assert_eq!(event["content"], "edited");
+    assert_eq!(event["updated_at"], message["updated_at"]);
+
+    let (status, body) = call(&app, &token, get(&path)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["content"], "edited");
+}
+
+#[sqlx::test]
+async fn message_of_deleted_thread_is_not_restored(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let message_id = send_message(&app, &token, &thread_id, "reply").await;
+    let restore = format!("/threads/{thread_id}/messages/{message_id}/restore");
+
+    let (status, body) = call(&app, &token, delete(&format!("/threads/{thread_id}/messages/{message_id}"))).await;
+    assert!(status.is_success(), "{body}");
+    let (status, body) = call(&app, &token, delete(&format!("/threads/{thread_id}"))).await;
+    assert!(status.is_success(), "{body}");
+
+    let (status, body) = call(&app, &token, post(&restore, json!({}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["code"], 20011);
+
+    let (status, body) = call(&app, &token, post(&format!("/threads/{thread_id}/restore"), json!({}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let (status, body) = call(&app, &token, post(&restore, json!({}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["content"], "reply");
+}
+
+#[sqlx::test]
+async fn reply_embeds_preview_and_notifies_referenced_author(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, admin_id) = register_admin(&app, &pool, "admin").await;
+    let (user_token, user_id) = register(&app, "user").await;
+    let (category_id, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let other_thread_id = create_thread_in(&app, &token, &category_id, json!({"type": "public"})).await;
+    let content = "a".repeat(150);
+    let message_id = send_message(&app, &user_token, &thread_id, &content).await;
+
+    let payload = json!({"content": "reply", "referenced_message_id": message_id});
+    let (status, body) = call(&app, &token, post(&format!("/threads/{other_thread_id}/messages"), payload.clone())).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+    assert_eq!(body["code"], 10003);
+
+    let mut events = data.channel.subscribe();
+    let (status, reply) = call(&app, &token, post(&format!("/threads/{thread_id}/messages"), payload)).await;
+    assert_eq!(status, StatusCode::OK, "{reply}");
+    assert_eq!(reply["referenced_message_id"], message_id);
+    assert_eq!(reply["referenced_message"]["id"], message_id);
+    assert_eq!(reply["referenced_message"]["author"]["id"], user_id);
+    assert_eq!(reply["referenced_message"]["content"], "a".repeat(100));
+
+    let reply_id = reply["id"].as_str().unwrap();
+    let replied = dispatched(&mut events).into_iter().any(|(target, event)| matches!(
+        (target, event),
+        (DispatchTarget::User(id), GatewayEvent::ReplyCreate(message)) if id.0.to_string() == user_id && message.id.0.to_string() == reply_id
+    ));
+    assert!(replied, "ReplyCreate is not dispatched to the referenced author");
+
+    let (status, body) = call(&app, &user_token, get("/users/@me/notifications")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body.as_array().unwrap().len(), 1, "{body}");
+    assert_eq!(body[0]["type"], "reply");
+    assert_eq!(body[0]["actor_id"], admin_id);
+    assert_eq!(body[0]["message_id"], reply_id);
+
+    let (status, body) = call(&app, &token, get(&format!("/threads/{thread_id}/messages/{reply_id}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["referenced_message"]["id"], message_id);
+}
+
+#[sqlx::test]
+async fn edits_are_kept_as_revisions_for_author_and_moderators(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (author_token, _) = register(&app, "author").await;
+    let (other_token, _) = register(&app, "other").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let message_id = send_message(&app, &author_token, &thread_id, "first").await;
+    let path = format!("/threads/{thread_id}/messages/{message_id}");
+
+    for (i, content) in ["second", "third"].into_iter().enumerate() {
+        let (status, body) = call(&app, &author_token, patch(&path, json!({"content": content}))).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["edit_count"], i + 1);
+    }
+
+    // Updates which don't change the content aren't revisions
+    sqlx::query("UPDATE messages SET flags = flags | 1 WHERE id = $1")
+        .bind(message_id.parse::<i64>().unwrap())
+        .execute(&pool).await
+        .unwrap();
+
+    let revisions = format!("{path}/revisions");
+    for token in [&author_token, &token] {
+        let (status, body) = call(&app, token, get(&revisions)).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let revisions = body.as_array().unwrap().iter()
+            .map(|revision| (revision["revision"].as_i64().unwrap(), revision["content"].as_str().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(revisions, [(1, "first"), (2, "second")]);
+    }
+
+    let (status, body) = call(&app, &other_token, get(&revisions)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+    assert_eq!(body["code"], 40000);
+
+    let (status, body) = call(&app, &other_token, get(&path)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["content"], "third");
+    assert_eq!(body["edit_count"], 2);
+}
+
+#[sqlx::test]
+async fn messages_are_fetched_around_a_message(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (user_token, _) = register(&app, "user").await;
+    let (category_id, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let other_thread_id = create_thread_in(&app, &token, &category_id, json!({"type": "public"})).await;
+    let other_message_id = send_message(&app, &token, &other_thread_id, "elsewhere").await;
+
+    let (_, thread) = call(&app, &token, get(&format!("/threads/{thread_id}"))).await;
+    let mut ids = vec![thread["original_message"]["id"].as_str().unwrap().to_string()];
+    for i in 1..10 {
+        ids.push(send_message(&app, &token, &thread_id, &format!("message {i}")).await);
+    }
+    let path = format!("/threads/{thread_id}/messages");
+    let fetch = |query: String| call(&app, &user_token, get(&format!("{path}?{query}")));
+    let window = |range: std::ops::RangeInclusive<usize>| range.rev().map(|i| ids[i].as_str()).collect::<Vec<_>>();
+    let fetched_ids = |body: &serde_json::Value| body.as_array().unwrap().iter()
+        .map(|message| message["id"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+
+    let (status, body) = fetch(format!("around={}&limit=5", ids[5])).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(fetched_ids(&body), window(3..=7));
+
+    let (_, body) = fetch(format!("around={}&limit=4", ids[1])).await;
+    assert_eq!(fetched_ids(&body), window(0..=3));
+
+    let (_, body) = fetch(format!("around={}", ids[5])).await;
+    assert_eq!(fetched_ids(&body), window(0..=9));
+
+    // Deleted messages are still listed as tombstones, purged ones are gone
+    let (status, body) = call(&app, &token, delete(&format!("{path}/{}", ids[5]))).await;
+    assert!(status.is_success(), "{body}");
+    let (status, body) = fetch(format!("around={}&limit=3", ids[5])).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(fetched_ids(&body), window(4..=6));
+    assert_eq!(body[1]["content"], "");
+
+    sqlx::query("DELETE FROM messages WHERE id = $1")
+        .bind(ids[8].parse::<i64>().unwrap())
+        .execute(&pool).await
+        .unwrap();
+    for around in [ids[8].as_str(), other_message_id.as_str(), "1"] {
+        let (status, body) = fetch(format!("around={around}")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+        assert_eq!(body["code"], 10003);
+    }
+
+    let (status, body) = fetch(format!("around={}&before={}", ids[5], ids[9])).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["code"], 20011);
+
+    for query in [format!("around={}&limit=0", ids[5]), format!("around={}&limit=101", ids[5]), "limit=101".to_string()] {
+        let (status, body) = fetch(query).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+        assert_eq!(body["code"], 20004);
+    }
+}
+
+#[sqlx::test]
+async fn purged_messages_are_dispatched_at_once(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (user_token, user_id) = register(&app, "user").await;
+    let (category_id, public_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let private_id = create_thread_in(&app, &token, &category_id, json!({"type": "private"})).await;
+    let own_id = create_thread_in(&app, &user_token, &category_id, json!({"type": "public"})).await;
+
+    let (status, body) = call(&app, &token, put(&format!("/threads/{private_id}/members/{user_id}"))).await;
+    assert!(status.is_success(), "{body}");
+    let public_message = send_message(&app, &user_token, &public_id, "spam").await;
+    let private_message = send_message(&app, &user_token, &private_id, "spam").await;
+    let own_message = send_message(&app, &user_token, &own_id, "spam").await;
+
+    let mut events = data.channel.subscribe();
+    let (status, body) = call(&app, &token, post(&format!("/users/{user_id}/messages/purge"), json!({"hours": 1}))).await;
+    assert_eq!(status, StatusCode::NO_CONTENT, "{body}");
+
+    let deleted = dispatched(&mut events).into_iter()
+        .filter_map(|(target, event)| match event {
+            GatewayEvent::MessageDeleteBulk {messages} => Some((matches!(target, DispatchTarget::Global), serde_json::to_value(messages).unwrap())),
+            _ => None
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(deleted.len(), 2);
+    assert!(deleted.contains(&(false, json!([{"thread_id": private_id, "message_ids": [private_message]}]))));
+    // The original message of the user's thread is undeleteable
+    assert!(deleted.contains(&(true, json!([
+        {"thread_id": public_id, "message_ids": [public_message]},
+        {"thread_id": own_id, "message_ids": [own_message]}
+    ]))));
+
+    let (status, body) = call(&app, &user_token, get(&format!("/threads/{own_id}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert!(body["original_message"]["deleted_at"].is_null(), "{body}");
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use {
+    actix_http::Request,
+    actix_web::{
+        body::MessageBody,
+        dev::{Service, ServiceResponse},
+        http::StatusCode
+    },
+    serde_json::{json, Value},
+    sqlx::PgPool,
+    common::*
+};
+
+/// Fetch notifications of the user from the newest one.
+async fn notifications<S, B>(app: &S, token: &str) -> Vec<Value>
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody
+{
+    let (status, body) = call(app, token, get("/users/@me/notifications")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    body.as_array().unwrap().clone()
+}
+
+#[sqlx::test]
+async fn notification_limit_is_validated(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register(&app, "user").await;
+
+    for limit in [0, 101] {
+        let (status, body) = call(&app, &token, get(&format!("/users/@me/notifications?limit={limit}"))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+        assert_eq!(body["code"], 20004);
+    }
+
+    let (status, body) = call(&app, &token, get("/users/@me/notifications?limit=100")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+}
+
+#[sqlx::test]
+async fn bulk_deletion_notifies_each_author_once(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (first_token, _) = register(&app, "first").await;
+    let (second_token, _) = register(&app, "second").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+
+    let messages = vec![
+        send_message(&app, &first_token, &thread_id, "spam").await,
+        send_message(&app, &first_token, &thread_id, "more spam").await,
+        send_message(&app, &second_token, &thread_id, "spam").await,
+        send_message(&app, &token, &thread_id, "own message").await
+    ];
+
+    let (status, body) = call(&app, &token, post(&format!("/threads/{thread_id}/messages/bulk-delete"), json!({"messages": messages}))).await;
+    assert_eq!(status, StatusCode::NO_CONTENT, "{body}");
+
+    for token in [&first_token, &second_token] {
+        let notifications = notifications(&app, token).await;
+        assert_eq!(notifications.len(), 1, "{notifications:?}");
+        assert_eq!(notifications[0]["type"], "moderation_action");
+        assert_eq!(notifications[0]["thread_id"], thread_id);
+    }
+    assert!(notifications(&app, &token).await.is_empty());
+}
+
+#[sqlx::test]
+async fn reaction_is_notified_once(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (user_token, _) = register(&app, "user").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let message_id = send_message(&app, &user_token, &thread_id, "hello").await;
+    let path = format!("/threads/{thread_id}/messages/{message_id}/reactions/%F0%9F%91%8D/@me");
+
+    for _ in 0..2 {
+        let (status, _) = call(&app, &token, put(&path)).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = call(&app, &token, delete(&path)).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    let (status, _) = call(&app, &token, put(&format!("/threads/{thread_id}/messages/{message_id}/reactions/%F0%9F%8E%89/@me"))).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let notifications = notifications(&app, &user_token).await;
+    assert_eq!(notifications.len(), 2, "{notifications:?}");
+    assert_eq!(notifications[0]["emoji"]["name"], "🎉");
+    assert_eq!(notifications[1]["emoji"]["name"], "👍");
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use {
+    actix_web::http::StatusCode,
+    serde_json::{json, Value},
+    sqlx::PgPool,
+    common::*
+};
+
+fn vote_counts(poll: &Value) -> Vec<i64> {
+    poll["options"].as_array().unwrap().iter()
+        .map(|option| option["vote_count"].as_i64().unwrap())
+        .collect()
+}
+
+#[sqlx::test]
+async fn single_choice_poll_keeps_one_vote_per_user(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let poll = json!({"question": "Which one?", "options": ["First", "Second", "Third"]});
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public", "poll": poll})).await;
+
+    let (status, body) = call(&app, &token, get(&format!("/threads/{thread_id}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let options = body["poll"]["options"].as_array().unwrap().iter()
+        .map(|option| option["id"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    let path = format!("/threads/{thread_id}/poll/votes");
+
+    let votes = options.iter()
+        .map(|id| call(&app, &token, put(&path).set_json(json!({"option_ids": [id]}))));
+    for (status, body) in futures::future::join_all(votes).await {
+        assert_eq!(status, StatusCode::OK, "{body}");
+    }
+
+    let count = sqlx::query_scalar!("SELECT COUNT(*) FROM poll_votes WHERE thread_id = $1", thread_id.parse::<i64>().unwrap())
+        .fetch_one(&pool).await
+        .unwrap();
+    assert_eq!(count, Some(1));
+
+    let (status, body) = call(&app, &token, put(&path).set_json(json!({"option_ids": [options[1]]}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(vote_counts(&body), [0, 1, 0]);
+
+    let (status, body) = call(&app, &token, put(&path).set_json(json!({"option_ids": [options[0], options[1]]}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+
+    let (status, body) = call(&app, &token, delete(&path)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(vote_counts(&body), [0, 0, 0]);
+}
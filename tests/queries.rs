@@ -0,0 +1,96 @@
+//! Pages of threads and messages are fetched with a fixed number of queries, whatever their size.
+
+mod common;
+
+use {
+    std::{cell::Cell, future::Future},
+    actix_web::http::StatusCode,
+    log::{Log, Metadata, Record, LevelFilter},
+    serde_json::json,
+    sqlx::PgPool,
+    common::*
+};
+
+thread_local! {
+    static QUERIES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Counts queries executed by sqlx on the current thread.
+struct QueryCounter;
+
+impl Log for QueryCounter {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target() == "sqlx::query"
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            QUERIES.with(|queries| queries.set(queries.get() + 1));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static COUNTER: QueryCounter = QueryCounter;
+
+/// Run the future and return the number of queries it executed along with its output.
+async fn count_queries<F: Future>(future: F) -> (usize, F::Output) {
+    if log::set_logger(&COUNTER).is_ok() {
+        log::set_max_level(LevelFilter::Trace);
+    }
+
+    QUERIES.with(|queries| queries.set(0));
+    let output = future.await;
+    (QUERIES.with(Cell::get), output)
+}
+
+#[sqlx::test]
+async fn thread_page_query_count_is_bounded(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (category_id, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let path = format!("/categories/{category_id}/threads");
+
+    let (single, (status, body)) = count_queries(call(&app, &token, get(&path))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body.as_array().unwrap().len(), 1);
+
+    for i in 0..10 {
+        let id = create_thread_in(&app, &token, &category_id, json!({"type": "public", "title": format!("Thread {i}")})).await;
+        send_message(&app, &token, &id, "reply").await;
+    }
+    send_message(&app, &token, &thread_id, "reply").await;
+
+    let (many, (status, body)) = count_queries(call(&app, &token, get(&path))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body.as_array().unwrap().len(), 11);
+    assert!(single > 0, "queries are not logged");
+    assert_eq!(single, many);
+}
+
+#[sqlx::test]
+async fn message_page_query_count_is_bounded(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let path = format!("/threads/{thread_id}/messages");
+
+    let (single, (status, body)) = count_queries(call(&app, &token, get(&path))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body.as_array().unwrap().len(), 1);
+
+    for i in 0..20 {
+        let id = send_message(&app, &token, &thread_id, &format!("message {i}")).await;
+        let (status, _) = call(&app, &token, put(&format!("/threads/{thread_id}/messages/{id}/reactions/%F0%9F%91%8D/@me"))).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    let (many, (status, body)) = count_queries(call(&app, &token, get(&path))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body.as_array().unwrap().len(), 21);
+    assert!(single > 0, "queries are not logged");
+    assert_eq!(single, many);
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use {
+    actix_web::http::StatusCode,
+    serde_json::json,
+    sqlx::PgPool,
+    common::*
+};
+
+/// Returns the percent encoded emoji `offset` positions after 😀.
+fn emoji(offset: u32) -> String {
+    char::from_u32(0x1F600 + offset).unwrap().to_string().bytes()
+        .map(|byte| format!("%{byte:02X}"))
+        .collect()
+}
+
+#[sqlx::test]
+async fn concurrent_reactions_keep_distinct_reaction_limit(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let message_id = send_message(&app, &token, &thread_id, "react to me").await;
+    let path = format!("/threads/{thread_id}/messages/{message_id}/reactions");
+
+    let reactions = (0..25)
+        .map(|offset| call(&app, &token, put(&format!("{path}/{}/@me", emoji(offset)))));
+    let statuses = futures::future::join_all(reactions).await.into_iter()
+        .map(|(status, _)| status)
+        .collect::<Vec<_>>();
+    assert_eq!(statuses.iter().filter(|status| **status == StatusCode::NO_CONTENT).count(), 20);
+    assert_eq!(statuses.iter().filter(|status| **status == StatusCode::BAD_REQUEST).count(), 5);
+
+    let (status, body) = call(&app, &token, get(&format!("/threads/{thread_id}/messages/{message_id}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["reactions"].as_array().unwrap().len(), 20);
+}
+
+#[sqlx::test]
+async fn reaction_with_unknown_custom_emoji_is_rejected(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let message_id = send_message(&app, &token, &thread_id, "react to me").await;
+
+    let (status, body) = call(&app, &token, put(&format!("/threads/{thread_id}/messages/{message_id}/reactions/missing:123/@me"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+    assert_eq!(body["code"], 10006);
+}
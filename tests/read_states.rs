@@ -0,0 +1,58 @@
+mod common;
+
+use {
+    actix_web::http::StatusCode,
+    serde_json::json,
+    sqlx::PgPool,
+    forum::{DispatchTarget, models::gateway::GatewayEvent},
+    common::*
+};
+
+#[sqlx::test]
+async fn acked_threads_count_unread_messages_and_mentions(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (user_token, user_id) = register(&app, "user").await;
+    let (category_id, first_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let second_id = create_thread_in(&app, &token, &category_id, json!({"type": "public"})).await;
+
+    for thread_id in [&first_id, &second_id] {
+        let (status, body) = call(&app, &user_token, post(&format!("/threads/{thread_id}/ack"), json!({}))).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+    }
+
+    let mention_id = send_message(&app, &token, &first_id, &format!("hey <@{user_id}>")).await;
+    send_message(&app, &token, &first_id, "anyone?").await;
+    send_message(&app, &user_token, &second_id, "own messages are read").await;
+    send_message(&app, &token, &second_id, "news").await;
+
+    let (status, body) = call(&app, &user_token, get("/users/@me/unread")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["categories"], json!([{"category_id": category_id, "unread_count": 3, "mention_count": 1}]));
+    assert_eq!(body["threads"], json!([
+        {"thread_id": first_id, "category_id": category_id, "unread_count": 2, "mention_count": 1},
+        {"thread_id": second_id, "category_id": category_id, "unread_count": 1, "mention_count": 0}
+    ]));
+
+    let mut events = data.channel.subscribe();
+    let (status, body) = call(&app, &user_token, post(&format!("/threads/{first_id}/ack"), json!({"message_id": mention_id}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["last_read_message_id"], mention_id);
+
+    let synced = dispatched(&mut events).into_iter().any(|(target, event)| matches!(
+        (target, event),
+        (DispatchTarget::User(id), GatewayEvent::ReadStateUpdate(state)) if id.0.to_string() == user_id && state.last_read_message_id.0.to_string() == mention_id
+    ));
+    assert!(synced, "ReadStateUpdate is not dispatched to the user");
+
+    let (_, body) = call(&app, &user_token, get("/users/@me/unread")).await;
+    assert_eq!(body["categories"], json!([{"category_id": category_id, "unread_count": 2, "mention_count": 0}]));
+    assert_eq!(body["threads"][0]["unread_count"], 1);
+
+    let (status, body) = call(&app, &user_token, post(&format!("/threads/{first_id}/ack"), json!({}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let (_, body) = call(&app, &user_token, get("/users/@me/unread")).await;
+    assert_eq!(body["threads"], json!([{"thread_id": second_id, "category_id": category_id, "unread_count": 1, "mention_count": 0}]));
+}
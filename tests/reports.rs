@@ -0,0 +1,104 @@
+mod common;
+
+use {
+    actix_web::http::StatusCode,
+    serde_json::{json, Value},
+    sqlx::PgPool,
+    forum::{DispatchTarget, models::gateway::GatewayEvent},
+    common::*
+};
+
+#[sqlx::test]
+async fn message_is_reported_once_per_reporter(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (author_token, _) = register(&app, "author").await;
+    let (reporter_token, reporter_id) = register(&app, "reporter").await;
+    let (other_token, _) = register(&app, "other").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let message_id = send_message(&app, &author_token, &thread_id, "spam").await;
+    let path = format!("/threads/{thread_id}/messages/{message_id}/report");
+
+    let mut events = data.channel.subscribe();
+    let (status, report) = call(&app, &reporter_token, post(&path, json!({"reason": "spam"}))).await;
+    assert_eq!(status, StatusCode::OK, "{report}");
+    assert_eq!(report["reporter_id"], reporter_id);
+    assert_eq!(report["status"], "open");
+
+    let targets = dispatched(&mut events).into_iter()
+        .filter_map(|(target, event)| matches!(event, GatewayEvent::ReportCreate(_)).then_some(target))
+        .collect::<Vec<_>>();
+    assert_eq!(targets.len(), 1);
+    assert!(matches!(targets[0], DispatchTarget::Moderators));
+
+    let (status, body) = call(&app, &reporter_token, post(&path, json!({"reason": "hate"}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["code"], 20011);
+
+    let (status, body) = call(&app, &author_token, post(&path, json!({"reason": "other"}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+
+    let (status, body) = call(&app, &other_token, post(&path, json!({"reason": "harassment", "details": "rude"}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let (status, body) = call(&app, &reporter_token, get("/reports")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+
+    let (status, body) = call(&app, &token, get("/reports")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body.as_array().unwrap().len(), 2);
+}
+
+#[sqlx::test]
+async fn reports_are_assigned_and_resolved_by_moderators(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, admin_id) = register_admin(&app, &pool, "admin").await;
+    let (author_token, author_id) = register(&app, "author").await;
+    let (reporter_token, _) = register(&app, "reporter").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let message_id = send_message(&app, &author_token, &thread_id, "spam").await;
+
+    let (status, report) = call(&app, &reporter_token, post(&format!("/threads/{thread_id}/messages/{message_id}/report"), json!({"reason": "spam"}))).await;
+    assert_eq!(status, StatusCode::OK, "{report}");
+    let path = format!("/reports/{}", report["id"].as_str().unwrap());
+
+    let (status, body) = call(&app, &reporter_token, patch(&path, json!({"status": "dismissed"}))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+
+    let (status, body) = call(&app, &token, patch(&path, json!({"assignee_id": author_id}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["code"], 20011);
+
+    let (status, body) = call(&app, &token, patch(&path, json!({"assignee_id": admin_id}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["assignee_id"], admin_id);
+
+    let (_, body) = call(&app, &token, get(&format!("/reports?assignee_id={admin_id}"))).await;
+    assert_eq!(body.as_array().unwrap().len(), 1);
+
+    let mut events = data.channel.subscribe();
+    let (status, body) = call(&app, &token, patch(&path, json!({"status": "actioned", "resolution_note": "Deleted", "assignee_id": null}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["status"], "actioned");
+    assert_eq!(body["assignee_id"], Value::Null);
+    assert_eq!(body["resolved_by"], admin_id);
+    assert!(body["resolved_at"].is_string(), "{body}");
+
+    let targets = dispatched(&mut events).into_iter()
+        .filter_map(|(target, event)| matches!(event, GatewayEvent::ReportUpdate(_)).then_some(target))
+        .collect::<Vec<_>>();
+    assert_eq!(targets.len(), 1);
+    assert!(matches!(targets[0], DispatchTarget::Moderators));
+
+    let (_, body) = call(&app, &token, get("/reports?status=open")).await;
+    assert_eq!(body, json!([]));
+    let (_, body) = call(&app, &token, get("/reports?status=actioned")).await;
+    assert_eq!(body.as_array().unwrap().len(), 1);
+
+    let (status, body) = call(&app, &token, patch(&path, json!({"status": "open"}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["resolved_by"], Value::Null);
+    assert_eq!(body["resolved_at"], Value::Null);
+}
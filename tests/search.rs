@@ -0,0 +1,102 @@
+mod common;
+
+use {
+    actix_web::http::StatusCode,
+    serde_json::json,
+    sqlx::PgPool,
+    common::*
+};
+
+#[sqlx::test]
+async fn private_threads_are_searched_only_by_members(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (user_token, user_id) = register(&app, "user").await;
+    let (category_id, public_id) = create_thread(&app, &token, json!({"type": "public", "content": "public banana"})).await;
+    let private_id = create_thread_in(&app, &token, &category_id, json!({"type": "private", "content": "secret banana"})).await;
+
+    let search = |token: &str| {
+        let token = token.to_string();
+        let app = &app;
+        async move {
+            let (status, body) = call(app, &token, get("/search?query=banana")).await;
+            assert_eq!(status, StatusCode::OK, "{body}");
+            body.as_array().unwrap().iter()
+                .map(|result| result["message"]["thread_id"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+
+    assert_eq!(search(&token).await, [private_id.as_str(), public_id.as_str()]);
+    assert_eq!(search(&user_token).await, [public_id.as_str()]);
+
+    let (status, body) = call(&app, &token, put(&format!("/threads/{private_id}/members/{user_id}"))).await;
+    assert!(status.is_success(), "{body}");
+    assert_eq!(search(&user_token).await, [private_id.as_str(), public_id.as_str()]);
+}
+
+#[sqlx::test]
+async fn deleted_items_are_searched_only_by_moderators(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (moderator_token, moderator_id) = register(&app, "moderator").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let message_id = send_message(&app, &token, &thread_id, "deleted banana").await;
+
+    let (status, body) = call(&app, &token, delete(&format!("/threads/{thread_id}/messages/{message_id}"))).await;
+    assert!(status.is_success(), "{body}");
+
+    // Moderator of messages only, who can't see deleted threads
+    sqlx::query("UPDATE users SET permissions = permissions | 16 WHERE id = $1")
+        .bind(moderator_id.parse::<i64>().unwrap())
+        .execute(&pool).await
+        .unwrap();
+
+    let (status, body) = call(&app, &moderator_token, get("/search?query=banana&deleted=true")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+
+    let (status, body) = call(&app, &moderator_token, get("/search?query=banana")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert!(body.as_array().unwrap().is_empty(), "{body}");
+
+    let (status, body) = call(&app, &token, get("/search?query=banana&deleted=true")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body[0]["message"]["id"], message_id);
+}
+
+#[sqlx::test]
+async fn highlight_markers_are_stripped_from_content(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public", "title": "\u{E000}fake\u{E001} banana"})).await;
+    send_message(&app, &token, &thread_id, "\u{E000}fake\u{E001} banana").await;
+
+    let (status, body) = call(&app, &token, get("/search?query=banana")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let results = body.as_array().unwrap();
+    assert_eq!(results.len(), 2, "{body}");
+    assert_eq!(results[0]["highlight"], "fake <mark>banana</mark>");
+    assert_eq!(results[1]["title_highlight"], "fake <mark>banana</mark>");
+}
+
+#[sqlx::test]
+async fn search_limit_is_bounded(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    create_thread(&app, &token, json!({"type": "public", "content": "banana"})).await;
+
+    for limit in [0, 101] {
+        let (status, body) = call(&app, &token, get(&format!("/search?query=banana&limit={limit}"))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+        assert_eq!(body["code"], 20004);
+    }
+
+    let (status, body) = call(&app, &token, get("/search?query=banana&limit=1")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body.as_array().unwrap().len(), 1);
+}
@@ -0,0 +1,117 @@
+//! Both storages are checked against the same expectations, S3 against an in-process server mocking the S3 API.
+
+use {
+    std::{io, collections::HashMap, sync::Mutex},
+    actix_web::{web, App, HttpRequest, HttpResponse, HttpServer, http::Method, web::Bytes},
+    sha2::{Digest, Sha256},
+    forum::storage::{Storage, content::Content, local::LocalStorage, s3::S3Storage}
+};
+
+async fn check_storage(storage: &dyn Storage) {
+    let content = Content::from_bytes(b"Hello, storage!").await.unwrap();
+    storage.put("attachments/1/2", "text/plain", &content).await.unwrap();
+
+    assert_eq!(storage.get("attachments/1/2", None).await.unwrap(), "Hello, storage!");
+    assert_eq!(storage.get("attachments/1/2", Some((7, 13))).await.unwrap(), "storage");
+    assert_eq!(storage.get("attachments/1/2", Some((7, 100))).await.unwrap(), "storage!");
+
+    let content = Content::from_bytes(b"Replaced").await.unwrap();
+    storage.put("attachments/1/2", "text/plain", &content).await.unwrap();
+    assert_eq!(storage.get("attachments/1/2", None).await.unwrap(), "Replaced");
+
+    let content = Content::from_bytes(b"").await.unwrap();
+    storage.put("attachments/1/3", "text/plain", &content).await.unwrap();
+    assert_eq!(storage.get("attachments/1/3", None).await.unwrap(), "");
+
+    storage.delete("attachments/1/2").await.unwrap();
+    assert_eq!(storage.get("attachments/1/2", None).await.unwrap_err().kind(), io::ErrorKind::NotFound);
+
+    // Deleting missing content succeeds
+    storage.delete("attachments/1/2").await.unwrap();
+}
+
+#[actix_web::test]
+async fn local_storage() {
+    let root = tempfile::tempdir().unwrap();
+    check_storage(&LocalStorage::new(root.path())).await;
+
+    assert!(!root.path().join("attachments/1/2").exists());
+    assert!(root.path().join("attachments/1/3").exists());
+}
+
+#[actix_web::test]
+async fn content_is_hashed_while_written() {
+    let content = Content::from_bytes(b"abc").await.unwrap();
+
+    assert_eq!(content.size, 3);
+    assert_eq!(content.hash, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    assert_eq!(content.head, "abc");
+}
+
+/// Objects of the mocked bucket by their paths
+#[derive(Default)]
+struct Bucket(Mutex<HashMap<String, Vec<u8>>>);
+
+/// Answers like S3 would, after checking the request is signed and its body is sent with a known length.
+async fn mock_s3(request: HttpRequest, body: Bytes, bucket: web::Data<Bucket>) -> HttpResponse {
+    let header = |name: &str| request.headers().get(name).and_then(|value| value.to_str().ok()).unwrap_or_default();
+
+    let authorization = header("authorization");
+    let payload_hash = format!("{:x}", Sha256::digest(&body));
+    if !authorization.starts_with("AWS4-HMAC-SHA256 Credential=access/")
+        || !authorization.contains("/us-east-1/s3/aws4_request")
+        || header("x-amz-date").len() != 16
+        || header("x-amz-content-sha256") != payload_hash
+        || request.headers().contains_key("transfer-encoding")
+        || (request.headers().contains_key("range") && !authorization.contains("SignedHeaders=host;range;")) {
+        return HttpResponse::Forbidden().finish()
+    }
+
+    let path = request.path().to_string();
+    let mut objects = bucket.0.lock().unwrap();
+
+    match *request.method() {
+        Method::PUT if header("content-length") == body.len().to_string() => {
+            objects.insert(path, body.to_vec());
+            HttpResponse::Ok().finish()
+        },
+        Method::GET => {
+            let Some(object) = objects.get(&path) else {
+                return HttpResponse::NotFound().finish()
+            };
+
+            match header("range").strip_prefix("bytes=").and_then(|range| range.split_once('-')) {
+                Some((start, end)) => {
+                    let start = start.parse::<usize>().unwrap();
+                    let end = end.parse::<usize>().unwrap().min(object.len() - 1);
+                    HttpResponse::PartialContent().body(object[start..=end].to_vec())
+                },
+                None => HttpResponse::Ok().body(object.clone())
+            }
+        },
+        Method::DELETE => {
+            objects.remove(&path);
+            HttpResponse::NoContent().finish()
+        },
+        _ => HttpResponse::BadRequest().finish()
+    }
+}
+
+#[actix_web::test]
+async fn s3_storage() {
+    let bucket = web::Data::new(Bucket::default());
+    let data = web::Data::clone(&bucket);
+    let server = HttpServer::new(move || App::new().app_data(web::Data::clone(&data)).default_service(web::to(mock_s3)))
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+    let address = server.addrs()[0];
+    actix_web::rt::spawn(server.run());
+
+    let storage = S3Storage::new(&format!("http://{}", address), "bucket", "us-east-1", "access", "secret");
+    check_storage(&storage).await;
+
+    let objects = bucket.0.lock().unwrap();
+    assert!(objects.contains_key("/bucket/attachments/1/3"));
+    assert!(!objects.contains_key("/bucket/attachments/1/2"));
+}
@@ -0,0 +1,301 @@
+mod common;
+
+use {
+    actix_http::Request,
+    actix_web::{
+        body::MessageBody,
+        dev::{Service, ServiceResponse},
+        http::StatusCode
+    },
+    serde_json::json,
+    sqlx::PgPool,
+    common::*
+};
+
+#[sqlx::test]
+async fn private_thread_merges_only_with_private_thread(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (category_id, private_id) = create_thread(&app, &token, json!({"type": "private"})).await;
+    let public_id = create_thread_in(&app, &token, &category_id, json!({"type": "public"})).await;
+    let other_private_id = create_thread_in(&app, &token, &category_id, json!({"type": "private"})).await;
+
+    let (status, body) = call(&app, &token, post(&format!("/threads/{private_id}/merge"), json!({"thread_id": public_id}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["code"], 20011);
+
+    let (status, body) = call(&app, &token, post(&format!("/threads/{public_id}/merge"), json!({"thread_id": private_id}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+
+    let (status, body) = call(&app, &token, post(&format!("/threads/{private_id}/merge"), json!({"thread_id": other_private_id}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["id"], other_private_id);
+}
+
+#[sqlx::test]
+async fn account_age_requirement_is_bounded(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (user_token, _) = register(&app, "user").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let path = format!("/threads/{thread_id}/requirements");
+
+    let (status, body) = call(&app, &token, put(&path).set_json(json!({"min_account_age": i64::MAX}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["code"], 20004);
+
+    let (status, body) = call(&app, &token, put(&path).set_json(json!({"min_account_age": 10 * 365 * 86400}))).await;
+    assert!(status.is_success(), "{body}");
+
+    let (status, body) = call(&app, &user_token, post(&format!("/threads/{thread_id}/messages"), json!({"content": "hi"}))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+    assert!(body["description"].as_str().unwrap().contains("3650 days"), "{body}");
+}
+
+#[sqlx::test]
+async fn kept_moderated_tags_count_towards_tag_limit(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (user_token, _) = register(&app, "user").await;
+    let (category_id, _) = create_thread(&app, &token, json!({"type": "public"})).await;
+
+    let mut tags = vec![];
+    for i in 0..6 {
+        let payload = json!({"name": format!("tag {i}"), "moderated": i == 0});
+        let (status, body) = call(&app, &token, post(&format!("/categories/{category_id}/tags"), payload)).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        tags.push(body["id"].as_str().unwrap().to_string());
+    }
+
+    let thread_id = create_thread_in(&app, &user_token, &category_id, json!({"type": "public"})).await;
+    let path = format!("/threads/{thread_id}/tags");
+    let (status, body) = call(&app, &token, put(&path).set_json(json!({"tags": [tags[0]]}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let (status, body) = call(&app, &user_token, put(&path).set_json(json!({"tags": tags[1..]}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["code"], 20011);
+
+    let (status, body) = call(&app, &user_token, put(&path).set_json(json!({"tags": tags[1..5]}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["tags"].as_array().unwrap().len(), 5);
+}
+
+/// List threads of the category and return their IDs with cursors.
+async fn list_threads<S, B>(app: &S, token: &str, category_id: &str, query: &str) -> Vec<(String, String)>
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody
+{
+    let (status, body) = call(app, token, get(&format!("/categories/{category_id}/threads?{query}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    body.as_array().unwrap().iter()
+        .map(|thread| (thread["id"].as_str().unwrap().to_string(), thread["cursor"].as_str().unwrap().to_string()))
+        .collect()
+}
+
+fn ids(threads: &[(String, String)]) -> Vec<&str> {
+    threads.iter().map(|(id, _)| id.as_str()).collect()
+}
+
+#[sqlx::test]
+async fn threads_are_sorted_with_pinned_first(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (category_id, a) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let b = create_thread_in(&app, &token, &category_id, json!({"type": "public"})).await;
+    let c = create_thread_in(&app, &token, &category_id, json!({"type": "public"})).await;
+
+    send_message(&app, &token, &a, "first").await;
+    send_message(&app, &token, &a, "second").await;
+    send_message(&app, &token, &b, "first").await;
+
+    let (status, thread) = call(&app, &token, get(&format!("/threads/{c}"))).await;
+    assert_eq!(status, StatusCode::OK, "{thread}");
+    let message_id = thread["original_message"]["id"].as_str().unwrap();
+    let (status, body) = call(&app, &token, patch(&format!("/threads/{c}/messages/{message_id}"), json!({"content": "edited"}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    // Flag changes by moderators aren't activity
+    sqlx::query("UPDATE messages SET flags = flags | 1 WHERE thread_id = $1")
+        .bind(a.parse::<i64>().unwrap())
+        .execute(&pool).await
+        .unwrap();
+
+    assert_eq!(ids(&list_threads(&app, &token, &category_id, "sort=latest").await), [&b, &a, &c]);
+    assert_eq!(ids(&list_threads(&app, &token, &category_id, "sort=activity").await), [&c, &b, &a]);
+    assert_eq!(ids(&list_threads(&app, &token, &category_id, "sort=top").await), [&a, &b, &c]);
+    assert_eq!(ids(&list_threads(&app, &token, &category_id, "sort=created").await), [&c, &b, &a]);
+
+    sqlx::query("UPDATE threads SET flags = flags | 1 WHERE id = $1")
+        .bind(c.parse::<i64>().unwrap())
+        .execute(&pool).await
+        .unwrap();
+
+    assert_eq!(ids(&list_threads(&app, &token, &category_id, "sort=latest").await), [&c, &b, &a]);
+    assert_eq!(ids(&list_threads(&app, &token, &category_id, "sort=top").await), [&c, &a, &b]);
+}
+
+#[sqlx::test]
+async fn thread_pages_are_stable_while_cursor_thread_changes(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (category_id, _) = create_thread(&app, &token, json!({"type": "public"})).await;
+    for _ in 0..3 {
+        create_thread_in(&app, &token, &category_id, json!({"type": "public"})).await;
+    }
+
+    for sort in ["latest", "activity", "top", "created"] {
+        let all = list_threads(&app, &token, &category_id, &format!("sort={sort}")).await;
+        let page = list_threads(&app, &token, &category_id, &format!("sort={sort}&limit=2")).await;
+        assert_eq!(ids(&page), ids(&all[..2]), "{sort}");
+
+        let (cursor_id, cursor) = &page[1];
+        send_message(&app, &token, cursor_id, "bump").await;
+        let next = list_threads(&app, &token, &category_id, &format!("sort={sort}&limit=2&before={cursor}")).await;
+        assert_eq!(ids(&next), ids(&all[2..]), "{sort}");
+    }
+
+    let all = list_threads(&app, &token, &category_id, "sort=latest").await;
+    let (status, body) = call(&app, &token, delete(&format!("/threads/{}", all[1].0))).await;
+    assert!(status.is_success(), "{body}");
+    let next = list_threads(&app, &token, &category_id, &format!("sort=latest&before={}", all[1].1)).await;
+    assert_eq!(ids(&next), ids(&all[2..]));
+
+    let (status, body) = call(&app, &token, get(&format!("/categories/{category_id}/threads?sort=top&before={}", all[1].1))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+}
+
+#[sqlx::test]
+async fn rejected_join_request_is_not_resubmitted(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (user_token, user_id) = register(&app, "user").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let join = format!("/threads/{thread_id}/join");
+
+    let (status, body) = call(&app, &token, put(&format!("/threads/{thread_id}/requirements")).set_json(json!({"question": "Why?"}))).await;
+    assert!(status.is_success(), "{body}");
+
+    let (status, body) = call(&app, &user_token, post(&join, json!({"answer": "Because"}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["status"], "pending");
+
+    let (status, body) = call(&app, &user_token, post(&join, json!({"answer": "Because, really"}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let review = format!("/threads/{thread_id}/join-requests/{user_id}");
+    let (status, body) = call(&app, &token, patch(&review, json!({"status": "rejected"}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let (status, body) = call(&app, &user_token, post(&join, json!({"answer": "Please"}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["code"], 20011);
+
+    let (status, body) = call(&app, &token, get(&format!("/threads/{thread_id}/join-requests"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body, json!([]));
+}
+
+/// Fetch contents of the thread messages, newest first.
+async fn contents<S, B>(app: &S, token: &str, thread_id: &str) -> Vec<String>
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody
+{
+    let (status, body) = call(app, token, get(&format!("/threads/{thread_id}/messages"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    body.as_array().unwrap().iter()
+        .map(|message| message["content"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[sqlx::test]
+async fn thread_is_moved_to_another_category(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (source_id, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let (category_id, _) = create_thread(&app, &token, json!({"type": "public"})).await;
+
+    let (status, body) = call(&app, &token, post(&format!("/threads/{thread_id}/move"), json!({"category_id": category_id}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["category_id"], category_id);
+
+    assert!(!ids(&list_threads(&app, &token, &source_id, "").await).contains(&thread_id.as_str()));
+    assert!(ids(&list_threads(&app, &token, &category_id, "").await).contains(&thread_id.as_str()));
+}
+
+#[sqlx::test]
+async fn merged_thread_is_soft_deleted_and_its_members_kept(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (user_token, user_id) = register(&app, "user").await;
+    let (category_id, source_id) = create_thread(&app, &token, json!({"type": "private"})).await;
+    let destination_id = create_thread_in(&app, &token, &category_id, json!({"type": "private"})).await;
+    let other_id = create_thread_in(&app, &token, &category_id, json!({"type": "private"})).await;
+
+    let (status, body) = call(&app, &token, put(&format!("/threads/{source_id}/members/{user_id}"))).await;
+    assert!(status.is_success(), "{body}");
+    send_message(&app, &user_token, &source_id, "reply").await;
+
+    let (status, body) = call(&app, &token, post(&format!("/threads/{source_id}/merge"), json!({"thread_id": destination_id}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    assert_eq!(contents(&app, &user_token, &destination_id).await, [
+        "admin merged 2 messages from Hello there into this thread", "reply", "first post", "first post"
+    ]);
+
+    let (status, body) = call(&app, &token, post(&format!("/threads/{source_id}/merge"), json!({"thread_id": other_id}))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+    assert_eq!(body["code"], 10002);
+
+    let (status, body) = call(&app, &token, post(&format!("/threads/{source_id}/restore"), json!({}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["code"], 20011);
+
+    let deleted = sqlx::query_scalar!("SELECT deleted_at IS NOT NULL AS \"deleted!\" FROM threads WHERE id = $1", source_id.parse::<i64>().unwrap())
+        .fetch_one(&pool).await.unwrap();
+    assert!(deleted);
+}
+
+#[sqlx::test]
+async fn thread_split_leaves_notes_in_both_threads(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (_, source_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let first = send_message(&app, &token, &source_id, "first").await;
+    let deleted = send_message(&app, &token, &source_id, "deleted").await;
+    let last = send_message(&app, &token, &source_id, "last").await;
+
+    let (status, body) = call(&app, &token, delete(&format!("/threads/{source_id}/messages/{deleted}"))).await;
+    assert!(status.is_success(), "{body}");
+
+    let split = format!("/threads/{source_id}/split");
+    let (status, body) = call(&app, &token, post(&split, json!({"title": "Split", "first_message_id": deleted, "last_message_id": last}))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+    assert_eq!(body["code"], 10003);
+
+    let (status, body) = call(&app, &token, post(&split, json!({"title": "Split", "first_message_id": first, "last_message_id": last}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["original_message"]["id"], first);
+    let thread_id = body["id"].as_str().unwrap().to_string();
+
+    assert_eq!(contents(&app, &token, &thread_id).await, ["admin split 3 messages from Hello there", "last", "deleted", "first"]);
+    assert_eq!(contents(&app, &token, &source_id).await, ["admin split 3 messages into Split", "first post"]);
+
+    let (status, body) = call(&app, &token, delete(&format!("/threads/{source_id}"))).await;
+    assert!(status.is_success(), "{body}");
+    let (status, body) = call(&app, &token, post(&split, json!({"title": "Split", "first_message_id": first, "last_message_id": last}))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+    assert_eq!(body["code"], 10002);
+}
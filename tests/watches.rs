@@ -0,0 +1,74 @@
+mod common;
+
+use {
+    std::collections::HashSet,
+    actix_web::http::StatusCode,
+    serde_json::json,
+    sqlx::PgPool,
+    forum::{DispatchTarget, models::gateway::GatewayEvent},
+    common::*
+};
+
+#[sqlx::test]
+async fn authors_and_repliers_watch_threads(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (user_token, _) = register(&app, "user").await;
+    let (_, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let path = format!("/threads/{thread_id}/watch");
+
+    let (status, body) = call(&app, &token, get(&path)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["level"], "watching");
+
+    let (_, body) = call(&app, &user_token, get(&path)).await;
+    assert_eq!(body["level"], "normal");
+
+    send_message(&app, &user_token, &thread_id, "first reply").await;
+    let (_, body) = call(&app, &user_token, get(&path)).await;
+    assert_eq!(body["level"], "watching");
+
+    let (status, body) = call(&app, &user_token, put(&path).set_json(json!({"level": "muted"}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    send_message(&app, &user_token, &thread_id, "second reply").await;
+    let (_, body) = call(&app, &user_token, get(&path)).await;
+    assert_eq!(body["level"], "muted");
+}
+
+#[sqlx::test]
+async fn watched_messages_reach_only_watchers(pool: PgPool) {
+    let data = app_data(pool.clone());
+    let app = service(&data).await;
+    let (token, _) = register_admin(&app, &pool, "admin").await;
+    let (category_id, thread_id) = create_thread(&app, &token, json!({"type": "public"})).await;
+    let (category_watcher_token, category_watcher_id) = register(&app, "category_watcher").await;
+    let (thread_watcher_token, thread_watcher_id) = register(&app, "thread_watcher").await;
+    let (muted_token, _) = register(&app, "muted").await;
+    register(&app, "bystander").await;
+
+    for token in [&category_watcher_token, &muted_token] {
+        let (status, body) = call(&app, token, put(&format!("/categories/{category_id}/watch")).set_json(json!({"level": "watching"}))).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+    }
+    let (status, body) = call(&app, &muted_token, put(&format!("/threads/{thread_id}/watch")).set_json(json!({"level": "muted"}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let (status, body) = call(&app, &thread_watcher_token, put(&format!("/threads/{thread_id}/watch")).set_json(json!({"level": "watching"}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let mut events = data.channel.subscribe();
+    let message_id = send_message(&app, &token, &thread_id, "news").await;
+
+    let watchers = dispatched(&mut events).into_iter()
+        .filter_map(|(target, event)| match (target, event) {
+            (DispatchTarget::User(user_id), GatewayEvent::WatchedMessageCreate(message)) => {
+                assert_eq!(message.id.0.to_string(), message_id);
+                Some(user_id.0.to_string())
+            },
+            (_, GatewayEvent::WatchedMessageCreate(_)) => panic!("WatchedMessageCreate is dispatched to more than a user"),
+            _ => None
+        })
+        .collect::<HashSet<_>>();
+    assert_eq!(watchers, HashSet::from([category_watcher_id, thread_watcher_id]));
+}